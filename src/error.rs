@@ -0,0 +1,30 @@
+//! Error type for the fallible APIs.
+
+use std::fmt;
+
+/// Invalid configuration reported by the `try_*` variants of the minimizers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The Boltzmann constant `k` was zero, negative or NaN.
+    NonPositiveBoltzmann,
+    /// The temperature schedule did not yield any temperature.
+    EmptySchedule,
+    /// The energy of the starting state evaluated to NaN.
+    NanInitialEnergy,
+    /// The number of parallel chains was zero.
+    EmptyBatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::NonPositiveBoltzmann => "Boltzmann constant must be positive",
+            Self::EmptySchedule => "temperature schedule is empty",
+            Self::NanInitialEnergy => "energy of the starting state is NaN",
+            Self::EmptyBatch => "batch size must be positive",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,34 @@
+//! Host-side copies of ArrayFire arrays.
+//!
+//! [`af::Array`] lives on the compute device and cannot be serialized directly; a
+//! [`HostArray`] holds its shape and elements in main memory so that it can be stored (e.g. in
+//! a [`crate::seqsa::Checkpoint`] with the `serde` feature) and uploaded again later.
+
+use arrayfire::{self as af};
+
+/// Shape and column-major elements of an `Array<f32>`, copied to the host.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostArray {
+    /// Dimensions of the array.
+    pub dims: [u64; 4],
+    /// Elements in column-major order.
+    pub data: Vec<f32>,
+}
+
+impl From<&af::Array<f32>> for HostArray {
+    fn from(array: &af::Array<f32>) -> Self {
+        let mut data = vec![0.0f32; array.elements()];
+        array.host(&mut data);
+        Self {
+            dims: *array.dims().get(),
+            data,
+        }
+    }
+}
+
+impl From<&HostArray> for af::Array<f32> {
+    fn from(array: &HostArray) -> Self {
+        af::Array::new(&array.data, af::Dim4::new(&array.dims))
+    }
+}
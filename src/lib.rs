@@ -1,5 +1,7 @@
 //! A small library for simulated annealing using arrayfire.
 
+mod error;
+pub mod host;
 #[warn(clippy::pedantic)]
 pub mod lsops;
 pub mod parsa;
@@ -11,5 +13,9 @@ pub mod testfunctions;
 // Unit tests.
 mod unittests;
 
+pub use error::Error;
+
 // Re-export arrayfire.
 pub use arrayfire::{self as af};
+// Re-export tinyrand, whose `Rand` trait is accepted by the sequential minimizers.
+pub use tinyrand;
@@ -4,6 +4,9 @@
 //! used to sample random candidate solutions related to a given solution in Simulated Annealing.
 
 use arrayfire::{self as af};
+use tinyrand::{Probability, Rand};
+
+use crate::seqsa::uniform;
 
 /// Creates a perturbed version of an input vector by adding random Gaussian noise scaled by the given factor.
 /// Returns a new array with random noise added to the input.
@@ -15,10 +18,2084 @@ pub fn random_perturbation(x: &af::Array<f32>, scale: f32) -> af::Array<f32> {
     x + noise
 }
 
-// TODO: Implement Swap operator.
-// fn random_swap(x: &af::Array<u32>) -> af::Array<u32> {
-//     let n = x.dims()[1]; // How many sequences to randomly swap in parallel.
-//     let l = x.dims()[0]; // What is the length of each sequence.
+/// Like [`random_perturbation`], but draws the noise from `engine` instead of arrayfire's
+/// global generator, so that a neighbour function can be made reproducible on its own.
+#[must_use]
+pub fn random_perturbation_with(
+    x: &af::Array<f32>,
+    scale: f32,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    let noise = af::random_normal::<f32>(x.dims(), engine) * scale;
+    x + noise
+}
+
+/// Like [`random_perturbation`], with a separate standard deviation for every dimension, so
+/// that dimensions of different units or ranges get steps of matching size. `scales` is a
+/// column of one scale per row of `x`, shared by all of its columns.
+///
+/// # Panics
+///
+/// Panics if `scales` does not hold one element per row of `x`.
+#[must_use]
+pub fn random_perturbation_scaled(x: &af::Array<f32>, scales: &af::Array<f32>) -> af::Array<f32> {
+    random_perturbation_scaled_with(x, scales, &af::get_default_random_engine())
+}
+
+/// Like [`random_perturbation_scaled`], but draws the noise from `engine`.
+///
+/// # Panics
+///
+/// Panics if `scales` does not hold one element per row of `x`.
+#[must_use]
+pub fn random_perturbation_scaled_with(
+    x: &af::Array<f32>,
+    scales: &af::Array<f32>,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    x + per_dimension(&af::random_normal::<f32>(x.dims(), engine), scales)
+}
+
+/// Creates a perturbed version of an input vector by adding uniform noise from
+/// `[-scale, scale]` to every element. Unlike the Gaussian [`random_perturbation`], a step
+/// never exceeds `scale`, which keeps proposals within a known distance of the current state.
+#[must_use]
+pub fn random_uniform_perturbation(x: &af::Array<f32>, scale: f32) -> af::Array<f32> {
+    random_uniform_perturbation_with(x, scale, &af::get_default_random_engine())
+}
+
+/// Like [`random_uniform_perturbation`], but draws the noise from `engine`.
+#[must_use]
+pub fn random_uniform_perturbation_with(
+    x: &af::Array<f32>,
+    scale: f32,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    x + symmetric_uniform(x.dims(), engine) * scale
+}
+
+/// Like [`random_uniform_perturbation`], with a separate half-width for every dimension.
+/// `scales` is a column of one scale per row of `x`, shared by all of its columns.
+///
+/// # Panics
+///
+/// Panics if `scales` does not hold one element per row of `x`.
+#[must_use]
+pub fn random_uniform_perturbation_scaled(
+    x: &af::Array<f32>,
+    scales: &af::Array<f32>,
+) -> af::Array<f32> {
+    random_uniform_perturbation_scaled_with(x, scales, &af::get_default_random_engine())
+}
+
+/// Like [`random_uniform_perturbation_scaled`], but draws the noise from `engine`.
+///
+/// # Panics
+///
+/// Panics if `scales` does not hold one element per row of `x`.
+#[must_use]
+pub fn random_uniform_perturbation_scaled_with(
+    x: &af::Array<f32>,
+    scales: &af::Array<f32>,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    x + per_dimension(&symmetric_uniform(x.dims(), engine), scales)
+}
+
+/// Adds Gaussian noise to every column of `x` with standard deviation `small` with probability
+/// `probability`, and `large` otherwise. The occasional long jump is a cheap heavy-tailed
+/// proposal that helps chains escape local minima, while most steps stay local. Every column
+/// picks its own scale, and the whole batch is a single fused expression on the device.
+///
+/// # Panics
+///
+/// Panics if `probability` is not in `[0, 1]`.
+#[must_use]
+pub fn random_two_scale_perturbation(
+    x: &af::Array<f32>,
+    small: f32,
+    large: f32,
+    probability: f32,
+) -> af::Array<f32> {
+    random_two_scale_perturbation_with(
+        x,
+        small,
+        large,
+        probability,
+        &af::get_default_random_engine(),
+    )
+}
+
+/// Like [`random_two_scale_perturbation`], but draws the noise and the scales from `engine`.
+///
+/// # Panics
+///
+/// Panics if `probability` is not in `[0, 1]`.
+#[must_use]
+pub fn random_two_scale_perturbation_with(
+    x: &af::Array<f32>,
+    small: f32,
+    large: f32,
+    probability: f32,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    assert!(
+        (0.0..=1.0).contains(&probability),
+        "probability must be in [0, 1]"
+    );
+    let dims = x.dims();
+    let draws = af::dim4!(1, dims[1], dims[2], dims[3]);
+    let small_step = af::lt(
+        &af::random_uniform::<f32>(draws, engine),
+        &probability,
+        false,
+    );
+    let scales = small_step.cast::<f32>() * (small - large) + large;
+    x + af::mul(&af::random_normal::<f32>(dims, engine), &scales, true)
+}
+
+/// Appends to every column of `x` a row holding the logarithm of its step size, starting at
+/// `scale`, the state layout of [`random_self_adaptive_perturbation`]. Annealed states keep
+/// this row; [`unpack_step_sizes`] splits it off again, and [`without_step_sizes`] hides it
+/// from energy functions.
+///
+/// # Panics
+///
+/// Panics if `scale` is not positive.
+#[must_use]
+pub fn pack_step_sizes(x: &af::Array<f32>, scale: f32) -> af::Array<f32> {
+    assert!(scale > 0.0, "step size must be positive");
+    let dims = x.dims();
+    let row = af::constant(scale.ln(), af::dim4!(1, dims[1], dims[2], dims[3]));
+    af::join(0, x, &row)
+}
+
+/// Splits states laid out by [`pack_step_sizes`] into the solutions and the step size of
+/// every column.
+///
+/// # Panics
+///
+/// Panics if the columns of `x` have fewer than two rows.
+#[must_use]
+pub fn unpack_step_sizes(x: &af::Array<f32>) -> (af::Array<f32>, af::Array<f32>) {
+    let last = step_size_row(x);
+    (af::rows(x, 0, last - 1), af::exp(&af::row(x, last)))
+}
+
+/// Index of the step size row of states laid out by [`pack_step_sizes`].
+fn step_size_row(x: &af::Array<f32>) -> i64 {
+    let rows = x.dims()[0];
+    assert!(rows >= 2, "states must hold a solution and a step size row");
+    #[allow(clippy::cast_possible_wrap)]
+    let last = rows as i64 - 1;
+    last
+}
+
+/// Wrap `energy` so that it can be called on states laid out by [`pack_step_sizes`]: the step
+/// size row is dropped before every call.
+pub fn without_step_sizes<E>(mut energy: E) -> impl FnMut(&af::Array<f32>) -> af::Array<f32>
+where
+    E: FnMut(&af::Array<f32>) -> af::Array<f32>,
+{
+    move |x| energy(&af::rows(x, 0, step_size_row(x) - 1))
+}
+
+/// Evolution strategy style self-adaptive perturbation of states laid out by
+/// [`pack_step_sizes`]. Every column first multiplies its step size by
+/// `exp(learning_rate * N(0, 1))`, then takes a Gaussian step of that size, so step sizes are
+/// proposed, accepted and rejected along with the solutions they produced. Chains thereby tune
+/// their own scale as the temperature falls, without any acceptance bookkeeping. A learning
+/// rate of about `1 / sqrt(n)` for `n` variables is customary.
+///
+/// # Panics
+///
+/// Panics if the columns of `x` have fewer than two rows.
+#[must_use]
+pub fn random_self_adaptive_perturbation(x: &af::Array<f32>, learning_rate: f32) -> af::Array<f32> {
+    random_self_adaptive_perturbation_with(x, learning_rate, &af::get_default_random_engine())
+}
+
+/// Like [`random_self_adaptive_perturbation`], but draws the noise from `engine`.
+///
+/// # Panics
+///
+/// Panics if the columns of `x` have fewer than two rows.
+#[must_use]
+pub fn random_self_adaptive_perturbation_with(
+    x: &af::Array<f32>,
+    learning_rate: f32,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    let last = step_size_row(x);
+    let solution = af::rows(x, 0, last - 1);
+    let log_scale = random_perturbation_with(&af::row(x, last), learning_rate, engine);
+    let step = af::mul(
+        &af::random_normal::<f32>(solution.dims(), engine),
+        &af::exp(&log_scale),
+        true,
+    );
+    af::join(0, &(solution + step), &log_scale)
+}
+
+/// Adds Gaussian noise scaled by `scale` to every column of `x` and rescales the result back
+/// to the column's original Euclidean norm, so that proposals stay on a sphere: unit vectors
+/// remain unit vectors. Suits states that are directions, such as antenna pointings or
+/// normalized embeddings.
+#[must_use]
+pub fn random_sphere_perturbation(x: &af::Array<f32>, scale: f32) -> af::Array<f32> {
+    random_sphere_perturbation_with(x, scale, &af::get_default_random_engine())
+}
+
+/// Like [`random_sphere_perturbation`], but draws the noise from `engine`.
+#[must_use]
+pub fn random_sphere_perturbation_with(
+    x: &af::Array<f32>,
+    scale: f32,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    on_sphere_of(x, &random_perturbation_with(x, scale, engine))
+}
+
+/// Rescale every column of `y` to the Euclidean norm of the same column of `x`.
+fn on_sphere_of(x: &af::Array<f32>, y: &af::Array<f32>) -> af::Array<f32> {
+    let norm = |a: &af::Array<f32>| af::sqrt(&af::sum(&(a * a), 0));
+    af::mul(y, &(norm(x) / norm(y)), true)
+}
+
+/// Rotates every rotation matrix of `x` by a small random rotation, keeping proposals on the
+/// rotation group SO(n) without any re-orthogonalization. `x` holds `n × n` matrices stacked
+/// along its third dimension; a batch of [`crate::parsa`] states with `n²` rows per column can
+/// be viewed that way with [`af::moddims`].
+///
+/// Every matrix is multiplied from the left by a Givens rotation in a random coordinate plane,
+/// with an angle drawn from a Gaussian of standard deviation `scale` radians.
+///
+/// # Panics
+///
+/// Panics if the matrices of `x` are not square.
+#[must_use]
+pub fn random_rotation(x: &af::Array<f32>, scale: f32) -> af::Array<f32> {
+    random_rotation_with(x, scale, &af::get_default_random_engine())
+}
+
+/// Like [`random_rotation`], but draws the planes and angles from `engine`.
+///
+/// # Panics
+///
+/// Panics if the matrices of `x` are not square.
+#[must_use]
+pub fn random_rotation_with(
+    x: &af::Array<f32>,
+    scale: f32,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    let dims = x.dims();
+    let n = dims[0];
+    assert_eq!(dims[1], n, "rotation matrices must be square");
+    if n < 2 {
+        return x.clone();
+    }
+    let batch = af::dim4!(1, 1, dims[2], dims[3]);
+    let plane = af::dim4!(n, n, dims[2], dims[3]);
+
+    // Two distinct axes per matrix, the second a random non-zero offset after the first.
+    let first = random_positions(af::dim4!(n, 1, dims[2], dims[3]), engine);
+    #[allow(clippy::cast_precision_loss)]
+    let offsets =
+        af::floor(&(af::random_uniform::<f32>(batch, engine) * (n - 1) as f32)).cast::<u32>();
+    #[allow(clippy::cast_possible_truncation)]
+    let second = af::rem(&(&first + offsets + 1u32), &(n as u32), false);
+    let angle = af::random_normal::<f32>(batch, engine) * scale;
+    let cos = af::tile(&af::cos(&angle), af::dim4!(n, n));
+    let sin = af::tile(&af::sin(&angle), af::dim4!(n, n));
+
+    let rows = af::range::<u32>(plane, 0);
+    let columns = af::range::<u32>(plane, 1);
+    let at = |row: &af::Array<u32>, col: &af::Array<u32>| {
+        af::and(
+            &af::eq(&rows, row, true),
+            &af::eq(&columns, col, true),
+            false,
+        )
+    };
+    let identity = af::eq(&rows, &columns, false).cast::<f32>();
+    let diagonal = af::or(&at(&first, &first), &at(&second, &second), false);
+    let givens = af::select(&cos, &diagonal, &identity);
+    let givens = af::select(&-&sin, &at(&first, &second), &givens);
+    let givens = af::select(&sin, &at(&second, &first), &givens);
+    af::matmul(&givens, x, af::MatProp::NONE, af::MatProp::NONE)
+}
+
+/// Which coordinates of a column [`random_subset_perturbation`] moves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinates {
+    /// Exactly this many distinct coordinates, chosen uniformly; every coordinate if the
+    /// columns have fewer.
+    Count(u32),
+    /// Every coordinate independently with this probability.
+    Probability(f32),
+}
+
+/// Adds Gaussian noise scaled by `scale` to a random subset of the coordinates of every
+/// column of `x`, leaving the others untouched. In high dimensions, moving a few coordinates
+/// at a time is accepted far more often than perturbing the whole vector. The subsets are
+/// drawn as masks on the device, independently for every column of a [`crate::parsa`] batch.
+///
+/// # Panics
+///
+/// Panics if `coordinates` selects no coordinate, i.e. a count of 0 or a probability that is
+/// not in `(0, 1]`.
+#[must_use]
+pub fn random_subset_perturbation(
+    x: &af::Array<f32>,
+    scale: f32,
+    coordinates: Coordinates,
+) -> af::Array<f32> {
+    random_subset_perturbation_with(x, scale, coordinates, &af::get_default_random_engine())
+}
+
+/// Like [`random_subset_perturbation`], but draws the subsets and the noise from `engine`.
+///
+/// # Panics
+///
+/// Panics if `coordinates` selects no coordinate, i.e. a count of 0 or a probability that is
+/// not in `(0, 1]`.
+#[must_use]
+pub fn random_subset_perturbation_with(
+    x: &af::Array<f32>,
+    scale: f32,
+    coordinates: Coordinates,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    let mask = subset_mask(x.dims(), coordinates, engine);
+    let noise = af::random_normal::<f32>(x.dims(), engine) * scale;
+    x + noise * mask
+}
+
+/// Like [`random_subset_perturbation`], with a separate standard deviation for every
+/// dimension. `scales` is a column of one scale per row of `x`, shared by all of its columns.
+///
+/// # Panics
+///
+/// Panics if `coordinates` selects no coordinate, or if `scales` does not hold one element
+/// per row of `x`.
+#[must_use]
+pub fn random_subset_perturbation_scaled(
+    x: &af::Array<f32>,
+    scales: &af::Array<f32>,
+    coordinates: Coordinates,
+) -> af::Array<f32> {
+    random_subset_perturbation_scaled_with(x, scales, coordinates, &af::get_default_random_engine())
+}
+
+/// Like [`random_subset_perturbation_scaled`], but draws the subsets and the noise from
+/// `engine`.
+///
+/// # Panics
+///
+/// Panics if `coordinates` selects no coordinate, or if `scales` does not hold one element
+/// per row of `x`.
+#[must_use]
+pub fn random_subset_perturbation_scaled_with(
+    x: &af::Array<f32>,
+    scales: &af::Array<f32>,
+    coordinates: Coordinates,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    let mask = subset_mask(x.dims(), coordinates, engine);
+    let noise = per_dimension(&af::random_normal::<f32>(x.dims(), engine), scales);
+    x + noise * mask
+}
+
+/// Mask of shape `dims` holding `1` at the coordinates picked by `coordinates` in every
+/// column, and `0` elsewhere.
+fn subset_mask(
+    dims: af::Dim4,
+    coordinates: Coordinates,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    let draws = af::random_uniform::<f32>(dims, engine);
+    let mask = match coordinates {
+        Coordinates::Count(count) => {
+            assert!(count > 0, "at least one coordinate must be perturbed");
+            if u64::from(count) >= dims[0] {
+                return af::constant(1.0f32, dims);
+            }
+            // The `count` smallest draws of every column mark its chosen coordinates.
+            let threshold = af::row(&af::sort(&draws, 0, true), i64::from(count) - 1);
+            af::le(&draws, &threshold, true)
+        }
+        Coordinates::Probability(probability) => {
+            assert!(
+                probability > 0.0 && probability <= 1.0,
+                "coordinate probability must be in (0, 1]"
+            );
+            af::lt(&draws, &probability, false)
+        }
+    };
+    mask.cast::<f32>()
+}
+
+/// Scale every row of `noise` by the matching element of the column `scales`.
+fn per_dimension(noise: &af::Array<f32>, scales: &af::Array<f32>) -> af::Array<f32> {
+    assert_eq!(
+        scales.elements() as u64,
+        noise.dims()[0],
+        "expected one scale per dimension"
+    );
+    af::mul(noise, &af::flat(scales), true)
+}
+
+/// Uniform noise from `[-1, 1]` of shape `dims`.
+fn symmetric_uniform(dims: af::Dim4, engine: &af::RandomEngine) -> af::Array<f32> {
+    af::random_uniform::<f32>(dims, engine) * 2.0f32 - 1.0f32
+}
+
+/// Gaussian proposals correlated through a user-supplied covariance `Σ`: every column of a
+/// state gets noise drawn from `N(0, Σ)`, which suits objectives whose variables are strongly
+/// correlated. The Cholesky factor is computed once, when the operator is built, and applied
+/// to a whole batch of chains with a single matrix product.
+#[derive(Debug, Clone)]
+pub struct CorrelatedPerturbation {
+    factor: af::Array<f32>,
+}
+
+impl CorrelatedPerturbation {
+    /// Build the operator from a symmetric positive definite `d × d` covariance matrix, for
+    /// states with `d` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `covariance` is not square or not positive definite.
+    #[must_use]
+    pub fn new(covariance: &af::Array<f32>) -> Self {
+        let dims = covariance.dims();
+        assert!(
+            dims[0] == dims[1] && dims[2] == 1 && dims[3] == 1,
+            "covariance must be a square matrix"
+        );
+        let (factor, info) = af::cholesky(covariance, false);
+        assert!(info == 0, "covariance must be positive definite");
+        Self { factor }
+    }
+
+    /// Add correlated noise to every column of `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` does not have as many rows as the covariance.
+    #[must_use]
+    pub fn perturb(&self, x: &af::Array<f32>) -> af::Array<f32> {
+        self.perturb_with(x, &af::get_default_random_engine())
+    }
+
+    /// Like [`CorrelatedPerturbation::perturb`], but draws the noise from `engine`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` does not have as many rows as the covariance.
+    #[must_use]
+    pub fn perturb_with(&self, x: &af::Array<f32>, engine: &af::RandomEngine) -> af::Array<f32> {
+        let dims = x.dims();
+        let rows = self.factor.dims()[0];
+        assert_eq!(dims[0], rows, "expected one row per covariance dimension");
+        let columns = dims.elements() / rows;
+        let noise = af::random_normal::<f32>(af::dim4!(rows, columns), engine);
+        let correlated = af::matmul(&self.factor, &noise, af::MatProp::NONE, af::MatProp::NONE);
+        x + af::moddims(&correlated, dims)
+    }
+}
+
+/// How [`Bounds`] bring a coordinate that left its interval back inside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// Move it to the nearest end of the interval.
+    Clamp,
+    /// Mirror it at the ends of the interval, as often as needed.
+    Reflect,
+    /// Treat the interval as periodic, re-entering at the opposite end.
+    Wrap,
+}
+
+/// Box constraints `[lower, upper]` on every dimension of a state, enforced on proposals with
+/// a [`Boundary`] policy. Apply them to any neighbour function with [`bounded`], or to a whole
+/// parallel batch through a [`crate::parsa::Projection`].
+#[derive(Debug, Clone)]
+pub struct Bounds {
+    lower: af::Array<f32>,
+    upper: af::Array<f32>,
+    boundary: Boundary,
+}
+
+impl Bounds {
+    /// Bounds given by a column of lower and a column of upper limits, one per dimension.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lower` and `upper` differ in length, or if a lower limit exceeds its upper
+    /// limit.
+    #[must_use]
+    pub fn new(lower: &af::Array<f32>, upper: &af::Array<f32>, boundary: Boundary) -> Self {
+        assert_eq!(
+            lower.elements(),
+            upper.elements(),
+            "expected as many lower as upper limits"
+        );
+        let (lower, upper) = (af::flat(lower), af::flat(upper));
+        let (inverted, _) = af::any_true_all(&af::gt(&lower, &upper, false));
+        assert!(!inverted, "lower limits must not exceed upper limits");
+        Self {
+            lower,
+            upper,
+            boundary,
+        }
+    }
+
+    /// The same interval `[lower, upper]` on each of `dimensions` dimensions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `lower` exceeds `upper`.
+    #[must_use]
+    pub fn uniform(lower: f32, upper: f32, dimensions: u64, boundary: Boundary) -> Self {
+        Self::new(
+            &af::constant(lower, af::dim4!(dimensions)),
+            &af::constant(upper, af::dim4!(dimensions)),
+            boundary,
+        )
+    }
+
+    /// Bring every column of `x` inside the bounds.
+    #[must_use]
+    pub fn apply(&self, x: &af::Array<f32>) -> af::Array<f32> {
+        let width = &self.upper - &self.lower;
+        let offset = af::sub(x, &self.lower, true);
+        match self.boundary {
+            Boundary::Clamp => af::clamp(x, &self.lower, &self.upper, true),
+            Boundary::Wrap => af::add(&modulo(&offset, &width), &self.lower, true),
+            Boundary::Reflect => {
+                // Fold the offset into a period of twice the width, then mirror its second half.
+                let folded = af::sub(&modulo(&offset, &(&width * 2.0f32)), &width, true);
+                af::sub(&(&self.lower + &width), &af::abs(&folded), true)
+            }
+        }
+    }
+}
+
+/// Remainder of every row of `x` modulo the matching element of the column `period`, taken in
+/// `[0, period)` even for negative `x`.
+fn modulo(x: &af::Array<f32>, period: &af::Array<f32>) -> af::Array<f32> {
+    let turns = af::floor(&af::div(x, period, true));
+    x - af::mul(&turns, period, true)
+}
+
+/// Wrap `neighbour` so that every proposal it makes is brought inside `bounds`.
+pub fn bounded<F>(mut neighbour: F, bounds: Bounds) -> impl FnMut(&af::Array<f32>) -> af::Array<f32>
+where
+    F: FnMut(&af::Array<f32>) -> af::Array<f32>,
+{
+    move |x| bounds.apply(&neighbour(x))
+}
+
+/// Flips one random bit of every column of a bitstring array holding `0`s and `1`s.
+/// Each column is treated as an independent bitstring, so a whole batch of chains of
+/// [`crate::parsa::minimize_discrete`] gets its proposals in one call.
+#[must_use]
+pub fn random_bit_flip<T>(x: &af::Array<T>) -> af::Array<T>
+where
+    T: af::HasAfEnum + af::IntegralType + af::ImplicitPromote<T, Output = T>,
+{
+    random_bit_flip_with(x, &af::get_default_random_engine())
+}
+
+/// Like [`random_bit_flip`], but draws the flipped positions from `engine`.
+#[must_use]
+pub fn random_bit_flip_with<T>(x: &af::Array<T>, engine: &af::RandomEngine) -> af::Array<T>
+where
+    T: af::HasAfEnum + af::IntegralType + af::ImplicitPromote<T, Output = T>,
+{
+    let dims = x.dims();
+    let positions = random_positions(dims, engine);
+    let mask = af::eq(&af::range::<u32>(dims, 0), &positions, true);
+    x ^ &mask.cast::<T>()
+}
+
+/// Flips a random number of bits of every column of a bitstring array holding `0`s and `1`s,
+/// around `radius` of them on average: one random bit of each column is always flipped, and
+/// every other bit with probability `(radius - 1) / (length - 1)`. The Hamming distance of a
+/// proposal thus follows `1 + Binomial(length - 1, p)`. Large radii explore a binary landscape
+/// coarsely; shrinking the radius with [`cooled_radius`] refines the search as it cools.
+///
+/// # Panics
+///
+/// Panics if `radius` is less than 1.
+#[must_use]
+pub fn random_multi_bit_flip<T>(x: &af::Array<T>, radius: f32) -> af::Array<T>
+where
+    T: af::HasAfEnum + af::IntegralType + af::ImplicitPromote<T, Output = T>,
+{
+    random_multi_bit_flip_with(x, radius, &af::get_default_random_engine())
+}
+
+/// Like [`random_multi_bit_flip`], but draws the flipped positions from `engine`.
+///
+/// # Panics
+///
+/// Panics if `radius` is less than 1.
+#[must_use]
+pub fn random_multi_bit_flip_with<T>(
+    x: &af::Array<T>,
+    radius: f32,
+    engine: &af::RandomEngine,
+) -> af::Array<T>
+where
+    T: af::HasAfEnum + af::IntegralType + af::ImplicitPromote<T, Output = T>,
+{
+    assert!(radius >= 1.0, "Hamming radius must be at least 1");
+    let dims = x.dims();
+    let length = dims[0];
+    if length < 2 {
+        return random_bit_flip_with(x, engine);
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let probability = ((radius - 1.0) / (length - 1) as f32).min(1.0);
+    let positions = random_positions(dims, engine);
+    let mask = af::or(
+        &af::eq(&af::range::<u32>(dims, 0), &positions, true),
+        &af::lt(
+            &af::random_uniform::<f32>(dims, engine),
+            &probability,
+            false,
+        ),
+        false,
+    );
+    x ^ &mask.cast::<T>()
+}
+
+/// Hamming radius for [`random_multi_bit_flip`] that shrinks linearly with the temperature,
+/// from `radius` at `initial_temperature` down to a single bit as it approaches zero.
+#[must_use]
+pub fn cooled_radius(radius: f32, temperature: f32, initial_temperature: f32) -> f32 {
+    let fraction = (temperature / initial_temperature).clamp(0.0, 1.0);
+    1.0 + (radius - 1.0).max(0.0) * fraction
+}
+
+/// Fixed-point Gray code encoding of real variables in `[lower, upper]`, the classic binary
+/// representation of continuous problems in the genetic algorithm literature.
+///
+/// Each variable becomes `bits` bits, most significant first, so a column of `d` variables is
+/// encoded as a column of `d * bits` bits holding `0`s and `1`s. Adjacent grid points differ
+/// in a single bit, so flipping one bit can always make the smallest possible step. States can
+/// be annealed as bitstrings with [`crate::parsa::minimize_discrete`], decoding them in the
+/// energy function, or kept real with [`GrayCode::random_flip`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrayCode {
+    /// Bits per variable.
+    pub bits: u32,
+    /// Value encoded by all zeros.
+    pub lower: f32,
+    /// Value encoded by the largest code.
+    pub upper: f32,
+}
+
+impl GrayCode {
+    /// Encoding of `[lower, upper]` on a grid of `2^bits` points.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is not in `1..=24`, beyond which `f32` cannot tell grid points apart,
+    /// or if `lower` is not below `upper`.
+    #[must_use]
+    pub fn new(bits: u32, lower: f32, upper: f32) -> Self {
+        assert!((1..=24).contains(&bits), "Gray code needs 1 to 24 bits");
+        assert!(lower < upper, "lower bound must be below the upper bound");
+        Self { bits, lower, upper }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn levels(&self) -> f32 {
+        ((1u32 << self.bits) - 1) as f32
+    }
+
+    /// Encode every column of `x` as Gray code bits, rounding to the nearest grid point and
+    /// clamping to the range.
+    #[must_use]
+    pub fn encode(&self, x: &af::Array<f32>) -> af::Array<u8> {
+        let dims = x.dims();
+        let scaled = (x - self.lower) * (self.levels() / (self.upper - self.lower));
+        let binary = af::round(&af::clamp(
+            &af::flat(&scaled),
+            &0.0f32,
+            &self.levels(),
+            false,
+        ))
+        .cast::<u32>();
+        let gray = af::bitxor(&binary, &af::shiftr(&binary, &1u32, false), false);
+
+        // Bit `i` of a variable is its code shifted right by `bits - 1 - i`.
+        let shifts = (self.bits - 1) - af::range::<u32>(af::dim4!(u64::from(self.bits)), 0);
+        let codes = af::moddims(&gray, af::dim4!(1, dims.elements()));
+        let bits = af::bitand(&af::shiftr(&codes, &shifts, true), &1u32, false);
+        let mut encoded = *dims.get();
+        encoded[0] *= u64::from(self.bits);
+        af::moddims(&bits, af::Dim4::new(&encoded)).cast::<u8>()
+    }
+
+    /// Decode every column of Gray code bits back to real variables.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the columns is not a multiple of [`GrayCode::bits`].
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn decode(&self, genes: &af::Array<u8>) -> af::Array<f32> {
+        let dims = genes.dims();
+        let bits = u64::from(self.bits);
+        assert!(
+            dims[0] % bits == 0,
+            "columns must hold a whole number of encoded variables"
+        );
+        let variables = dims.elements() / bits;
+
+        // A binary bit is the parity of the Gray bits up to and including it.
+        let gray = af::moddims(&genes.cast::<u32>(), af::dim4!(bits, variables));
+        let binary = af::rem(&af::accum(&gray, 0), &2u32, false).cast::<f32>();
+        let weights: Vec<f32> = (0..self.bits).rev().map(|i| (1u32 << i) as f32).collect();
+        let weights = af::Array::new(&weights, af::dim4!(bits));
+        let codes = af::sum(&af::mul(&binary, &weights, true), 0);
+
+        let mut decoded = *dims.get();
+        decoded[0] /= bits;
+        let values = codes * ((self.upper - self.lower) / self.levels()) + self.lower;
+        af::moddims(&values, af::Dim4::new(&decoded))
+    }
+
+    /// Flip one random bit of the Gray code of every column of `x`, a batch of real states,
+    /// and decode the result. States are snapped to the grid of the encoding.
+    #[must_use]
+    pub fn random_flip(&self, x: &af::Array<f32>) -> af::Array<f32> {
+        self.random_flip_with(x, &af::get_default_random_engine())
+    }
+
+    /// Like [`GrayCode::random_flip`], but draws the flipped bits from `engine`.
+    #[must_use]
+    pub fn random_flip_with(
+        &self,
+        x: &af::Array<f32>,
+        engine: &af::RandomEngine,
+    ) -> af::Array<f32> {
+        self.decode(&random_bit_flip_with(&self.encode(x), engine))
+    }
+}
+
+/// Moves one random coordinate of every column of an integer state by a random step, up or
+/// down with equal odds, for discrete numeric problems such as resource allocation counts.
+///
+/// Step lengths follow a geometric distribution starting at 1 with mean `reach`, so `reach = 1`
+/// always takes a unit step. With `bounds`, every coordinate is clamped to the inclusive
+/// interval `(lower, upper)` afterwards.
+///
+/// # Panics
+///
+/// Panics if `reach` is less than 1.
+#[must_use]
+pub fn random_lattice_step(
+    x: &af::Array<i32>,
+    reach: f32,
+    bounds: Option<(i32, i32)>,
+) -> af::Array<i32> {
+    random_lattice_step_with(x, reach, bounds, &af::get_default_random_engine())
+}
+
+/// Like [`random_lattice_step`], but draws the coordinates and steps from `engine`.
+///
+/// # Panics
+///
+/// Panics if `reach` is less than 1.
+#[must_use]
+pub fn random_lattice_step_with(
+    x: &af::Array<i32>,
+    reach: f32,
+    bounds: Option<(i32, i32)>,
+    engine: &af::RandomEngine,
+) -> af::Array<i32> {
+    assert!(reach >= 1.0, "mean step length must be at least 1");
+    let dims = x.dims();
+    let draws = af::dim4!(1, dims[1], dims[2], dims[3]);
+
+    // Inverse transform sampling of the number of failures before a success with probability
+    // `1 / reach`; `1 - u` lies in (0, 1], which keeps the logarithm finite.
+    let length = if reach > 1.0 {
+        let failures = af::log(&(1.0f32 - af::random_uniform::<f32>(draws, engine)))
+            / (1.0 - 1.0 / reach).ln();
+        af::floor(&failures) + 1.0f32
+    } else {
+        af::constant(1.0f32, draws)
+    };
+    let downwards = af::lt(&af::random_uniform::<f32>(draws, engine), &0.5f32, false);
+    let step = af::select(&-&length, &downwards, &length);
+
+    let mask = af::eq(
+        &af::range::<u32>(dims, 0),
+        &random_positions(dims, engine),
+        true,
+    );
+    let moved = x + af::mul(&mask.cast::<f32>(), &step, true).cast::<i32>();
+    match bounds {
+        Some((lower, upper)) => af::clamp(&moved, &lower, &upper, false),
+        None => moved,
+    }
+}
+
+/// Reassigns one random position of every column of `x` to a different category, drawn
+/// uniformly among the other `categories - 1`. Positions hold category indices in
+/// `0..categories`, as in graph colouring, clustering assignments or timetabling; every column
+/// is an independent assignment of a [`crate::parsa`] batch.
+///
+/// # Panics
+///
+/// Panics if `categories` is less than 2.
+#[must_use]
+pub fn random_reassignment<T: af::HasAfEnum>(x: &af::Array<T>, categories: u32) -> af::Array<T> {
+    random_reassignment_with(x, categories, &af::get_default_random_engine())
+}
+
+/// Like [`random_reassignment`], but draws the positions and categories from `engine`.
+///
+/// # Panics
+///
+/// Panics if `categories` is less than 2.
+#[must_use]
+pub fn random_reassignment_with<T: af::HasAfEnum>(
+    x: &af::Array<T>,
+    categories: u32,
+    engine: &af::RandomEngine,
+) -> af::Array<T> {
+    assert!(
+        categories >= 2,
+        "reassignment needs at least two categories"
+    );
+    let dims = x.dims();
+    let length = dims[0];
+    let columns = dims.elements() / length;
+    let matrix = af::dim4!(length, columns);
+
+    // Adding an offset in [1, categories - 1] modulo `categories` never keeps the category.
+    let offsets = af::moddims(
+        &random_offsets(columns, u64::from(categories - 1), engine),
+        af::dim4!(1, columns),
+    );
+    let current = af::moddims(x, matrix).cast::<u32>();
+    let reassigned = af::rem(&af::add(&current, &offsets, true), &categories, false);
+    let mask = af::eq(
+        &af::range::<u32>(matrix, 0),
+        &random_positions(matrix, engine),
+        true,
+    );
+    af::moddims(&af::select(&reassigned, &mask, &current), dims).cast::<T>()
+}
+
+/// Partitioning move for every column of `x`, a batch of assignments of elements to `groups`
+/// groups as in graph partitioning or load balancing. Each column either relocates a random
+/// element to another group, or swaps the groups of two elements in different groups, with
+/// even odds.
+///
+/// Relocations change group sizes, so with `slack = 0` only swaps are made and the sizes of the
+/// starting partition are kept exactly. Otherwise a relocation is only made when the largest
+/// group of the result is at most `slack` elements larger than the smallest one, and the column
+/// swaps instead.
+/// Columns whose elements all share a group are left as is by swaps.
+///
+/// # Panics
+///
+/// Panics if `groups` is less than 2.
+#[must_use]
+pub fn random_group_exchange<T: af::HasAfEnum>(
+    x: &af::Array<T>,
+    groups: u32,
+    slack: u32,
+) -> af::Array<T> {
+    random_group_exchange_with(x, groups, slack, &af::get_default_random_engine())
+}
+
+/// Like [`random_group_exchange`], but draws the moves from `engine`.
+///
+/// # Panics
+///
+/// Panics if `groups` is less than 2.
+#[must_use]
+pub fn random_group_exchange_with<T: af::HasAfEnum>(
+    x: &af::Array<T>,
+    groups: u32,
+    slack: u32,
+    engine: &af::RandomEngine,
+) -> af::Array<T> {
+    assert!(groups >= 2, "group exchange needs at least two groups");
+    let dims = x.dims();
+    let length = dims[0];
+    let columns = dims.elements() / length;
+    let matrix = af::dim4!(length, columns);
+    let current = af::moddims(x, matrix).cast::<u32>();
+
+    // Swap a random element with a random one from another group: keys of elements in the
+    // same group are pushed above every other key before taking the smallest.
+    let first = random_positions(matrix, engine);
+    let group = gather_rows(&current, &af::tile(&first, af::dim4!(length)));
+    let keys = af::random_uniform::<f32>(matrix, engine);
+    let keys = af::select(&(&keys + 2.0f32), &af::eq(&current, &group, false), &keys);
+    let (_, second) = af::imin(&keys, 0);
+    let rows = af::range::<u32>(matrix, 0);
+    let sources = af::select(
+        &af::tile(&second, af::dim4!(length)),
+        &af::eq(&rows, &first, true),
+        &af::select(
+            &af::tile(&first, af::dim4!(length)),
+            &af::eq(&rows, &second, true),
+            &rows,
+        ),
+    );
+    let swapped = gather_rows(&current, &sources);
+    // Even a relocation that balances the groups would change their sizes.
+    if slack == 0 {
+        return af::moddims(&swapped, dims).cast::<T>();
+    }
+
+    let relocated = random_reassignment_with(&current, groups, engine);
+    let labels = af::range::<u32>(af::dim4!(1, u64::from(groups)), 1);
+    let sizes = af::count(
+        &af::eq(
+            &af::moddims(&relocated, af::dim4!(length, 1, columns)),
+            &labels,
+            true,
+        ),
+        0,
+    );
+    let spread = af::moddims(
+        &(af::max(&sizes, 1) - af::min(&sizes, 1)),
+        af::dim4!(1, columns),
+    );
+    let relocate = af::and(
+        &af::le(&spread, &slack, false),
+        &af::lt(
+            &af::random_uniform::<f32>(af::dim4!(1, columns), engine),
+            &0.5f32,
+            false,
+        ),
+        false,
+    );
+    let moved = af::select(
+        &relocated,
+        &af::tile(&relocate, af::dim4!(length)),
+        &swapped,
+    );
+    af::moddims(&moved, dims).cast::<T>()
+}
+
+/// Swaps two distinct random positions of every column of `x`, such as a batch of
+/// permutations for the travelling salesman or quadratic assignment problems. Each column is
+/// swapped independently, entirely on the device; columns of a single element are left as is.
+#[must_use]
+pub fn random_swap<T: af::HasAfEnum>(x: &af::Array<T>) -> af::Array<T> {
+    random_swap_with(x, &af::get_default_random_engine())
+}
+
+/// Like [`random_swap`], but draws the swapped positions from `engine`.
+#[must_use]
+pub fn random_swap_with<T: af::HasAfEnum>(
+    x: &af::Array<T>,
+    engine: &af::RandomEngine,
+) -> af::Array<T> {
+    let dims = x.dims();
+    let length = dims[0];
+    if length < 2 {
+        return x.clone();
+    }
+    let columns = dims.elements() / length;
+    let matrix = af::dim4!(length, columns);
+
+    // The second position lies a random non-zero offset after the first, wrapping around.
+    let first = random_positions(matrix, engine);
+    #[allow(clippy::cast_precision_loss)]
+    let offsets = af::floor(
+        &(af::random_uniform::<f32>(af::dim4!(1, columns), engine) * (length - 1) as f32),
+    )
+    .cast::<u32>()
+        + 1u32;
+    #[allow(clippy::cast_possible_truncation)]
+    let length = length as u32;
+    let second = af::rem(&(&first + offsets), &length, false);
+
+    let rows = af::range::<u32>(matrix, 0);
+    let first = af::tile(&first, af::dim4!(u64::from(length)));
+    let second = af::tile(&second, af::dim4!(u64::from(length)));
+    let sources = af::select(
+        &second,
+        &af::eq(&rows, &first, false),
+        &af::select(&first, &af::eq(&rows, &second, false), &rows),
+    );
+    gather_rows(x, &sources)
+}
+
+/// Swaps a random position of every column of `x` with the one right after it. Moves of this
+/// kind only reorder neighbouring elements, as suits schedules where distant reorderings are
+/// rarely meaningful; columns of a single element are left as is. See
+/// [`adjacent_swap`] for sequences held on the host.
+#[must_use]
+pub fn random_adjacent_swap<T: af::HasAfEnum>(x: &af::Array<T>) -> af::Array<T> {
+    random_adjacent_swap_with(x, &af::get_default_random_engine())
+}
+
+/// Like [`random_adjacent_swap`], but draws the swapped positions from `engine`.
+#[must_use]
+pub fn random_adjacent_swap_with<T: af::HasAfEnum>(
+    x: &af::Array<T>,
+    engine: &af::RandomEngine,
+) -> af::Array<T> {
+    let dims = x.dims();
+    let length = dims[0];
+    if length < 2 {
+        return x.clone();
+    }
+    let columns = dims.elements() / length;
+    let matrix = af::dim4!(length, columns);
+
+    let first = af::tile(
+        &random_positions(af::dim4!(length - 1, columns), engine),
+        af::dim4!(length),
+    );
+    let second = &first + 1u32;
+    let rows = af::range::<u32>(matrix, 0);
+    let sources = af::select(
+        &second,
+        &af::eq(&rows, &first, false),
+        &af::select(&first, &af::eq(&rows, &second, false), &rows),
+    );
+    gather_rows(x, &sources)
+}
+
+/// Host counterpart of [`random_adjacent_swap`] for a single sequence, e.g. the neighbour
+/// function of a [`crate::seqsa`] run over `Vec` states.
+#[must_use]
+pub fn adjacent_swap<T: Clone, R: Rand + ?Sized>(x: &[T], rng: &mut R) -> Vec<T> {
+    let mut swapped = x.to_vec();
+    if x.len() > 1 {
+        let i = rng.next_lim_usize(x.len() - 1);
+        swapped.swap(i, i + 1);
+    }
+    swapped
+}
+
+/// Rotates a random window of every column of `x` by one position, left or right with even
+/// odds: the element at one end of the window moves to the other end and the rest shift up
+/// by one. A gentle reordering move for schedules and routes, which keeps the relative order of
+/// all but one element. Windows of two elements are adjacent swaps. Columns are processed
+/// independently, on the device; columns of a single element are left as is.
+#[must_use]
+pub fn random_cyclic_shift<T: af::HasAfEnum>(x: &af::Array<T>) -> af::Array<T> {
+    random_cyclic_shift_with(x, &af::get_default_random_engine())
+}
+
+/// Like [`random_cyclic_shift`], but draws the windows from `engine`.
+#[must_use]
+pub fn random_cyclic_shift_with<T: af::HasAfEnum>(
+    x: &af::Array<T>,
+    engine: &af::RandomEngine,
+) -> af::Array<T> {
+    let dims = x.dims();
+    let length = dims[0];
+    if length < 2 {
+        return x.clone();
+    }
+    let columns = dims.elements() / length;
+    let matrix = af::dim4!(length, columns);
 
-//     todo!("Implement swapping..")
-// }
+    // The window spans two distinct positions, the second a non-zero offset after the first.
+    let first = random_positions(matrix, engine);
+    let offsets = af::moddims(
+        &random_offsets(columns, length - 1, engine),
+        af::dim4!(1, columns),
+    );
+    #[allow(clippy::cast_possible_truncation)]
+    let second = af::rem(&(&first + offsets), &(length as u32), false);
+    let start = af::tile(&af::minof(&first, &second, false), af::dim4!(length));
+    let end = af::tile(&af::maxof(&first, &second, false), af::dim4!(length));
+
+    let rows = af::range::<u32>(matrix, 0);
+    let inside = af::and(
+        &af::ge(&rows, &start, false),
+        &af::le(&rows, &end, false),
+        false,
+    );
+    let left = af::select(&start, &af::eq(&rows, &end, false), &(&rows + 1u32));
+    let right = af::select(&end, &af::eq(&rows, &start, false), &(&rows - 1u32));
+    let leftwards = af::tile(
+        &af::lt(
+            &af::random_uniform::<f32>(af::dim4!(1, columns), engine),
+            &0.5f32,
+            false,
+        ),
+        af::dim4!(length),
+    );
+    let shifted = af::select(&left, &leftwards, &right);
+    gather_rows(x, &af::select(&shifted, &inside, &rows))
+}
+
+/// Host counterpart of [`random_cyclic_shift`] for a single sequence.
+#[must_use]
+pub fn cyclic_shift<T: Clone, R: Rand + ?Sized>(x: &[T], rng: &mut R) -> Vec<T> {
+    let mut shifted = x.to_vec();
+    if x.len() > 1 {
+        let first = rng.next_lim_usize(x.len());
+        let second = (first + 1 + rng.next_lim_usize(x.len() - 1)) % x.len();
+        let window = &mut shifted[first.min(second)..=first.max(second)];
+        if rng.next_bool(Probability::new(0.5)) {
+            window.rotate_left(1);
+        } else {
+            window.rotate_right(1);
+        }
+    }
+    shifted
+}
+
+/// Applies a random k-opt move to every column of `x`, a batch of sequences such as
+/// travelling salesman tours: `k` edges are removed and the `k - 1` segments between them are
+/// put back in reverse order, each one reversed with even odds. With `k = 2` this is the 2-opt
+/// segment reversal; see [`random_three_opt`] for the usual choice. Columns are processed
+/// independently, on the device.
+///
+/// # Panics
+///
+/// Panics if `k` is less than 2, or not less than the length of the columns.
+#[must_use]
+pub fn random_k_opt<T: af::HasAfEnum>(x: &af::Array<T>, k: usize) -> af::Array<T> {
+    random_k_opt_with(x, k, &af::get_default_random_engine())
+}
+
+/// [`random_k_opt`] with `k = 3`, whose neighbourhood improves markedly on 2-opt alone.
+///
+/// # Panics
+///
+/// Panics if the columns of `x` have fewer than four elements.
+#[must_use]
+pub fn random_three_opt<T: af::HasAfEnum>(x: &af::Array<T>) -> af::Array<T> {
+    random_k_opt(x, 3)
+}
+
+/// Like [`random_k_opt`], but draws the removed edges and reversals from `engine`.
+///
+/// # Panics
+///
+/// Panics if `k` is less than 2, or not less than the length of the columns.
+#[must_use]
+#[allow(clippy::cast_possible_wrap)]
+pub fn random_k_opt_with<T: af::HasAfEnum>(
+    x: &af::Array<T>,
+    k: usize,
+    engine: &af::RandomEngine,
+) -> af::Array<T> {
+    let dims = x.dims();
+    let length = dims[0];
+    assert!(
+        k >= 2 && (k as u64) < length,
+        "k-opt needs 2 <= k < the sequence length"
+    );
+    let columns = dims.elements() / length;
+    let matrix = af::dim4!(length, columns);
+
+    // `k` distinct cuts in [1, length - 1] per column, in increasing order; a cut at `c`
+    // removes the edge between positions `c - 1` and `c`.
+    let draws = af::random_uniform::<f32>(af::dim4!(length - 1, columns), engine);
+    let (_, order) = af::sort_index(&draws, 0, true);
+    let cuts = af::sort(&(af::rows(&order, 0, k as i64 - 1) + 1u32), 0, true);
+    let cut = |j: usize| af::tile(&af::row(&cuts, j as i64), af::dim4!(length));
+    let reversed = if k == 2 {
+        af::constant(true, af::dim4!(1, columns))
+    } else {
+        af::lt(
+            &af::random_uniform::<f32>(af::dim4!(k as u64 - 1, columns), engine),
+            &0.5f32,
+            false,
+        )
+    };
+
+    // Slot `m` between the outer cuts receives inner segment `k - m`, which spans
+    // `[c[k - m - 1], c[k - m])` and is placed from `c[0] + c[k - 1] - c[k - m]` on.
+    let rows = af::range::<u32>(matrix, 0);
+    let (first, last) = (cut(0), cut(k - 1));
+    let mut sources = rows.clone();
+    for m in 1..k {
+        let (begin, end) = (cut(k - m - 1), cut(k - m));
+        let placed = &first + &last - &end;
+        let inside = af::and(
+            &af::ge(&rows, &placed, false),
+            &af::lt(&rows, &(&placed + &end - &begin), false),
+            false,
+        );
+        let offset = &rows - &placed;
+        let flipped = af::tile(&af::row(&reversed, m as i64 - 1), af::dim4!(length));
+        let source = af::select(&(&end - 1u32 - &offset), &flipped, &(&begin + &offset));
+        sources = af::select(&source, &inside, &sources);
+    }
+    gather_rows(x, &sources)
+}
+
+/// Cuts a random block out of every column of `x`, a batch of sequences, and splices it in at
+/// another random position: the displacement move of scheduling and routing problems, of
+/// which or-opt is the special case of short blocks. The order within the block and of the
+/// remaining elements is kept. Columns are processed independently, on the device; columns of
+/// a single element are left as is.
+#[must_use]
+pub fn random_block_move<T: af::HasAfEnum>(x: &af::Array<T>) -> af::Array<T> {
+    random_block_move_with(x, &af::get_default_random_engine())
+}
+
+/// Like [`random_block_move`], but draws the blocks from `engine`.
+#[must_use]
+pub fn random_block_move_with<T: af::HasAfEnum>(
+    x: &af::Array<T>,
+    engine: &af::RandomEngine,
+) -> af::Array<T> {
+    let dims = x.dims();
+    let length = dims[0];
+    if length < 2 {
+        return x.clone();
+    }
+    let columns = dims.elements() / length;
+    let matrix = af::dim4!(length, columns);
+
+    // Three distinct cuts in [0, length] per column, in increasing order. Moving the block
+    // between the first two cuts to the third is exchanging it with the block that follows.
+    let draws = af::random_uniform::<f32>(af::dim4!(length + 1, columns), engine);
+    let (_, order) = af::sort_index(&draws, 0, true);
+    let cuts = af::sort(&af::rows(&order, 0, 2), 0, true);
+    let cut = |j: i64| af::tile(&af::row(&cuts, j), af::dim4!(length));
+    let (start, middle, end) = (cut(0), cut(1), cut(2));
+
+    let rows = af::range::<u32>(matrix, 0);
+    let split = &start + &end - &middle;
+    let front = af::and(
+        &af::ge(&rows, &start, false),
+        &af::lt(&rows, &split, false),
+        false,
+    );
+    let back = af::and(
+        &af::ge(&rows, &split, false),
+        &af::lt(&rows, &end, false),
+        false,
+    );
+    let sources = af::select(
+        &(&rows + &middle - &start),
+        &front,
+        &af::select(&(&rows + &middle - &end), &back, &rows),
+    );
+    gather_rows(x, &sources)
+}
+
+/// Gather, for every column of `x` viewed as a matrix with the shape of `sources`, the rows
+/// listed in the matching column of `sources`.
+fn gather_rows<T: af::HasAfEnum>(x: &af::Array<T>, sources: &af::Array<u32>) -> af::Array<T> {
+    let matrix = sources.dims();
+    #[allow(clippy::cast_possible_truncation)]
+    let length = matrix[0] as u32;
+    let indices = sources + af::range::<u32>(matrix, 1) * length;
+    af::moddims(&af::lookup(&af::flat(x), &af::flat(&indices), 0), x.dims())
+}
+
+/// Draw one uniformly random row index per column of an array of shape `dims`.
+fn random_positions(dims: af::Dim4, engine: &af::RandomEngine) -> af::Array<u32> {
+    let draws = af::dim4!(1, dims[1], dims[2], dims[3]);
+    #[allow(clippy::cast_precision_loss)]
+    let rows = dims[0] as f32;
+    af::floor(&(af::random_uniform::<f32>(draws, engine) * rows)).cast::<u32>()
+}
+
+/// Moves every column of `x` along the difference of two other randomly chosen columns,
+/// `x_i + weight * (x_a - x_b)`, as in differential evolution.
+///
+/// Each column is a chain of a [`crate::parsa`] batch, so proposals follow the shape of the
+/// population: long steps while the chains are spread out, short ones once they gather. The
+/// differences are gathered entirely on the device. Adding a little noise, e.g. through
+/// [`random_perturbation`], keeps collapsed populations moving.
+///
+/// # Panics
+///
+/// Panics if `x` has fewer than three columns.
+#[must_use]
+pub fn differential_mutation(x: &af::Array<f32>, weight: f32) -> af::Array<f32> {
+    differential_mutation_with(x, weight, &af::get_default_random_engine())
+}
+
+/// Like [`differential_mutation`], but draws the partner columns from `engine`.
+///
+/// # Panics
+///
+/// Panics if `x` has fewer than three columns.
+#[must_use]
+pub fn differential_mutation_with(
+    x: &af::Array<f32>,
+    weight: f32,
+    engine: &af::RandomEngine,
+) -> af::Array<f32> {
+    let columns = x.dims()[1];
+    assert!(
+        columns >= 3,
+        "Differential mutation needs at least three columns"
+    );
+
+    // Distinct offsets `first` in [1, n - 1] and `second` in [1, n - 1] \ {first} pick two
+    // partners other than the column itself.
+    let first = random_offsets(columns, columns - 1, engine);
+    let second = random_offsets(columns, columns - 2, engine);
+    let second = &second + af::ge(&second, &first, false).cast::<u32>();
+
+    let own = af::range::<u32>(af::dim4!(columns), 0);
+    #[allow(clippy::cast_possible_truncation)]
+    let columns = columns as u32;
+    let a = af::lookup(x, &af::rem(&(&own + first), &columns, false), 1);
+    let b = af::lookup(x, &af::rem(&(&own + second), &columns, false), 1);
+    x + weight * (a - b)
+}
+
+/// Draw `count` uniformly random offsets in `[1, range]`.
+fn random_offsets(count: u64, range: u64, engine: &af::RandomEngine) -> af::Array<u32> {
+    #[allow(clippy::cast_precision_loss)]
+    let range = range as f32;
+    let draws = af::random_uniform::<f32>(af::dim4!(count), engine);
+    af::floor(&(draws * range)).cast::<u32>() + 1u32
+}
+
+/// Several neighbour operators, one of which is picked at random for every proposal with
+/// probability proportional to its weight. Most problems need more than one move type, e.g.
+/// swaps and segment reversals for a tour, or small and large steps for a vector.
+///
+/// [`Mixture::propose`] serves sequential chains. For a [`crate::parsa`] batch,
+/// [`Mixture::propose_batch`] picks an operator per chain instead: every operator is applied
+/// to the whole batch and each column takes the proposal of its own choice, so a batch costs
+/// one call of every operator.
+pub struct Mixture<'a, T> {
+    operators: Vec<(f32, Operator<'a, T>)>,
+}
+
+type Operator<'a, T> = Box<dyn FnMut(&T) -> T + 'a>;
+
+impl<'a, T> Mixture<'a, T> {
+    /// An empty mixture; add operators with [`Mixture::with`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            operators: Vec::new(),
+        }
+    }
+
+    /// Add `operator`, picked with probability proportional to `weight`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is not positive and finite.
+    #[must_use]
+    pub fn with(mut self, weight: f32, operator: impl FnMut(&T) -> T + 'a) -> Self {
+        assert!(
+            weight > 0.0 && weight.is_finite(),
+            "operator weights must be positive and finite"
+        );
+        self.operators.push((weight, Box::new(operator)));
+        self
+    }
+
+    /// Weights of the operators, in the order they were added.
+    pub fn weights(&self) -> impl Iterator<Item = f32> + '_ {
+        self.operators.iter().map(|(weight, _)| *weight)
+    }
+
+    /// Sample a neighbour of `x` with an operator drawn through `rng`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mixture holds no operator.
+    pub fn propose<R: Rand + ?Sized>(&mut self, x: &T, rng: &mut R) -> T {
+        let chosen = self.pick(rng);
+        (self.operators[chosen].1)(x)
+    }
+
+    /// Index of an operator drawn through `rng`.
+    fn pick<R: Rand + ?Sized>(&self, rng: &mut R) -> usize {
+        assert!(!self.operators.is_empty(), "mixture has no operators");
+        let mut threshold = uniform(rng) * f64::from(self.total_weight());
+        for (index, weight) in self.weights().enumerate() {
+            threshold -= f64::from(weight);
+            if threshold < 0.0 {
+                return index;
+            }
+        }
+        // Rounding may leave a sliver of the total weight to the last operator.
+        self.operators.len() - 1
+    }
+
+    fn total_weight(&self) -> f32 {
+        self.weights().sum()
+    }
+}
+
+impl<S: af::HasAfEnum> Mixture<'_, af::Array<S>> {
+    /// Sample a neighbour of every column of `x`, drawing an operator for each column
+    /// separately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mixture holds no operator.
+    pub fn propose_batch(&mut self, x: &af::Array<S>) -> af::Array<S> {
+        self.propose_batch_with(x, &af::get_default_random_engine())
+    }
+
+    /// Like [`Mixture::propose_batch`], but draws the operators from `engine`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mixture holds no operator.
+    pub fn propose_batch_with(
+        &mut self,
+        x: &af::Array<S>,
+        engine: &af::RandomEngine,
+    ) -> af::Array<S> {
+        self.propose_batch_choices(x, engine).0
+    }
+
+    /// Proposals for every column of `x`, along with the index of the operator each column
+    /// drew, shaped `1 × columns`.
+    fn propose_batch_choices(
+        &mut self,
+        x: &af::Array<S>,
+        engine: &af::RandomEngine,
+    ) -> (af::Array<S>, af::Array<u32>) {
+        assert!(!self.operators.is_empty(), "mixture has no operators");
+        let dims = x.dims();
+        let draws = af::random_uniform::<f32>(af::dim4!(1, dims[1], dims[2], dims[3]), engine)
+            * self.total_weight();
+
+        // A column draws the number of cumulative weights its draw reaches, excluding the
+        // total, which rounding could otherwise let it reach.
+        let mut choices = af::constant(0u32, draws.dims());
+        let mut cumulative = 0.0;
+        for weight in self.weights().take(self.operators.len() - 1) {
+            cumulative += weight;
+            choices += af::ge(&draws, &cumulative, false).cast::<u32>();
+        }
+
+        let mut operators = self.operators.iter_mut();
+        let (_, first) = operators.next().expect("checked above");
+        let mut proposals = first(x);
+        for (index, (_, operator)) in (1u32..).zip(operators) {
+            let chosen = af::tile(&af::eq(&choices, &index, false), af::dim4!(dims[0]));
+            proposals = af::select(&operator(x), &chosen, &proposals);
+        }
+        (proposals, choices)
+    }
+}
+
+/// A [`Mixture`] that learns which operators pay off during the run and picks them more
+/// often, through probability matching.
+///
+/// Every operator keeps a quality: an exponential moving average of the energy decrease its
+/// proposals achieved, reported through [`AdaptiveMixture::reward`] (or
+/// [`AdaptiveMixture::reward_batch`] for a batch). Operators are then picked with probability
+/// `p_min + (1 - K p_min) q_i / Σ q`, so productive operators dominate while the `K`
+/// operators each keep at least `p_min` and can recover when the landscape changes.
+pub struct AdaptiveMixture<'a, T> {
+    mixture: Mixture<'a, T>,
+    quality: Vec<f32>,
+    learning_rate: f32,
+    min_probability: f32,
+    last: Option<usize>,
+    last_batch: Option<af::Array<u32>>,
+}
+
+impl<'a, T> AdaptiveMixture<'a, T> {
+    /// Learn over the operators of `mixture`, starting from their weights.
+    ///
+    /// `learning_rate` in `(0, 1]` sets how quickly qualities follow new rewards, and
+    /// `min_probability` the chance every operator keeps; it is capped at an even split.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mixture` holds no operator, if `learning_rate` is not in `(0, 1]`, or if
+    /// `min_probability` is negative.
+    #[must_use]
+    pub fn new(mixture: Mixture<'a, T>, learning_rate: f32, min_probability: f32) -> Self {
+        assert!(!mixture.operators.is_empty(), "mixture has no operators");
+        assert!(
+            learning_rate > 0.0 && learning_rate <= 1.0,
+            "learning rate must be in (0, 1]"
+        );
+        assert!(
+            min_probability >= 0.0,
+            "minimum probability must not be negative"
+        );
+        #[allow(clippy::cast_precision_loss)]
+        let even = 1.0 / mixture.operators.len() as f32;
+        let total = mixture.total_weight();
+        Self {
+            quality: mixture.weights().map(|weight| weight / total).collect(),
+            mixture,
+            learning_rate,
+            min_probability: min_probability.min(even),
+            last: None,
+            last_batch: None,
+        }
+    }
+
+    /// Current probability of picking each operator, in the order they were added.
+    pub fn probabilities(&self) -> impl Iterator<Item = f32> + '_ {
+        let total = self.mixture.total_weight();
+        self.mixture.weights().map(move |weight| weight / total)
+    }
+
+    /// Sample a neighbour of `x` with an operator drawn through `rng`, remembering the
+    /// operator for the next [`AdaptiveMixture::reward`].
+    pub fn propose<R: Rand + ?Sized>(&mut self, x: &T, rng: &mut R) -> T {
+        let chosen = self.mixture.pick(rng);
+        self.last = Some(chosen);
+        (self.mixture.operators[chosen].1)(x)
+    }
+
+    /// Credit the operator of the last proposal with `improvement`, the energy decrease it
+    /// achieved; increases count as no improvement. Does nothing before the first proposal.
+    pub fn reward(&mut self, improvement: f32) {
+        if let Some(chosen) = self.last.take() {
+            self.credit(chosen, improvement.max(0.0));
+            self.reweight();
+        }
+    }
+
+    fn credit(&mut self, operator: usize, reward: f32) {
+        let quality = &mut self.quality[operator];
+        *quality += self.learning_rate * (reward - *quality);
+    }
+
+    /// Turn the qualities into the weights of the mixture.
+    fn reweight(&mut self) {
+        let total: f32 = self.quality.iter().sum();
+        #[allow(clippy::cast_precision_loss)]
+        let count = self.quality.len() as f32;
+        let share = 1.0 - count * self.min_probability;
+        for ((weight, _), quality) in self.mixture.operators.iter_mut().zip(&self.quality) {
+            let matched = if total > 0.0 {
+                quality / total
+            } else {
+                1.0 / count
+            };
+            // Keep weights positive even for a zero `min_probability`.
+            *weight = (self.min_probability + share * matched).max(f32::MIN_POSITIVE);
+        }
+    }
+}
+
+impl<S: af::HasAfEnum> AdaptiveMixture<'_, af::Array<S>> {
+    /// Sample a neighbour of every column of `x`, drawing an operator for each column
+    /// separately and remembering them for the next [`AdaptiveMixture::reward_batch`].
+    pub fn propose_batch(&mut self, x: &af::Array<S>) -> af::Array<S> {
+        self.propose_batch_with(x, &af::get_default_random_engine())
+    }
+
+    /// Like [`AdaptiveMixture::propose_batch`], but draws the operators from `engine`.
+    pub fn propose_batch_with(
+        &mut self,
+        x: &af::Array<S>,
+        engine: &af::RandomEngine,
+    ) -> af::Array<S> {
+        let (proposals, choices) = self.mixture.propose_batch_choices(x, engine);
+        self.last_batch = Some(choices);
+        proposals
+    }
+
+    /// Credit every operator with the mean energy decrease of the columns that used it in the
+    /// last batch; `improvements` holds one value per column. Operators no column used keep
+    /// their quality.
+    pub fn reward_batch(&mut self, improvements: &af::Array<f32>) {
+        let Some(choices) = self.last_batch.take() else {
+            return;
+        };
+        let rewards = af::moddims(&af::maxof(improvements, &0.0f32, false), choices.dims());
+        for operator in 0..self.quality.len() {
+            #[allow(clippy::cast_possible_truncation)]
+            let used = af::eq(&choices, &(operator as u32), false);
+            let (count, _) = af::count_all(&used);
+            if count > 0 {
+                let (total, _) = af::sum_all(&(&rewards * used.cast::<f32>()));
+                #[allow(clippy::cast_precision_loss)]
+                self.credit(operator, total / count as f32);
+            }
+        }
+        self.reweight();
+    }
+}
+
+impl<T> std::fmt::Debug for AdaptiveMixture<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdaptiveMixture")
+            .field("quality", &self.quality)
+            .field("learning_rate", &self.learning_rate)
+            .field("min_probability", &self.min_probability)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Default for Mixture<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> std::fmt::Debug for Mixture<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mixture")
+            .field("weights", &self.weights().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A local search operator whose step size is controlled by the caller.
+///
+/// Adaptive annealers (see [`crate::seqsa::minimize_adaptive`]) tune `scale` on the fly to
+/// keep the acceptance ratio near a target. Any `FnMut(&T, f32) -> T` closure is an
+/// `AdaptiveNeighbour`, e.g. `|x, scale| random_perturbation(x, scale)`.
+pub trait AdaptiveNeighbour<T> {
+    /// Sample a neighbour of `x`, taking a step proportional to `scale`.
+    fn neighbour(&mut self, x: &T, scale: f32) -> T;
+}
+
+impl<T, F> AdaptiveNeighbour<T> for F
+where
+    F: FnMut(&T, f32) -> T,
+{
+    fn neighbour(&mut self, x: &T, scale: f32) -> T {
+        self(x, scale)
+    }
+}
+
+/// Neighbour function that also receives the temperature the proposal is made at, so that it
+/// can take smaller steps as the system cools; see [`crate::seqsa::minimize_tempered`].
+/// Implemented for every `FnMut(&T, N) -> T`.
+pub trait TemperatureNeighbour<T, N = f32> {
+    /// Sample a neighbour of `x` at `temperature`.
+    fn neighbour(&mut self, x: &T, temperature: N) -> T;
+}
+
+impl<T, N, F> TemperatureNeighbour<T, N> for F
+where
+    F: FnMut(&T, N) -> T,
+{
+    fn neighbour(&mut self, x: &T, temperature: N) -> T {
+        self(x, temperature)
+    }
+}
+
+/// What a [`LocalSearchOp`] may draw on when proposing a neighbour.
+pub struct Context<'a> {
+    /// Host random number generator, for operators on host states such as [`AdjacentSwap`]
+    /// on a `Vec`.
+    pub rng: &'a mut dyn Rand,
+    /// Random engine for operators on arrays; arrayfire's default engine if `None`.
+    pub engine: Option<&'a af::RandomEngine>,
+    /// Temperature the proposal is made at, if known.
+    pub temperature: Option<f32>,
+    /// Step scale of every chain of an adaptive data-parallel run, shaped like its energies.
+    pub scale: Option<&'a af::Array<f32>>,
+}
+
+impl<'a> Context<'a> {
+    /// A context holding only `rng`.
+    pub fn new(rng: &'a mut dyn Rand) -> Self {
+        Self {
+            rng,
+            engine: None,
+            temperature: None,
+            scale: None,
+        }
+    }
+
+    /// The engine array operators draw from.
+    #[must_use]
+    pub fn random_engine(&self) -> af::RandomEngine {
+        self.engine
+            .map_or_else(af::get_default_random_engine, Clone::clone)
+    }
+}
+
+/// A neighbour operator that receives a [`Context`] with every proposal.
+///
+/// Plain `FnMut(&T) -> T` closures are operators that ignore the context, and so are boxed
+/// trait objects, so [`crate::seqsa::minimize_op`] and [`crate::parsa::minimize_op`] accept
+/// either. The operators of this module are available as types with their parameters as
+/// fields, e.g. [`Perturbation`] for [`random_perturbation`]; those that take a step size
+/// multiply it by the per-chain [`Context::scale`] when one is given, unless their
+/// documentation says otherwise.
+///
+/// Both minimizers report how much every proposal lowered the energy once it went through the
+/// acceptance test, so that operators such as [`AdaptiveMixture`] can learn during the run.
+pub trait LocalSearchOp<T> {
+    /// Sample a neighbour of `x`.
+    fn propose(&mut self, x: &T, context: &mut Context<'_>) -> T;
+
+    /// Learn from the energy decrease `improvement` of the last proposal; negative for an
+    /// increase. Does nothing by default.
+    fn feedback(&mut self, improvement: f32) {
+        let _ = improvement;
+    }
+
+    /// Learn from the energy decreases of the last batch of proposals, one per chain and
+    /// shaped like their energies. Does nothing by default.
+    fn feedback_batch(&mut self, improvements: &af::Array<f32>) {
+        let _ = improvements;
+    }
+}
+
+impl<T, F> LocalSearchOp<T> for F
+where
+    F: FnMut(&T) -> T,
+{
+    fn propose(&mut self, x: &T, _: &mut Context<'_>) -> T {
+        self(x)
+    }
+}
+
+impl<T> LocalSearchOp<T> for Box<dyn LocalSearchOp<T> + '_> {
+    fn propose(&mut self, x: &T, context: &mut Context<'_>) -> T {
+        (**self).propose(x, context)
+    }
+
+    fn feedback(&mut self, improvement: f32) {
+        (**self).feedback(improvement);
+    }
+
+    fn feedback_batch(&mut self, improvements: &af::Array<f32>) {
+        (**self).feedback_batch(improvements);
+    }
+}
+
+impl<T> LocalSearchOp<T> for Mixture<'_, T> {
+    fn propose(&mut self, x: &T, context: &mut Context<'_>) -> T {
+        Mixture::propose(self, x, context.rng)
+    }
+}
+
+impl<T> LocalSearchOp<T> for AdaptiveMixture<'_, T> {
+    fn propose(&mut self, x: &T, context: &mut Context<'_>) -> T {
+        AdaptiveMixture::propose(self, x, context.rng)
+    }
+
+    fn feedback(&mut self, improvement: f32) {
+        self.reward(improvement);
+    }
+
+    /// The whole batch was proposed by a single operator, which is credited with the mean
+    /// improvement over the chains, increases counting as none.
+    fn feedback_batch(&mut self, improvements: &af::Array<f32>) {
+        let (mean, _) = af::mean_all(&af::maxof(improvements, &0.0f32, false));
+        #[allow(clippy::cast_possible_truncation)]
+        self.reward(mean as f32);
+    }
+}
+
+/// Scale the step from `x` to `proposal` by the per-chain scales of `context`, if any.
+fn chain_scaled(
+    x: &af::Array<f32>,
+    proposal: af::Array<f32>,
+    context: &Context<'_>,
+) -> af::Array<f32> {
+    match context.scale {
+        Some(scale) => x + af::mul(&(proposal - x), scale, true),
+        None => proposal,
+    }
+}
+
+/// [`random_perturbation`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Perturbation {
+    /// Standard deviation of the noise.
+    pub scale: f32,
+}
+
+impl LocalSearchOp<af::Array<f32>> for Perturbation {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        let proposal = random_perturbation_with(x, self.scale, &context.random_engine());
+        chain_scaled(x, proposal, context)
+    }
+}
+
+/// [`random_two_scale_perturbation`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwoScalePerturbation {
+    /// Standard deviation of the usual steps.
+    pub small: f32,
+    /// Standard deviation of the occasional jumps.
+    pub large: f32,
+    /// Probability of a small step.
+    pub probability: f32,
+}
+
+impl LocalSearchOp<af::Array<f32>> for TwoScalePerturbation {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        let proposal = random_two_scale_perturbation_with(
+            x,
+            self.small,
+            self.large,
+            self.probability,
+            &context.random_engine(),
+        );
+        chain_scaled(x, proposal, context)
+    }
+}
+
+/// [`random_self_adaptive_perturbation`] as a [`LocalSearchOp`]. The step sizes carried in
+/// the states take the place of the per-chain scales of the context, which are ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfAdaptivePerturbation {
+    /// Standard deviation of the change of the logarithm of the step sizes.
+    pub learning_rate: f32,
+}
+
+impl LocalSearchOp<af::Array<f32>> for SelfAdaptivePerturbation {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        random_self_adaptive_perturbation_with(x, self.learning_rate, &context.random_engine())
+    }
+}
+
+/// [`random_uniform_perturbation`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UniformPerturbation {
+    /// Half-width of the noise.
+    pub scale: f32,
+}
+
+impl LocalSearchOp<af::Array<f32>> for UniformPerturbation {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        let proposal = random_uniform_perturbation_with(x, self.scale, &context.random_engine());
+        chain_scaled(x, proposal, context)
+    }
+}
+
+/// [`random_subset_perturbation`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubsetPerturbation {
+    /// Standard deviation of the noise.
+    pub scale: f32,
+    /// Which coordinates are moved.
+    pub coordinates: Coordinates,
+}
+
+impl LocalSearchOp<af::Array<f32>> for SubsetPerturbation {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        let proposal = random_subset_perturbation_with(
+            x,
+            self.scale,
+            self.coordinates,
+            &context.random_engine(),
+        );
+        chain_scaled(x, proposal, context)
+    }
+}
+
+impl LocalSearchOp<af::Array<f32>> for CorrelatedPerturbation {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        let proposal = self.perturb_with(x, &context.random_engine());
+        chain_scaled(x, proposal, context)
+    }
+}
+
+/// [`random_sphere_perturbation`] as a [`LocalSearchOp`]. A step scaled by the per-chain
+/// scales of the context is projected back onto the sphere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpherePerturbation {
+    /// Standard deviation of the noise before re-projection.
+    pub scale: f32,
+}
+
+impl LocalSearchOp<af::Array<f32>> for SpherePerturbation {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        let proposal = random_sphere_perturbation_with(x, self.scale, &context.random_engine());
+        match context.scale {
+            Some(_) => on_sphere_of(x, &chain_scaled(x, proposal, context)),
+            None => proposal,
+        }
+    }
+}
+
+/// [`random_rotation`] as a [`LocalSearchOp`]. Its states are matrices rather than columns,
+/// and scaling a step would leave the rotation group, so the per-chain scales of the context
+/// are ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rotation {
+    /// Standard deviation of the angle, in radians.
+    pub scale: f32,
+}
+
+impl LocalSearchOp<af::Array<f32>> for Rotation {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        random_rotation_with(x, self.scale, &context.random_engine())
+    }
+}
+
+/// [`differential_mutation`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifferentialMutation {
+    /// Weight of the difference between the two other chains.
+    pub weight: f32,
+}
+
+impl LocalSearchOp<af::Array<f32>> for DifferentialMutation {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        let proposal = differential_mutation_with(x, self.weight, &context.random_engine());
+        chain_scaled(x, proposal, context)
+    }
+}
+
+/// [`random_bit_flip`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BitFlip;
+
+impl<T> LocalSearchOp<af::Array<T>> for BitFlip
+where
+    T: af::HasAfEnum + af::IntegralType + af::ImplicitPromote<T, Output = T>,
+{
+    fn propose(&mut self, x: &af::Array<T>, context: &mut Context<'_>) -> af::Array<T> {
+        random_bit_flip_with(x, &context.random_engine())
+    }
+}
+
+/// [`random_multi_bit_flip`] as a [`LocalSearchOp`], optionally cooling its radius with
+/// [`cooled_radius`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MultiBitFlip {
+    /// Mean number of flipped bits.
+    pub radius: f32,
+    /// Temperature at which the full `radius` applies; the radius shrinks below it when the
+    /// context carries a temperature.
+    pub initial_temperature: Option<f32>,
+}
+
+impl<T> LocalSearchOp<af::Array<T>> for MultiBitFlip
+where
+    T: af::HasAfEnum + af::IntegralType + af::ImplicitPromote<T, Output = T>,
+{
+    fn propose(&mut self, x: &af::Array<T>, context: &mut Context<'_>) -> af::Array<T> {
+        let radius = match (self.initial_temperature, context.temperature) {
+            (Some(initial), Some(temperature)) => cooled_radius(self.radius, temperature, initial),
+            _ => self.radius,
+        };
+        random_multi_bit_flip_with(x, radius, &context.random_engine())
+    }
+}
+
+impl LocalSearchOp<af::Array<f32>> for GrayCode {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        self.random_flip_with(x, &context.random_engine())
+    }
+}
+
+/// [`random_swap`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Swap;
+
+impl<T: af::HasAfEnum> LocalSearchOp<af::Array<T>> for Swap {
+    fn propose(&mut self, x: &af::Array<T>, context: &mut Context<'_>) -> af::Array<T> {
+        random_swap_with(x, &context.random_engine())
+    }
+}
+
+/// [`random_adjacent_swap`] as a [`LocalSearchOp`], and [`adjacent_swap`] for `Vec` states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AdjacentSwap;
+
+impl<T: af::HasAfEnum> LocalSearchOp<af::Array<T>> for AdjacentSwap {
+    fn propose(&mut self, x: &af::Array<T>, context: &mut Context<'_>) -> af::Array<T> {
+        random_adjacent_swap_with(x, &context.random_engine())
+    }
+}
+
+impl<T: Clone> LocalSearchOp<Vec<T>> for AdjacentSwap {
+    fn propose(&mut self, x: &Vec<T>, context: &mut Context<'_>) -> Vec<T> {
+        adjacent_swap(x, context.rng)
+    }
+}
+
+/// [`random_cyclic_shift`] as a [`LocalSearchOp`], and [`cyclic_shift`] for `Vec` states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CyclicShift;
+
+impl<T: af::HasAfEnum> LocalSearchOp<af::Array<T>> for CyclicShift {
+    fn propose(&mut self, x: &af::Array<T>, context: &mut Context<'_>) -> af::Array<T> {
+        random_cyclic_shift_with(x, &context.random_engine())
+    }
+}
+
+impl<T: Clone> LocalSearchOp<Vec<T>> for CyclicShift {
+    fn propose(&mut self, x: &Vec<T>, context: &mut Context<'_>) -> Vec<T> {
+        cyclic_shift(x, context.rng)
+    }
+}
+
+/// [`random_k_opt`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KOpt {
+    /// Number of removed edges.
+    pub k: usize,
+}
+
+impl<T: af::HasAfEnum> LocalSearchOp<af::Array<T>> for KOpt {
+    fn propose(&mut self, x: &af::Array<T>, context: &mut Context<'_>) -> af::Array<T> {
+        random_k_opt_with(x, self.k, &context.random_engine())
+    }
+}
+
+/// [`random_block_move`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BlockMove;
+
+impl<T: af::HasAfEnum> LocalSearchOp<af::Array<T>> for BlockMove {
+    fn propose(&mut self, x: &af::Array<T>, context: &mut Context<'_>) -> af::Array<T> {
+        random_block_move_with(x, &context.random_engine())
+    }
+}
+
+/// [`random_reassignment`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reassignment {
+    /// Number of categories.
+    pub categories: u32,
+}
+
+impl<T: af::HasAfEnum> LocalSearchOp<af::Array<T>> for Reassignment {
+    fn propose(&mut self, x: &af::Array<T>, context: &mut Context<'_>) -> af::Array<T> {
+        random_reassignment_with(x, self.categories, &context.random_engine())
+    }
+}
+
+/// [`random_group_exchange`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupExchange {
+    /// Number of groups the elements are assigned to.
+    pub groups: u32,
+    /// Largest difference of group sizes a relocation may leave; `0` disables relocations.
+    pub slack: u32,
+}
+
+impl<T: af::HasAfEnum> LocalSearchOp<af::Array<T>> for GroupExchange {
+    fn propose(&mut self, x: &af::Array<T>, context: &mut Context<'_>) -> af::Array<T> {
+        random_group_exchange_with(x, self.groups, self.slack, &context.random_engine())
+    }
+}
+
+/// [`random_lattice_step`] as a [`LocalSearchOp`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatticeStep {
+    /// Mean step length.
+    pub reach: f32,
+    /// Inclusive bounds of every coordinate.
+    pub bounds: Option<(i32, i32)>,
+}
+
+impl LocalSearchOp<af::Array<i32>> for LatticeStep {
+    fn propose(&mut self, x: &af::Array<i32>, context: &mut Context<'_>) -> af::Array<i32> {
+        random_lattice_step_with(x, self.reach, self.bounds, &context.random_engine())
+    }
+}
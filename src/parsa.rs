@@ -1,14 +1,519 @@
 //! Data-parallel simulated annealing.
 
+use std::cell::{Cell, RefCell};
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use arrayfire::{self as af, dim4};
+use half::f16;
+use tinyrand::{Seeded, StdRand};
+
+use crate::lsops::{Context, LocalSearchOp};
+use crate::Error;
+
+pub use crate::seqsa::{NonPositiveTemperature, Resampling, StepAdaptation, Termination};
+
+/// Floating-point element types that states and energies can be annealed in: `f32`, `f64`,
+/// and `f16` for memory-bound problems.
+///
+/// Acceptance probabilities are computed in the element type, from a temperature scaled in
+/// double precision.
+pub trait Element:
+    af::HasAfEnum<InType = Self, BaseType = Self, UnaryOutType = Self>
+    + af::ImplicitPromote<Self, Output = Self>
+    + af::ConstGenerator<OutType = Self>
+    + af::Convertable<OutType = Self>
+    + af::RealNumber
+    + af::Fromf64
+    + std::ops::Neg<Output = Self>
+    + Into<f64>
+    + PartialOrd
+    + Copy
+    + std::fmt::Debug
+{
+}
+
+impl Element for f32 {}
+impl Element for f64 {}
+impl Element for f16 {}
+
+/// A temperature of the annealing schedule: a single value of type `T` shared by every chain,
+/// or an `af::Array<T>` holding one temperature per chain, for heterogeneous ensembles or
+/// per-chain cooling. Arrays must have `batch_size` elements, in any shape.
+pub trait Temperature<T: Element> {
+    /// Decide, for every chain, whether to move from energy `ex` to the proposal's energy `en`
+    /// given uniform draws `uniform`: the Metropolis criterion with Boltzmann constant `k`,
+    /// except that chains at a non-positive temperature accept only improvements.
+    ///
+    /// The built-in implementations compare `(ex - en) / kT` against `log(uniform)` rather
+    /// than exponentiating, so extreme temperatures cannot overflow or underflow.
+    fn accept(
+        &self,
+        ex: &af::Array<T>,
+        en: &af::Array<T>,
+        k: T,
+        uniform: &af::Array<T>,
+    ) -> af::Array<bool>;
+
+    /// Whether every chain is at a non-positive temperature.
+    fn is_frozen(&self) -> bool;
+
+    /// The temperature shared by every chain, if there is a single one.
+    fn value(&self) -> Option<f64> {
+        None
+    }
+
+    /// The temperatures of the chains selected by `seqs` out of a batch shaped `chain_dims`,
+    /// for annealing the batch one tile at a time.
+    #[must_use]
+    fn tile(&self, chain_dims: af::Dim4, seqs: &[af::Seq<f64>; 4]) -> Self
+    where
+        Self: Sized;
+}
+
+impl<T: Element> Temperature<T> for T {
+    fn accept(
+        &self,
+        ex: &af::Array<T>,
+        en: &af::Array<T>,
+        k: T,
+        uniform: &af::Array<T>,
+    ) -> af::Array<bool> {
+        if self.is_frozen() {
+            return af::gt(ex, en, false);
+        }
+        let kt = T::fromf64(k.into() * (*self).into());
+        af::gt(&af::div(&(ex - en), &kt, false), &af::log(uniform), true)
+    }
+
+    fn is_frozen(&self) -> bool {
+        (*self).into() <= 0.0
+    }
+
+    fn value(&self) -> Option<f64> {
+        Some((*self).into())
+    }
+
+    fn tile(&self, _chain_dims: af::Dim4, _seqs: &[af::Seq<f64>; 4]) -> Self {
+        *self
+    }
+}
+
+impl<T: Element> Temperature<T> for af::Array<T> {
+    fn accept(
+        &self,
+        ex: &af::Array<T>,
+        en: &af::Array<T>,
+        k: T,
+        uniform: &af::Array<T>,
+    ) -> af::Array<bool> {
+        let temperature = af::moddims(self, ex.dims());
+        let kt = af::mul(&temperature, &k, false);
+        let metropolis = af::gt(&((ex - en) / &kt), &af::log(uniform), true);
+        let frozen = af::le(&temperature, &T::fromf64(0.0), false);
+        af::select(&af::gt(ex, en, false), &frozen, &metropolis)
+    }
+
+    fn is_frozen(&self) -> bool {
+        let (max, _) = af::max_all(self);
+        max.into() <= 0.0
+    }
+
+    fn tile(&self, chain_dims: af::Dim4, seqs: &[af::Seq<f64>; 4]) -> Self {
+        af::index(&af::moddims(self, chain_dims), seqs)
+    }
+}
+
+/// Outcome of a data-parallel annealing run, with energies of type `T` and states of type `S`
+/// (the same as the energies, except for [`minimize_discrete`]).
+#[derive(Clone)]
+pub struct ParsaResult<T: Element = f32, S: af::HasAfEnum = T> {
+    /// Final state of every chain, batched along [`Options::batch_dim`] (one per column by
+    /// default).
+    pub states: af::Array<S>,
+    /// Final energy of every chain, as a `1 x batch_size` row.
+    pub energies: af::Array<T>,
+    /// Index, along the batch dimension of `states`, of the chain with the lowest final energy.
+    pub best_index: usize,
+    /// Lowest-energy state seen by any chain during the whole run, with the shape of a single
+    /// chain's state. It may be better than every final state, since chains keep accepting uphill
+    /// moves.
+    pub best_state: af::Array<S>,
+    /// Energy of `best_state`.
+    pub best_energy: T,
+    /// Mean of the final energies.
+    pub mean_energy: T,
+    /// Number of temperatures processed.
+    pub temperatures: usize,
+    /// Number of steps each chain made; every step evaluates one proposal per chain.
+    pub steps: usize,
+    /// Accepted proposals over all chains at each processed temperature, in schedule order,
+    /// excluding the quench. Counted on the device and copied once the run ends; empty for
+    /// [`minimize_replica_exchange`], [`minimize_numeric_multi_device`] and
+    /// [`minimize_numeric_lazy`].
+    pub acceptances: Vec<u64>,
+    /// Why the run stopped.
+    pub termination: Termination,
+    /// Best distinct states seen, when [`Options::archive_size`] is non-zero.
+    pub archive: Option<Archive<T, S>>,
+}
+
+/// The best distinct states seen during a run, kept on the device.
+#[derive(Clone)]
+pub struct Archive<T: Element = f32, S: af::HasAfEnum = T> {
+    /// Archived states from best to worst, batched along [`Options::batch_dim`].
+    pub states: af::Array<S>,
+    /// Energies of `states`, as a row.
+    pub energies: af::Array<T>,
+}
+
+impl<T: Element, S: af::HasAfEnum> std::fmt::Debug for Archive<T, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Archive")
+            .field("states", &format_args!("Array {}", self.states.dims()))
+            .field("energies", &format_args!("Array {}", self.energies.dims()))
+            .finish()
+    }
+}
+
+impl<T: Element, S: af::HasAfEnum> ParsaResult<T, S> {
+    /// The lowest-energy state seen by any chain, with the shape of a single chain's state,
+    /// and its energy.
+    pub fn best(&self) -> (&af::Array<S>, T) {
+        (&self.best_state, self.best_energy)
+    }
+
+    /// Like [`ParsaResult::best`], dropping the rest of the result.
+    pub fn into_best(self) -> (af::Array<S>, T) {
+        (self.best_state, self.best_energy)
+    }
+}
+
+impl<T: Element, S: af::HasAfEnum> std::fmt::Debug for ParsaResult<T, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParsaResult")
+            .field("states", &format_args!("Array {}", self.states.dims()))
+            .field("energies", &format_args!("Array {}", self.energies.dims()))
+            .field("best_index", &self.best_index)
+            .field(
+                "best_state",
+                &format_args!("Array {}", self.best_state.dims()),
+            )
+            .field("best_energy", &self.best_energy)
+            .field("mean_energy", &self.mean_energy)
+            .field("temperatures", &self.temperatures)
+            .field("steps", &self.steps)
+            .field("acceptances", &self.acceptances)
+            .field("termination", &self.termination)
+            .field("archive", &self.archive)
+            .finish()
+    }
+}
+
+/// Device-side snapshot of a run at the end of a temperature, handed to observers.
+///
+/// The states and energies can be modified in place, e.g. to inject a custom migration;
+/// they must be kept consistent, since the run continues from them.
+pub struct Progress<'a, T: Element, S: af::HasAfEnum = T, K = T> {
+    /// Index of the temperature that just finished.
+    pub step: usize,
+    /// Temperature that just finished, a value or one temperature per chain.
+    pub temperature: &'a K,
+    /// Current state of every chain, batched along [`Options::batch_dim`].
+    pub states: &'a mut af::Array<S>,
+    /// Current energy of every chain, with the chains along [`Options::batch_dim`].
+    pub energies: &'a mut af::Array<T>,
+    /// Lowest energy seen so far by each chain.
+    pub best_energies: &'a af::Array<T>,
+    /// Proposals accepted by each chain at this temperature, shaped like the energies.
+    pub accepted: &'a af::Array<u32>,
+    /// Proposals made by each chain at this temperature.
+    pub proposed: usize,
+}
+
+/// How chains exchange states at the end of every temperature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Migration {
+    /// Replace every chain by the single best one. This concentrates the whole batch on the
+    /// most promising region, at the cost of population diversity.
+    Collapse,
+    /// Leave the chains untouched, so they anneal independently.
+    Independent,
+    /// Replace the batch by copies of its `k` best chains, assigned round-robin.
+    TopK(u32),
+    /// Replace every chain by the winner of a tournament among this many chains drawn at
+    /// random (with replacement).
+    Tournament(u32),
+    /// Replace this fraction of the batch, the worst chains first, by the best chain.
+    ReplaceWorst(f32),
+    /// Replace every chain but the best by a child of two parents drawn from this many best
+    /// chains, taking every element from either parent with equal probability. Children are
+    /// evaluated afresh.
+    UniformCrossover(u32),
+    /// Like [`Migration::UniformCrossover`], with children taking the elements before a random
+    /// cut point (in column-major order) from the first parent and the rest from the second.
+    SinglePointCrossover(u32),
+}
+
+/// Measure of how far apart the chains' energies are, with the threshold below which the
+/// population is considered converged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spread<T = f32> {
+    /// Difference between the highest and the lowest energy.
+    Range(T),
+    /// Population variance of the energies.
+    Variance(T),
+}
+
+/// Correlated Gaussian proposals for [`minimize_numeric_covariance`], drawn through the Cholesky
+/// factor of the covariance of the states the chains recently moved through.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CovarianceAdaptation {
+    /// Standard deviation of the isotropic proposals made before the first estimate.
+    pub initial_scale: f32,
+    /// Factor applied to the Cholesky factor; `None` uses `2.38 / sqrt(d)` for states of `d`
+    /// elements, after Haario et al.
+    pub scale: Option<f32>,
+    /// Number of chain steps between covariance updates.
+    pub update_interval: usize,
+    /// Weight, in `[0, 1)`, of the previous estimate in every update.
+    pub memory: f32,
+    /// Value added to the diagonal of the estimate to keep it positive definite.
+    pub regularization: f32,
+}
+
+impl CovarianceAdaptation {
+    /// Adaptation starting from isotropic proposals of standard deviation `initial_scale`,
+    /// updating the estimate every 10 steps with equal weight on the previous one.
+    pub fn new(initial_scale: f32) -> Self {
+        Self {
+            initial_scale,
+            scale: None,
+            update_interval: 10,
+            memory: 0.5,
+            regularization: 1e-6,
+        }
+    }
+}
+
+/// Map from a batch of proposals onto the feasible set, such as a clamp to bounds or a
+/// renormalization, see [`Options::projection`].
+#[derive(Clone)]
+pub struct Projection<T: af::HasAfEnum>(Arc<ArrayMap<T>>);
+
+type ArrayMap<T> = dyn Fn(&af::Array<T>) -> af::Array<T> + Send + Sync;
+
+impl<T: af::HasAfEnum> Projection<T> {
+    /// Wrap `project`, which receives every chain's proposal batched as the states are and must
+    /// return them in the same shape.
+    pub fn new(project: impl Fn(&af::Array<T>) -> af::Array<T> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(project))
+    }
+}
+
+impl<T: af::HasAfEnum> std::fmt::Debug for Projection<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Projection")
+    }
+}
+
+/// Parameters of a data-parallel annealing run, in element type `T`.
+#[derive(Debug, Clone)]
+pub struct Options<T: af::HasAfEnum = f32> {
+    /// Number of parallel annealing chains to run.
+    pub batch_size: u64,
+    /// Number of iterations at each temperature.
+    pub chain_length: usize,
+    /// Number of iterations at each temperature of the schedule, in order, e.g. to refine longer
+    /// once the chains have cooled down. Temperatures past its end use `chain_length`; ignored
+    /// by [`minimize_replica_exchange`], which has no schedule.
+    pub chain_lengths: Vec<usize>,
+    /// Boltzmann constant used in acceptance probability calculation.
+    pub k: T,
+    /// Boltzmann constant of every chain, overriding `k`, as `batch_size` positive elements in
+    /// any shape; lets chains of a single run race acceptance aggressiveness. Honoured by
+    /// [`minimize_numeric_with`], [`minimize_numeric_adaptive`], [`minimize_numeric_covariance`],
+    /// [`minimize_numeric_observed`], `minimize_numeric_stream`, [`minimize_discrete`],
+    /// [`minimize_op`] and [`minimize_numeric_chunked`]; [`minimize_replica_exchange`] and
+    /// [`minimize_numeric_multi_device`] panic if it is set.
+    pub chain_k: Option<af::Array<T>>,
+    /// Wall-clock budget for the run; the best states found so far are returned once it elapses.
+    pub time_limit: Option<Duration>,
+    /// Cancellation token checked after every chain step; setting it to `true` stops the run
+    /// cooperatively.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Averaging of repeated evaluations for noisy objectives: every candidate's energy is
+    /// the mean of `samples` calls, and the chains' current energies are re-estimated every
+    /// `reevaluate_interval` chain steps.
+    pub resampling: Option<Resampling>,
+    /// Number of greedy chain steps, accepting only improvements, run after the schedule is
+    /// exhausted, reaches a non-positive temperature or the chains have converged.
+    pub quench_iterations: usize,
+    /// Seed of a random engine dedicated to the acceptance draws, making runs reproducible
+    /// independently of arrayfire's global seed. `None` draws from the default engine, which
+    /// is seeded by `af::set_seed`.
+    pub seed: Option<u64>,
+    /// Exchange of states between chains after every temperature.
+    pub migration: Migration,
+    /// Dimension along which the chains are batched. The start is either a single state, with
+    /// extent 1 along it, tiled into the batch, or already holds one distinct start per chain
+    /// along it. The default, 1, suits column-vector states, while matrix or higher-rank
+    /// states are batched along dimension 3. The energy function receives the whole batch and
+    /// returns one energy per chain, in any shape.
+    pub batch_dim: usize,
+    /// Number of chain steps between explicit evaluations of the chains' arrays, which cuts
+    /// arrayfire's lazily built JIT trees short and lets their buffers be reused; 0 never
+    /// forces evaluation.
+    pub eval_interval: usize,
+    /// Number of chain steps between waits for the device to finish its queued work, bounding
+    /// how far the host runs ahead; 0 never waits.
+    pub sync_interval: usize,
+    /// Number of best distinct states to keep in [`ParsaResult::archive`]; 0 keeps no archive.
+    /// The archive collects the chains' best states after every temperature, and is meant to
+    /// stay small.
+    pub archive_size: u32,
+    /// Projection applied to every batch of proposals before it is evaluated, keeping
+    /// constrained problems feasible whatever the neighbour function; ignored by
+    /// [`minimize_discrete`].
+    pub projection: Option<Projection<T>>,
+    /// Handling of schedule temperatures that are non-positive for every chain; chains at a
+    /// non-positive temperature otherwise accept only improvements.
+    pub non_positive_temperature: NonPositiveTemperature,
+    /// Stop with [`Termination::Converged`] once the spread of the chains' energies at the end
+    /// of a temperature, before migration, drops below the threshold.
+    pub convergence: Option<Spread<T>>,
+    /// Stop with [`Termination::TargetReached`] once the best energy seen, checked at the end of
+    /// every temperature, is at most this value.
+    pub target_energy: Option<T>,
+}
+
+impl<T: Element> Options<T> {
+    /// Options with the given batch size, chain length and Boltzmann constant, batching
+    /// column states along dimension 1, evaluating them after every step and collapsing them
+    /// after every temperature, with no time limit, cancellation token, resampling, quench,
+    /// dedicated seed, device synchronization, archive or early stopping.
+    pub fn new(batch_size: u64, chain_length: usize, k: T) -> Self {
+        Self {
+            batch_size,
+            chain_length,
+            chain_lengths: Vec::new(),
+            k,
+            chain_k: None,
+            time_limit: None,
+            cancel: None,
+            resampling: None,
+            quench_iterations: 0,
+            seed: None,
+            migration: Migration::Collapse,
+            batch_dim: 1,
+            eval_interval: 1,
+            sync_interval: 0,
+            archive_size: 0,
+            projection: None,
+            non_positive_temperature: NonPositiveTemperature::Terminate,
+            convergence: None,
+            target_energy: None,
+        }
+    }
+
+    /// Number of iterations at the temperature with index `step` in the schedule.
+    #[must_use]
+    pub fn chain_length_at(&self, step: usize) -> usize {
+        self.chain_lengths
+            .get(step)
+            .copied()
+            .unwrap_or(self.chain_length)
+    }
+
+    /// Apply the projection, if any, to `proposals`.
+    fn project(&self, proposals: af::Array<T>) -> af::Array<T> {
+        match &self.projection {
+            Some(Projection(project)) => project(&proposals),
+            None => proposals,
+        }
+    }
+
+    fn engine(&self) -> af::RandomEngine {
+        match self.seed {
+            Some(seed) => af::RandomEngine::new(af::DEFAULT_RANDOM_ENGINE, Some(seed)),
+            None => af::get_default_random_engine(),
+        }
+    }
+
+    /// Evaluate the chains' arrays and wait for the device as configured, after `steps` steps.
+    fn flush<S: af::HasAfEnum>(
+        &self,
+        steps: usize,
+        states: [&af::Array<S>; 2],
+        energies: [&af::Array<T>; 2],
+    ) {
+        if self.eval_interval > 0 && steps % self.eval_interval == 0 {
+            states.iter().for_each(|x| x.eval());
+            energies.iter().for_each(|ex| ex.eval());
+        }
+        if self.sync_interval > 0 && steps % self.sync_interval == 0 {
+            af::sync(af::get_device());
+        }
+    }
+
+    /// Whether the run terminates upon reaching `temperature`.
+    fn stops_at(&self, temperature: &impl Temperature<T>) -> bool {
+        self.non_positive_temperature == NonPositiveTemperature::Terminate
+            && temperature.is_frozen()
+    }
+
+    /// Check the early stopping criteria at the end of a temperature.
+    fn convergence(&self, ex: &af::Array<T>, best_ex: &af::Array<T>) -> Option<Termination> {
+        if let Some(target) = self.target_energy {
+            let (best, _) = af::min_all(best_ex);
+            if best <= target {
+                return Some(Termination::TargetReached);
+            }
+        }
+        let spread = match self.convergence? {
+            Spread::Range(threshold) => {
+                let (max, _) = af::max_all(ex);
+                let (min, _) = af::min_all(ex);
+                (max.into() - min.into(), threshold)
+            }
+            Spread::Variance(threshold) => {
+                let (variance, _) = af::var_all_v2(ex, af::VarianceBias::POPULATION);
+                (variance, threshold)
+            }
+        };
+        (spread.0 < spread.1.into()).then_some(Termination::Converged)
+    }
+
+    fn interruption(&self, started: Instant) -> Option<Termination> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        {
+            Some(Termination::Cancelled)
+        } else if self
+            .time_limit
+            .is_some_and(|limit| started.elapsed() >= limit)
+        {
+            Some(Termination::TimeLimit)
+        } else {
+            None
+        }
+    }
+}
 
 /// Performs data-parallel simulated annealing to minimize a numeric function.
 ///
 /// # Type Parameters
 ///
-/// * `E` - Function type that computes the energy/cost of a state. Must accept an `Array<f32>` and return an `Array<f32>`.
-/// * `F` - Function type that generates neighboring states. Must accept an `Array<f32>` and return an `Array<f32>`.
-/// * `G` - Iterator type that yields temperature values of type `f32` for the annealing schedule.
+/// * `T` - Element type of states, energies and temperatures (`f32`, `f64` or `f16`).
+/// * `E` - Function type that computes the energy/cost of a state. Must accept an `Array<T>` and return an `Array<T>`.
+/// * `F` - Function type that generates neighboring states. Must accept an `Array<T>` and return an `Array<T>`;
+///   it may carry mutable state, such as its own `RandomEngine` or an iteration counter.
+/// * `G` - Iterator type that yields the temperatures of the annealing schedule: values of type
+///   `T`, or arrays holding one temperature per chain (see [`Temperature`]).
 ///
 /// # Arguments
 ///
@@ -22,48 +527,1906 @@ use arrayfire::{self as af, dim4};
 ///
 /// # Returns
 ///
-/// The best state(s) found during the annealing process.
+/// A [`ParsaResult`] holding the final chains, their energies, and the best state seen.
 ///
 /// # Panics
 ///
 /// Panics if the Boltzmann constant `k` is not positive (must be > 0.0)
-pub fn minimize_numeric<E, F, G>(
+pub fn minimize_numeric<T, E, F, G>(
     batch_size: u64,
     chain_length: usize,
-    k: f32,
-    start: &af::Array<f32>,
+    k: T,
+    start: &af::Array<T>,
     energy: E,
     neighbour_map: F,
     temperatures: G,
-) -> af::Array<f32>
+) -> ParsaResult<T>
 where
-    E: Fn(&af::Array<f32>) -> af::Array<f32>,
-    F: Fn(&af::Array<f32>) -> af::Array<f32>,
-    G: Iterator<Item = f32>,
+    T: Element,
+    E: FnMut(&af::Array<T>) -> af::Array<T>,
+    F: FnMut(&af::Array<T>) -> af::Array<T>,
+    G: Iterator,
+    G::Item: Temperature<T>,
 {
-    let tile_dim = dim4!(1, batch_size);
-    let mut x = af::tile(start, tile_dim);
-    let mut ex = energy(&x);
+    minimize_numeric_with(
+        start,
+        energy,
+        neighbour_map,
+        temperatures,
+        &Options::new(batch_size, chain_length, k),
+    )
+}
 
-    assert!(k > 0.0, "Boltzmann constant must be positive");
+/// Performs data-parallel simulated annealing to maximize a numeric function.
+///
+/// Equivalent to [`minimize_numeric`] on the negated objective; the arguments have the same
+/// meaning, with `objective` being the reward to maximize. The energies reported in the
+/// [`ParsaResult`] are objective values, with `best_energy` the highest one.
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive (must be > 0.0)
+pub fn maximize_numeric<T, E, F, G>(
+    batch_size: u64,
+    chain_length: usize,
+    k: T,
+    start: &af::Array<T>,
+    mut objective: E,
+    neighbour_map: F,
+    temperatures: G,
+) -> ParsaResult<T>
+where
+    T: Element,
+    E: FnMut(&af::Array<T>) -> af::Array<T>,
+    F: FnMut(&af::Array<T>) -> af::Array<T>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+{
+    let mut result = minimize_numeric(
+        batch_size,
+        chain_length,
+        k,
+        start,
+        |x| -objective(x),
+        neighbour_map,
+        temperatures,
+    );
+    result.energies = -result.energies;
+    result.best_energy = -result.best_energy;
+    result.mean_energy = -result.mean_energy;
+    result
+}
 
-    for temperature in temperatures {
+/// Performs data-parallel simulated annealing as configured by `options`.
+///
+/// Unlike [`minimize_numeric`], the run can be bounded by [`Options::time_limit`] or stopped
+/// through [`Options::cancel`], in which case [`ParsaResult::termination`] reports why the run
+/// stopped and the chains are returned as they were, without a final migration. The way
+/// chains share states is chosen through [`Options::migration`].
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `options.k` is not positive (must be > 0.0)
+/// * If `options.batch_dim` is not below 4, or the extent of `start` along it is neither 1
+///   nor the number of chains
+pub fn minimize_numeric_with<T, E, F, G>(
+    start: &af::Array<T>,
+    energy: E,
+    mut neighbour_map: F,
+    temperatures: G,
+    options: &Options<T>,
+) -> ParsaResult<T>
+where
+    T: Element,
+    E: FnMut(&af::Array<T>) -> af::Array<T>,
+    F: FnMut(&af::Array<T>) -> af::Array<T>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+{
+    anneal(
+        start,
+        energy,
+        |x: &af::Array<T>, _: &af::Array<T>| options.project(neighbour_map(x)),
+        temperatures,
+        options,
+        None,
+        |_: &mut Progress<T, T, G::Item>| ControlFlow::Continue(()),
+        || {},
+        |_, _| {},
+    )
+}
+
+/// Like [`minimize_numeric_with`], adapting every chain's step size on the device.
+///
+/// The neighbour receives the chains' states and their step scales, one per chain shaped like
+/// the chains' energies (a `1 x batch_size` row by default), to be broadcast with batched
+/// operations such as `af::mul(&noise, scale, true)`. After every temperature the scale of each
+/// chain is adjusted from its own acceptance ratio as described by [`StepAdaptation`];
+/// [`StepAdaptation::one_fifth`] gives the classic 1/5-success rule.
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `options.k` is not positive (must be > 0.0)
+/// * If `options.batch_dim` is not below 4, or the extent of `start` along it is neither 1
+///   nor the number of chains
+pub fn minimize_numeric_adaptive<T, E, F, G>(
+    start: &af::Array<T>,
+    energy: E,
+    mut neighbour_map: F,
+    temperatures: G,
+    options: &Options<T>,
+    adaptation: &StepAdaptation,
+) -> ParsaResult<T>
+where
+    T: Element,
+    E: FnMut(&af::Array<T>) -> af::Array<T>,
+    F: FnMut(&af::Array<T>, &af::Array<T>) -> af::Array<T>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+{
+    anneal(
+        start,
+        energy,
+        |x: &af::Array<T>, scale: &af::Array<T>| options.project(neighbour_map(x, scale)),
+        temperatures,
+        options,
+        Some(adaptation),
+        |_: &mut Progress<T, T, G::Item>| ControlFlow::Continue(()),
+        || {},
+        |_, _| {},
+    )
+}
+
+/// Like [`minimize_numeric_with`], proposing correlated Gaussian moves shaped by the chains'
+/// own trajectories, which makes progress along narrow curved valleys such as Rosenbrock's.
+///
+/// The covariance of the states every chain visited is estimated on the device across all
+/// chains, blended into the previous estimate every [`CovarianceAdaptation::update_interval`]
+/// steps, and factorized; proposals add the factor times standard normal draws. An estimate
+/// that is not positive definite leaves the previous factor in place.
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `options.k` is not positive (must be > 0.0)
+/// * If the states are not columns batched along dimension 1
+pub fn minimize_numeric_covariance<T, E, G>(
+    start: &af::Array<T>,
+    energy: E,
+    temperatures: G,
+    options: &Options<T>,
+    adaptation: &CovarianceAdaptation,
+) -> ParsaResult<T>
+where
+    T: Element + af::FloatingPoint,
+    E: FnMut(&af::Array<T>) -> af::Array<T>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+{
+    let dims = start.dims();
+    assert!(
+        options.batch_dim == 1 && dims[2] == 1 && dims[3] == 1,
+        "Covariance-adaptive proposals need column states batched along dimension 1"
+    );
+
+    // Proposals draw from a stream of their own, so they stay independent of acceptance draws.
+    let engine = match options.seed {
+        Some(seed) => af::RandomEngine::new(af::DEFAULT_RANDOM_ENGINE, Some(seed ^ u64::MAX)),
+        None => af::get_default_random_engine(),
+    };
+    let mut estimate = Covariance::new(dims[0], adaptation);
+    anneal(
+        start,
+        energy,
+        |x: &af::Array<T>, _: &af::Array<T>| {
+            estimate.record(x, adaptation);
+            let noise = af::random_normal::<T>(x.dims(), &engine);
+            options.project(
+                x + af::matmul(
+                    &estimate.factor,
+                    &noise,
+                    af::MatProp::NONE,
+                    af::MatProp::NONE,
+                ),
+            )
+        },
+        temperatures,
+        options,
+        None,
+        |_: &mut Progress<T, T, G::Item>| ControlFlow::Continue(()),
+        || {},
+        |_, _| {},
+    )
+}
+
+/// Running covariance estimate of column states, and the scaled Cholesky factor proposals
+/// are drawn through.
+struct Covariance<T: af::HasAfEnum> {
+    dims: u64,
+    scale: f64,
+    factor: af::Array<T>,
+    covariance: Option<af::Array<T>>,
+    sum: af::Array<T>,
+    scatter: af::Array<T>,
+    samples: u64,
+    steps: usize,
+}
+
+impl<T: Element + af::FloatingPoint> Covariance<T> {
+    fn new(dims: u64, adaptation: &CovarianceAdaptation) -> Self {
+        let scale = adaptation
+            .scale
+            .map_or(2.38 / (dims as f64).sqrt(), f64::from);
+        let identity = af::identity::<T>(dim4!(dims, dims));
+        Self {
+            dims,
+            scale,
+            factor: af::mul(
+                &identity,
+                &T::fromf64(f64::from(adaptation.initial_scale)),
+                false,
+            ),
+            covariance: None,
+            sum: af::constant(T::fromf64(0.0), dim4!(dims)),
+            scatter: af::constant(T::fromf64(0.0), dim4!(dims, dims)),
+            samples: 0,
+            steps: 0,
+        }
+    }
+
+    /// Accumulate the chains `x`, one per column, and refresh the factor when an update is due.
+    fn record(&mut self, x: &af::Array<T>, adaptation: &CovarianceAdaptation) {
+        let ones = af::constant(T::fromf64(1.0), dim4!(x.dims()[1]));
+        self.sum += af::matmul(x, &ones, af::MatProp::NONE, af::MatProp::NONE);
+        self.scatter += af::matmul(x, x, af::MatProp::NONE, af::MatProp::TRANS);
+        self.samples += x.dims()[1];
+        self.steps += 1;
+        if self.steps % adaptation.update_interval.max(1) == 0 {
+            self.update(adaptation);
+        }
+    }
+
+    fn update(&mut self, adaptation: &CovarianceAdaptation) {
+        let samples = T::fromf64(self.samples as f64);
+        let mean = af::div(&self.sum, &samples, false);
+        let window = af::div(&self.scatter, &samples, false)
+            - af::matmul(&mean, &mean, af::MatProp::NONE, af::MatProp::TRANS);
+        let covariance = match &self.covariance {
+            Some(previous) => {
+                let memory = f64::from(adaptation.memory);
+                af::mul(previous, &T::fromf64(memory), false)
+                    + af::mul(&window, &T::fromf64(1.0 - memory), false)
+            }
+            None => window,
+        };
+
+        let regularization = af::mul(
+            &af::identity::<T>(dim4!(self.dims, self.dims)),
+            &T::fromf64(f64::from(adaptation.regularization)),
+            false,
+        );
+        let (lower, info) = af::cholesky(&(&covariance + regularization), false);
+        if info == 0 {
+            self.factor = af::mul(&lower, &T::fromf64(self.scale), false);
+        }
+        self.covariance = Some(covariance);
+        self.sum = af::constant(T::fromf64(0.0), dim4!(self.dims));
+        self.scatter = af::constant(T::fromf64(0.0), dim4!(self.dims, self.dims));
+        self.samples = 0;
+    }
+}
+
+/// Like [`minimize_numeric_with`], reporting the device arrays to an observer after every
+/// temperature, without copying them to the host.
+///
+/// The observer is called once the chains have migrated; returning [`ControlFlow::Break`]
+/// stops the run with [`Termination::Observer`], skipping the quench.
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `options.k` is not positive (must be > 0.0)
+/// * If `options.batch_dim` is not below 4, or the extent of `start` along it is neither 1
+///   nor the number of chains
+pub fn minimize_numeric_observed<T, E, F, G, O>(
+    start: &af::Array<T>,
+    energy: E,
+    mut neighbour_map: F,
+    temperatures: G,
+    options: &Options<T>,
+    observer: O,
+) -> ParsaResult<T>
+where
+    T: Element,
+    E: FnMut(&af::Array<T>) -> af::Array<T>,
+    F: FnMut(&af::Array<T>) -> af::Array<T>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+    O: FnMut(&mut Progress<T, T, G::Item>) -> ControlFlow<()>,
+{
+    anneal(
+        start,
+        energy,
+        |x: &af::Array<T>, _: &af::Array<T>| options.project(neighbour_map(x)),
+        temperatures,
+        options,
+        None,
+        observer,
+        || {},
+        |_, _| {},
+    )
+}
+
+/// Per-temperature snapshot of a run, yielded by [`minimize_numeric_stream`].
+#[cfg(feature = "async")]
+#[derive(Clone)]
+pub struct Snapshot<T: Element = f32> {
+    /// Index of the temperature that just finished.
+    pub step: usize,
+    /// Current state of every chain, batched along [`Options::batch_dim`].
+    pub states: af::Array<T>,
+    /// Current energy of every chain.
+    pub energies: af::Array<T>,
+    /// Lowest energy seen so far by any chain.
+    pub best_energy: T,
+    /// Mean of the current energies.
+    pub mean_energy: T,
+    /// Proposals accepted over all chains at this temperature.
+    pub accepted: u64,
+}
+
+#[cfg(feature = "async")]
+impl<T: Element> std::fmt::Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("step", &self.step)
+            .field("states", &format_args!("Array {}", self.states.dims()))
+            .field("energies", &format_args!("Array {}", self.energies.dims()))
+            .field("best_energy", &self.best_energy)
+            .field("mean_energy", &self.mean_energy)
+            .field("accepted", &self.accepted)
+            .finish()
+    }
+}
+
+/// Like [`minimize_numeric_with`], running on a thread of its own and streaming a
+/// [`Snapshot`] after every temperature, so async applications can follow a run without
+/// blocking on it.
+///
+/// The run uses the device that is active when this is called. Dropping the stream stops the
+/// run with [`Termination::Observer`] at the end of the current temperature; otherwise the
+/// stream ends with the run. A quench, if any, is not reported.
+#[cfg(feature = "async")]
+pub fn minimize_numeric_stream<T, E, F, G>(
+    start: &af::Array<T>,
+    energy: E,
+    neighbour_map: F,
+    temperatures: G,
+    options: &Options<T>,
+) -> impl futures::Stream<Item = Snapshot<T>>
+where
+    T: Element + Send + 'static,
+    E: FnMut(&af::Array<T>) -> af::Array<T> + Send + 'static,
+    F: FnMut(&af::Array<T>) -> af::Array<T> + Send + 'static,
+    G: Iterator + Send + 'static,
+    G::Item: Temperature<T>,
+{
+    let (sender, receiver) = futures::channel::mpsc::unbounded();
+    let (start, options) = (start.clone(), options.clone());
+    let device = af::get_device();
+    std::thread::spawn(move || {
+        af::set_device(device);
+        minimize_numeric_observed(
+            &start,
+            energy,
+            neighbour_map,
+            temperatures,
+            &options,
+            |progress| {
+                let (best_energy, _) = af::min_all(progress.best_energies);
+                let (mean_energy, _) = af::mean_all(progress.energies);
+                let (accepted, _) = af::sum_all(progress.accepted);
+                let snapshot = Snapshot {
+                    step: progress.step,
+                    states: progress.states.clone(),
+                    energies: progress.energies.clone(),
+                    best_energy,
+                    mean_energy: T::fromf64(mean_energy),
+                    accepted: u64::from(accepted),
+                };
+                match sender.unbounded_send(snapshot) {
+                    Ok(()) => ControlFlow::Continue(()),
+                    Err(_) => ControlFlow::Break(()),
+                }
+            },
+        );
+    });
+    receiver
+}
+
+/// Performs data-parallel simulated annealing over integer-valued states, such as
+/// permutations, assignments or bitstrings, as configured by `options`.
+///
+/// Chains are accepted exactly as in [`minimize_numeric_with`], with energies of the
+/// floating-point type `T`; proposals come from a discrete operator such as
+/// [`crate::lsops::random_bit_flip`].
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `options.k` is not positive (must be > 0.0)
+/// * If `options.batch_dim` is not below 4, or the extent of `start` along it is neither 1
+///   nor the number of chains
+pub fn minimize_discrete<S, T, E, F, G>(
+    start: &af::Array<S>,
+    energy: E,
+    mut neighbour_map: F,
+    temperatures: G,
+    options: &Options<T>,
+) -> ParsaResult<T, S>
+where
+    S: af::HasAfEnum + af::IntegralType + af::ImplicitPromote<S>,
+    T: Element,
+    E: FnMut(&af::Array<S>) -> af::Array<T>,
+    F: FnMut(&af::Array<S>) -> af::Array<S>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+{
+    anneal(
+        start,
+        energy,
+        |x: &af::Array<S>, _: &af::Array<T>| neighbour_map(x),
+        temperatures,
+        options,
+        None,
+        |_: &mut Progress<T, S, G::Item>| ControlFlow::Continue(()),
+        || {},
+        |_, _| {},
+    )
+}
+
+/// Performs data-parallel simulated annealing with proposals from a [`LocalSearchOp`], such as
+/// a closure, a boxed operator or [`crate::lsops::Mixture`], over states of any element type.
+///
+/// Every proposal gets a [`Context`] with the current temperature, zero during the greedy
+/// quench and `None` for per-chain temperatures, an engine and a host generator seeded from [`Options::seed`] (or drawn from the
+/// default engine without one), and, with `adaptation`, the per-chain step scales,
+/// adapted as in [`minimize_numeric_adaptive`], both converted to `f32`. After every
+/// acceptance test the operator receives the chains' energy decreases, likewise in `f32`,
+/// through [`LocalSearchOp::feedback_batch`]. Otherwise
+/// behaves like [`minimize_discrete`]; [`Options::projection`] is not applied.
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `options.k` is not positive (must be > 0.0)
+/// * If `options.batch_dim` is not below 4, or the extent of `start` along it is neither 1
+///   nor the number of chains
+pub fn minimize_op<S, T, E, P, G>(
+    start: &af::Array<S>,
+    energy: E,
+    operator: P,
+    temperatures: G,
+    options: &Options<T>,
+    adaptation: Option<&StepAdaptation>,
+) -> ParsaResult<T, S>
+where
+    S: af::HasAfEnum + af::ImplicitPromote<S>,
+    T: Element,
+    E: FnMut(&af::Array<S>) -> af::Array<T>,
+    P: LocalSearchOp<af::Array<S>>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+{
+    // Offset from the seed of the acceptance draws, so that proposals do not mirror them.
+    let engine = options
+        .seed
+        .map(|seed| af::RandomEngine::new(af::DEFAULT_RANDOM_ENGINE, Some(seed ^ u64::MAX)));
+    // Without a seed the host generator is seeded from the default engine, like the chains.
+    let host_seed = options.seed.unwrap_or_else(|| {
+        let mut seed = [0u64];
+        af::random_uniform::<u64>(af::dim4!(1), &af::get_default_random_engine()).host(&mut seed);
+        seed[0]
+    });
+    let mut rng = StdRand::seed(host_seed);
+    let current = Cell::new(None);
+    // Proposals and feedback take turns at the operator.
+    let operator = RefCell::new(operator);
+    anneal(
+        start,
+        energy,
+        |x: &af::Array<S>, scale: &af::Array<T>| {
+            let scale = adaptation.is_some().then(|| scale.cast::<f32>());
+            let mut context = Context {
+                engine: engine.as_ref(),
+                temperature: current.get(),
+                scale: scale.as_ref(),
+                ..Context::new(&mut rng)
+            };
+            operator.borrow_mut().propose(x, &mut context)
+        },
+        temperatures.inspect(|temperature| current.set(temperature.value().map(|t| t as f32))),
+        options,
+        adaptation,
+        |_: &mut Progress<T, S, G::Item>| ControlFlow::Continue(()),
+        // The quench only accepts improvements, as at a temperature of zero.
+        || current.set(Some(0.0)),
+        |ex: &af::Array<T>, en: &af::Array<T>| {
+            operator
+                .borrow_mut()
+                .feedback_batch(&(ex - en).cast::<f32>());
+        },
+    )
+}
+
+/// Annealing loop shared by the numeric and discrete minimizers, adapting the per-chain step
+/// scales passed to the neighbour if `adaptation` is given. `on_quench` is called before the
+/// greedy quench, if there is one, and `on_proposal(ex, en)` after every acceptance test with
+/// the chains' energies and those of their proposals.
+#[allow(clippy::too_many_arguments)]
+fn anneal<S, T, E, F, G, O, Q, P>(
+    start: &af::Array<S>,
+    energy: E,
+    neighbour_map: F,
+    temperatures: G,
+    options: &Options<T>,
+    adaptation: Option<&StepAdaptation>,
+    observer: O,
+    on_quench: Q,
+    on_proposal: P,
+) -> ParsaResult<T, S>
+where
+    S: af::HasAfEnum,
+    T: Element,
+    E: FnMut(&af::Array<S>) -> af::Array<T>,
+    F: FnMut(&af::Array<S>, &af::Array<T>) -> af::Array<S>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+    O: FnMut(&mut Progress<T, S, G::Item>) -> ControlFlow<()>,
+    Q: FnOnce(),
+    P: FnMut(&af::Array<T>, &af::Array<T>),
+    S: af::ImplicitPromote<S>,
+{
+    match try_anneal(
+        start,
+        energy,
+        neighbour_map,
+        temperatures,
+        options,
+        adaptation,
+        observer,
+        on_quench,
+        on_proposal,
+        false,
+    ) {
+        Ok(result) => result,
+        Err(_) => unreachable!("initial energies are only checked when asked to"),
+    }
+}
+
+/// Like [`anneal`], failing with [`Error::NanInitialEnergy`] before the first step if
+/// `reject_nan` is set and any chain starts with a NaN energy.
+#[allow(clippy::too_many_arguments)]
+fn try_anneal<S, T, E, F, G, O, Q, P>(
+    start: &af::Array<S>,
+    mut energy: E,
+    mut neighbour_map: F,
+    temperatures: G,
+    options: &Options<T>,
+    adaptation: Option<&StepAdaptation>,
+    mut observer: O,
+    on_quench: Q,
+    mut on_proposal: P,
+    reject_nan: bool,
+) -> Result<ParsaResult<T, S>, Error>
+where
+    S: af::HasAfEnum,
+    T: Element,
+    E: FnMut(&af::Array<S>) -> af::Array<T>,
+    F: FnMut(&af::Array<S>, &af::Array<T>) -> af::Array<S>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+    O: FnMut(&mut Progress<T, S, G::Item>) -> ControlFlow<()>,
+    Q: FnOnce(),
+    P: FnMut(&af::Array<T>, &af::Array<T>),
+    S: af::ImplicitPromote<S>,
+{
+    let Options {
+        batch_size,
+        k,
+        batch_dim,
+        archive_size,
+        ..
+    } = *options;
+    let chain_dims = chain_dims(start, batch_dim, batch_size);
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    let mut estimate = |x: &af::Array<S>| {
+        let total = (1..samples).fold(energy(x), |total, _| total + energy(x));
+        let mean = if samples == 1 {
+            total
+        } else {
+            af::div(&total, &T::fromf64(samples as f64), false)
+        };
+        af::moddims(&mean, chain_dims)
+    };
+
+    let engine = options.engine();
+    let mut x = populate(start, batch_dim, chain_dims);
+    let mut ex = estimate(&x);
+    if reject_nan && af::any_true_all(&af::isnan(&ex)).0 {
+        return Err(Error::NanInitialEnergy);
+    }
+    let mut best_x = x.clone();
+    let mut best_ex = ex.clone();
+    let mut archive = None;
+    let mut acceptances = Vec::new();
+    let initial_scale = adaptation.map_or(1.0, |adaptation| adaptation.initial_scale);
+    let mut scale = af::constant(T::fromf64(f64::from(initial_scale)), chain_dims);
+
+    assert!(k.into() > 0.0, "Boltzmann constant must be positive");
+    // A chain's own constant `k_i` enters the Metropolis test by rescaling its energies by
+    // `k / k_i`, which leaves temperatures and greedy comparisons untouched.
+    let energy_scale = options.chain_k.as_ref().map(|chain_k| {
+        let (least, _) = af::min_all(chain_k);
+        assert!(least.into() > 0.0, "Boltzmann constant must be positive");
+        af::div(&k, &af::moddims(chain_k, chain_dims), false)
+    });
+
+    let started = Instant::now();
+    let mut since_reevaluation = 0;
+    let mut processed = 0;
+    let mut steps = 0;
+    let mut termination = Termination::ScheduleExhausted;
+    'schedule: for temperature in temperatures {
+        if options.stops_at(&temperature) {
+            termination = Termination::ZeroTemperature;
+            break;
+        }
+        let chain_length = options.chain_length_at(processed);
+        processed += 1;
+        let mut accepted = af::constant(0u32, chain_dims);
         for _chain_idx in 0..chain_length {
-            let n = neighbour_map(&x);
-            let en = energy(&n);
-            let logprobs = (&ex - &en) / (k * temperature);
+            if let Some(resampling) = options.resampling {
+                since_reevaluation += 1;
+                if resampling.reevaluate_interval > 0
+                    && since_reevaluation >= resampling.reevaluate_interval
+                {
+                    since_reevaluation = 0;
+                    ex = estimate(&x);
+                }
+            }
+
+            let n = neighbour_map(&x, &scale);
+            let en = estimate(&n);
+            let diffs = match &energy_scale {
+                Some(scale) => accept(
+                    &(&ex * scale),
+                    &(&en * scale),
+                    k,
+                    &temperature,
+                    chain_dims,
+                    &engine,
+                ),
+                None => accept(&ex, &en, k, &temperature, chain_dims, &engine),
+            };
+            on_proposal(&ex, &en);
+            x = af::select(&n, &diffs, &x);
+            ex = af::select(&en, &diffs, &ex);
+            track_best(&x, &ex, &mut best_x, &mut best_ex);
+            accepted += diffs.cast::<u32>();
+            steps += 1;
+            options.flush(steps, [&x, &best_x], [&ex, &best_ex]);
+
+            // Migrating could evaluate the whole batch again after the run was told to stop.
+            if let Some(reason) = options.interruption(started) {
+                acceptances.push(af::sum(&af::flat(&accepted), 0));
+                termination = reason;
+                break 'schedule;
+            }
+        }
+        acceptances.push(af::sum(&af::flat(&accepted), 0));
+        if let Some(adaptation) = adaptation {
+            scale = adapt(&scale, &accepted, chain_length, adaptation);
+        }
+
+        if archive_size > 0 {
+            archive = Some(archived(
+                archive,
+                &best_x,
+                &best_ex,
+                archive_size,
+                batch_dim,
+            ));
+        }
+        if let Some(reason) = options.convergence(&ex, &best_ex) {
+            termination = reason;
+            migrate(
+                &mut x,
+                &mut ex,
+                options.migration,
+                batch_dim,
+                &engine,
+                &mut estimate,
+            );
+            break;
+        }
+        migrate(
+            &mut x,
+            &mut ex,
+            options.migration,
+            batch_dim,
+            &engine,
+            &mut estimate,
+        );
+
+        let mut progress = Progress {
+            step: processed - 1,
+            temperature: &temperature,
+            states: &mut x,
+            energies: &mut ex,
+            best_energies: &best_ex,
+            accepted: &accepted,
+            proposed: chain_length,
+        };
+        if observer(&mut progress).is_break() {
+            termination = Termination::Observer;
+            break;
+        }
+    }
+
+    if matches!(
+        termination,
+        Termination::ScheduleExhausted | Termination::ZeroTemperature | Termination::Converged
+    ) && options.quench_iterations > 0
+    {
+        on_quench();
+        for _ in 0..options.quench_iterations {
+            let n = neighbour_map(&x, &scale);
+            let en = estimate(&n);
+            let improved = af::lt(&en, &ex, false);
+            on_proposal(&ex, &en);
+            x = af::select(&n, &improved, &x);
+            ex = af::select(&en, &improved, &ex);
+            track_best(&x, &ex, &mut best_x, &mut best_ex);
+            steps += 1;
+            options.flush(steps, [&x, &best_x], [&ex, &best_ex]);
+        }
+        migrate(
+            &mut x,
+            &mut ex,
+            options.migration,
+            batch_dim,
+            &engine,
+            &mut estimate,
+        );
+    }
+    if archive_size > 0 {
+        archive = Some(archived(
+            archive,
+            &best_x,
+            &best_ex,
+            archive_size,
+            batch_dim,
+        ));
+    }
+
+    Ok(ParsaResult {
+        archive,
+        acceptances: host_counts(&acceptances),
+        ..summarize(
+            x,
+            &ex,
+            &best_x,
+            &best_ex,
+            batch_dim,
+            processed,
+            steps,
+            termination,
+        )
+    })
+}
+
+/// Copy single-element counts to the host.
+fn host_counts(counts: &[af::Array<u32>]) -> Vec<u64> {
+    counts
+        .iter()
+        .map(|count| {
+            let mut host = [0u32];
+            count.host(&mut host);
+            u64::from(host[0])
+        })
+        .collect()
+}
+
+/// Grow or shrink each chain's step `scale` from its number of `accepted` moves out of
+/// `proposed`, as described by `adaptation`.
+fn adapt<T: Element>(
+    scale: &af::Array<T>,
+    accepted: &af::Array<u32>,
+    proposed: usize,
+    adaptation: &StepAdaptation,
+) -> af::Array<T> {
+    let factor = T::fromf64(f64::from(adaptation.factor));
+    let rate = accepted.cast::<f32>() / proposed as f32;
+    let shrunk = af::select(
+        &af::div(scale, &factor, false),
+        &af::lt(&rate, &adaptation.target_acceptance, false),
+        scale,
+    );
+    let adapted = af::select(
+        &af::mul(scale, &factor, false),
+        &af::gt(&rate, &adaptation.target_acceptance, false),
+        &shrunk,
+    );
+    af::clamp(
+        &adapted,
+        &T::fromf64(f64::from(adaptation.min_scale)),
+        &T::fromf64(f64::from(adaptation.max_scale)),
+        false,
+    )
+}
+
+/// Merge the chains `x`, with energies `ex`, into `archive`, keeping its `size` best distinct
+/// states.
+fn archived<S, T>(
+    archive: Option<Archive<T, S>>,
+    x: &af::Array<S>,
+    ex: &af::Array<T>,
+    size: u32,
+    batch_dim: usize,
+) -> Archive<T, S>
+where
+    S: af::HasAfEnum + af::ImplicitPromote<S>,
+    T: Element,
+{
+    let (states, energies) = match archive {
+        Some(archive) => (
+            af::join(batch_dim as i32, &archive.states, x),
+            af::join(0, &af::flat(&archive.energies), &af::flat(ex)),
+        ),
+        None => (x.clone(), af::flat(ex)),
+    };
+
+    // Each state keeps only its lowest-energy copy. States are compared against every
+    // better candidate, since noisy estimates can give copies of a state different energies
+    // and distinct states of equal energy can interleave.
+    let (sorted, order) = af::sort_index(&energies, 0, true);
+    let states = af::lookup(&states, &order, batch_dim as i32);
+    let duplicate = repeated_columns(&chain_columns(&states, batch_dim));
+    let (duplicates, _) = af::count_all(&duplicate);
+
+    let kept = (duplicate.elements() as u64 - duplicates).min(u64::from(size));
+    let index = lowest(&af::selectl(f64::INFINITY, &duplicate, &sorted), kept);
+    Archive {
+        states: af::lookup(&states, &index, batch_dim as i32),
+        energies: af::moddims(&af::lookup(&sorted, &index, 0), dim4!(1, kept)),
+    }
+}
+
+/// Number of columns [`repeated_columns`] compares against all others at once.
+const DEDUPLICATION_BLOCK: u64 = 256;
+
+/// Flag, as a column, every column of `columns` equal to an earlier one. Columns are compared
+/// a block at a time, so the comparisons take at most `DEDUPLICATION_BLOCK` times the memory
+/// of `columns`.
+fn repeated_columns<S>(columns: &af::Array<S>) -> af::Array<bool>
+where
+    S: af::HasAfEnum + af::ImplicitPromote<S>,
+{
+    let (rows, count) = (columns.dims()[0], columns.dims()[1]);
+    (0..count)
+        .step_by(DEDUPLICATION_BLOCK as usize)
+        .map(|first| {
+            let block = DEDUPLICATION_BLOCK.min(count - first);
+            let last = (first + block - 1) as i64;
+            let candidates = af::moddims(
+                &af::cols(columns, first as i64, last),
+                dim4!(rows, 1, block),
+            );
+            let equal = af::all_true(&af::eq(columns, &candidates, true), 0);
+            let pairs = dim4!(1, count, block);
+            let earlier = af::lt(
+                &af::range::<u32>(pairs, 1),
+                &(af::range::<u32>(pairs, 2) + first as u32),
+                false,
+            );
+            af::flat(&af::any_true(&af::and(&equal, &earlier, false), 1))
+        })
+        .reduce(|flags, block| af::join(0, &flags, &block))
+        .expect("there is at least one column")
+}
+
+/// Largest selection `af::topk` accepts.
+const TOPK_LIMIT: u64 = 256;
+
+/// Indices of the `k` lowest elements of the column `values`, lowest first. Selections too
+/// large for `af::topk` sort the whole column instead.
+fn lowest<T: Element>(values: &af::Array<T>, k: u64) -> af::Array<u32> {
+    if k <= TOPK_LIMIT {
+        let (_, index) = af::topk(values, k as u32, 0, af::TopkFn::MIN);
+        index
+    } else {
+        let (_, order) = af::sort_index(values, 0, true);
+        af::rows(&order, 0, k as i64 - 1)
+    }
+}
+
+/// View the chains of `x`, batched along `batch_dim`, as the columns of a matrix.
+fn chain_columns<S: af::HasAfEnum>(x: &af::Array<S>, batch_dim: usize) -> af::Array<S> {
+    let dims = x.dims();
+    let chains = dims[batch_dim];
+    let mut axes: Vec<u64> = (0..4).filter(|&axis| axis != batch_dim as u64).collect();
+    axes.push(batch_dim as u64);
+    let reordered = af::reorder_v2(x, axes[0], axes[1], Some(axes[2..].to_vec()));
+    af::moddims(&reordered, dim4!(dims.elements() / chains, chains))
+}
+
+/// Dimensions of the per-chain energies of a batch of `chains` chains along `batch_dim`,
+/// started from `start`. Energies are kept with the chains along the batch dimension, so that
+/// acceptance masks broadcast over the remaining dimensions of the states.
+fn chain_dims<S: af::HasAfEnum>(start: &af::Array<S>, batch_dim: usize, chains: u64) -> af::Dim4 {
+    assert!(
+        batch_dim < 4 && [1, chains].contains(&start.dims()[batch_dim]),
+        "Start state must have extent 1 or the number of chains along the batch dimension"
+    );
+    let mut dims = [1; 4];
+    dims[batch_dim] = chains;
+    af::Dim4::new(&dims)
+}
+
+/// Initial states of the chains: `start` itself if it holds one state per chain, or copies of
+/// the single state it holds.
+fn populate<S: af::HasAfEnum>(
+    start: &af::Array<S>,
+    batch_dim: usize,
+    chain_dims: af::Dim4,
+) -> af::Array<S> {
+    if start.dims()[batch_dim] == chain_dims[batch_dim] {
+        start.clone()
+    } else {
+        af::tile(start, chain_dims)
+    }
+}
+
+/// Collect the final chains and the best state seen into a [`ParsaResult`].
+#[allow(clippy::too_many_arguments)]
+fn summarize<S: af::HasAfEnum, T: Element>(
+    x: af::Array<S>,
+    ex: &af::Array<T>,
+    best_x: &af::Array<S>,
+    best_ex: &af::Array<T>,
+    batch_dim: usize,
+    temperatures: usize,
+    steps: usize,
+    termination: Termination,
+) -> ParsaResult<T, S> {
+    let (_, _, best_index) = af::imin_all(ex);
+    let (mean_energy, _) = af::mean_all(ex);
+    let (best_energy, _, best_chain) = af::imin_all(best_ex);
+    ParsaResult {
+        states: x,
+        energies: af::moddims(ex, dim4!(1, ex.elements() as u64)),
+        best_index: best_index as usize,
+        best_state: af::lookup(
+            best_x,
+            &af::Array::new(&[best_chain], dim4!(1)),
+            batch_dim as i32,
+        ),
+        best_energy,
+        mean_energy: T::fromf64(mean_energy),
+        temperatures,
+        steps,
+        termination,
+        acceptances: Vec::new(),
+        archive: None,
+    }
+}
+
+/// Performs replica-exchange Monte Carlo (parallel tempering) over a ladder of fixed
+/// temperatures.
+///
+/// Every rung of `ladder` holds `options.batch_size` replicas, so the batch has
+/// `ladder.len() * options.batch_size` chains, each sampling at its rung's temperature. After
+/// every `options.chain_length` steps, replicas on adjacent rungs attempt to swap states,
+/// accepting with probability `min(1, exp((1/kT_i - 1/kT_j) (E_i - E_j)))`; even and odd pairs
+/// of rungs alternate between rounds. This is repeated `exchanges` times, and the result
+/// reports each round as a processed temperature.
+///
+/// The chains are ordered rung by rung within each replica set, so `states` holds the replica
+/// of set `s` on rung `r` at index `s * ladder.len() + r`. [`Options::time_limit`],
+/// [`Options::cancel`], [`Options::resampling`], [`Options::seed`] and [`Options::batch_dim`]
+/// apply as for [`minimize_numeric_with`]; the exchanges replace [`Options::migration`] and
+/// there is no quench.
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `options.k` is not positive (must be > 0.0)
+/// * If `options.chain_k` is set, since the rungs already set each replica's temperature
+/// * If `ladder` is empty or holds a non-positive temperature
+/// * If `options.batch_dim` is not below 4, or the extent of `start` along it is neither 1
+///   nor the number of chains
+pub fn minimize_replica_exchange<T, E, F>(
+    start: &af::Array<T>,
+    mut energy: E,
+    mut neighbour_map: F,
+    ladder: &[T],
+    exchanges: usize,
+    options: &Options<T>,
+) -> ParsaResult<T>
+where
+    T: Element,
+    E: FnMut(&af::Array<T>) -> af::Array<T>,
+    F: FnMut(&af::Array<T>) -> af::Array<T>,
+{
+    let Options {
+        batch_size,
+        chain_length,
+        k,
+        batch_dim,
+        ..
+    } = *options;
+    assert!(k.into() > 0.0, "Boltzmann constant must be positive");
+    assert!(
+        options.chain_k.is_none(),
+        "Replica exchange does not support per-chain Boltzmann constants"
+    );
+    assert!(
+        !ladder.is_empty() && ladder.iter().all(|&t| t.into() > 0.0),
+        "Temperature ladder must be non-empty and positive"
+    );
+
+    let rungs = ladder.len();
+    let chains = rungs as u64 * batch_size;
+    let chain_dims = chain_dims(start, batch_dim, chains);
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    let mut estimate = |x: &af::Array<T>| {
+        let total = (1..samples).fold(energy(x), |total, _| total + energy(x));
+        let mean = if samples == 1 {
+            total
+        } else {
+            af::div(&total, &T::fromf64(samples as f64), false)
+        };
+        af::moddims(&mean, chain_dims)
+    };
+
+    // Inverse temperatures of every chain, computed in double precision.
+    let betas: Vec<T> = (0..chains as usize)
+        .map(|chain| T::fromf64(1.0 / (k.into() * ladder[chain % rungs].into())))
+        .collect();
+    let betas = af::Array::new(&betas, chain_dims);
+
+    let engine = options.engine();
+    let mut x = populate(start, batch_dim, chain_dims);
+    let mut ex = estimate(&x);
+    let mut best_x = x.clone();
+    let mut best_ex = ex.clone();
+
+    let started = Instant::now();
+    let mut since_reevaluation = 0;
+    let mut rounds = 0;
+    let mut steps = 0;
+    let mut termination = Termination::ScheduleExhausted;
+    'exchanges: for round in 0..exchanges {
+        rounds += 1;
+        for _ in 0..chain_length {
+            if let Some(resampling) = options.resampling {
+                since_reevaluation += 1;
+                if resampling.reevaluate_interval > 0
+                    && since_reevaluation >= resampling.reevaluate_interval
+                {
+                    since_reevaluation = 0;
+                    ex = estimate(&x);
+                }
+            }
+
+            let n = options.project(neighbour_map(&x));
+            let en = estimate(&n);
             let diffs = af::gt(
-                &af::exp(&logprobs),
-                &af::randu::<f32>(dim4!(1, batch_size)),
+                &((&ex - &en) * &betas),
+                &af::log(&af::random_uniform::<T>(chain_dims, &engine)),
                 true,
             );
             x = af::select(&n, &diffs, &x);
             ex = af::select(&en, &diffs, &ex);
+            track_best(&x, &ex, &mut best_x, &mut best_ex);
+            steps += 1;
+            options.flush(steps, [&x, &best_x], [&ex, &best_ex]);
+
+            if let Some(reason) = options.interruption(started) {
+                termination = reason;
+                break 'exchanges;
+            }
+        }
+
+        exchange(
+            &mut x,
+            &mut ex,
+            &betas,
+            rungs,
+            round % 2,
+            batch_dim,
+            &engine,
+        );
+    }
+
+    summarize(
+        x,
+        &ex,
+        &best_x,
+        &best_ex,
+        batch_dim,
+        rounds,
+        steps,
+        termination,
+    )
+}
+
+/// Attempt state swaps between the pairs of adjacent rungs `(i, i + 1)` with `i` of the given
+/// `parity`, in every replica set.
+fn exchange<T: Element>(
+    x: &mut af::Array<T>,
+    ex: &mut af::Array<T>,
+    betas: &af::Array<T>,
+    rungs: usize,
+    parity: usize,
+    batch_dim: usize,
+    engine: &af::RandomEngine,
+) {
+    let chains = ex.elements();
+    let partner_of = |rung: usize| {
+        if rung % 2 == parity {
+            if rung + 1 < rungs {
+                rung + 1
+            } else {
+                rung
+            }
+        } else {
+            rung.saturating_sub(1)
+        }
+    };
+    let partners: Vec<u32> = (0..chains)
+        .map(|chain| (chain - chain % rungs + partner_of(chain % rungs)) as u32)
+        .collect();
+    // Both replicas of a pair share the uniform draw of the lower one.
+    let lower: Vec<u32> = (0..chains)
+        .map(|chain| chain.min(partners[chain] as usize) as u32)
+        .collect();
+    let partners = af::Array::new(&partners, dim4!(chains as u64));
+    let lower = af::Array::new(&lower, dim4!(chains as u64));
+
+    let flat_ex = af::flat(ex);
+    let flat_betas = af::flat(betas);
+    let log_ratio = (&flat_betas - &af::lookup(&flat_betas, &partners, 0))
+        * (&flat_ex - &af::lookup(&flat_ex, &partners, 0));
+    let draws = af::lookup(
+        &af::random_uniform::<T>(dim4!(chains as u64), engine),
+        &lower,
+        0,
+    );
+    let swapped = af::gt(&log_ratio, &af::log(&draws), false);
+    let sources = af::select(
+        &partners,
+        &swapped,
+        &af::range::<u32>(dim4!(chains as u64), 0),
+    );
+
+    *x = af::lookup(x, &sources, batch_dim as i32);
+    *ex = af::lookup(ex, &sources, batch_dim as i32);
+}
+
+/// Performs data-parallel simulated annealing on batches too large to anneal at once, taking
+/// `tile_size` chains at a time through every temperature.
+///
+/// The states and energies of all `options.batch_size` chains stay on the device, but
+/// proposals, energy evaluations and acceptance tests, which usually dominate device memory,
+/// only ever cover a single tile. Once every tile has been through a temperature, the chains
+/// migrate over the whole batch as configured by [`Options::migration`], so tiles share their
+/// best states. [`Options::chain_lengths`], [`Options::chain_k`], [`Options::time_limit`],
+/// [`Options::cancel`], [`Options::seed`], [`Options::convergence`],
+/// [`Options::target_energy`] and [`Options::batch_dim`] apply as for
+/// [`minimize_numeric_with`]; resampling, the quench and the archive are not supported. Each
+/// tile anneals with the slices of per-chain temperatures and Boltzmann constants that belong
+/// to its chains. An interruption stops the run in the middle of a tile.
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `options.k`, or any of `options.chain_k`, is not positive
+///   (must be > 0.0)
+/// * If `tile_size` is zero
+/// * If `options.batch_dim` is not below 4, or the extent of `start` along it is neither 1
+///   nor the number of chains
+pub fn minimize_numeric_chunked<T, E, F, G>(
+    start: &af::Array<T>,
+    mut energy: E,
+    mut neighbour_map: F,
+    temperatures: G,
+    options: &Options<T>,
+    tile_size: u64,
+) -> ParsaResult<T>
+where
+    T: Element,
+    E: FnMut(&af::Array<T>) -> af::Array<T>,
+    F: FnMut(&af::Array<T>) -> af::Array<T>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+{
+    let Options {
+        batch_size,
+        k,
+        batch_dim,
+        ..
+    } = *options;
+    assert!(k.into() > 0.0, "Boltzmann constant must be positive");
+    assert!(tile_size > 0, "Tile size must be positive");
+
+    let chain_dims = chain_dims(start, batch_dim, batch_size);
+    // As in `anneal`, a chain's own constant rescales its energies by `k / k_i`.
+    let energy_scale = options.chain_k.as_ref().map(|chain_k| {
+        let (least, _) = af::min_all(chain_k);
+        assert!(least.into() > 0.0, "Boltzmann constant must be positive");
+        af::div(&k, &af::moddims(chain_k, chain_dims), false)
+    });
+    let tiles: Vec<(u64, [af::Seq<f64>; 4])> = (0..batch_size)
+        .step_by(tile_size as usize)
+        .map(|first| {
+            let chains = tile_size.min(batch_size - first);
+            let mut seqs = [af::Seq::default(); 4];
+            seqs[batch_dim] = af::Seq::new(first as f64, (first + chains - 1) as f64, 1.0);
+            (chains, seqs)
+        })
+        .collect();
+    let tile_dims = |chains: u64| {
+        let mut dims = [1; 4];
+        dims[batch_dim] = chains;
+        af::Dim4::new(&dims)
+    };
+    let evaluate = |energy: &mut E, x: &af::Array<T>| {
+        let mut ex = af::constant(T::fromf64(0.0), chain_dims);
+        for (chains, seqs) in &tiles {
+            let tile_ex = af::moddims(&energy(&af::index(x, seqs)), tile_dims(*chains));
+            af::assign_seq(&mut ex, seqs, &tile_ex);
+        }
+        ex
+    };
+
+    let engine = options.engine();
+    let mut x = populate(start, batch_dim, chain_dims);
+    let mut ex = evaluate(&mut energy, &x);
+    let mut best_x = x.clone();
+    let mut best_ex = ex.clone();
+    let mut acceptances = Vec::new();
+
+    let started = Instant::now();
+    let mut processed = 0;
+    let mut steps = 0;
+    let mut termination = Termination::ScheduleExhausted;
+    'schedule: for temperature in temperatures {
+        if options.stops_at(&temperature) {
+            termination = Termination::ZeroTemperature;
+            break;
+        }
+        let chain_length = options.chain_length_at(processed);
+        processed += 1;
+        let mut accepted = af::constant(0u32, dim4!(1));
+        for (chains, seqs) in &tiles {
+            let tile_dims = tile_dims(*chains);
+            let tile_temperature = temperature.tile(chain_dims, seqs);
+            let tile_scale = energy_scale.as_ref().map(|scale| af::index(scale, seqs));
+            let mut tile_x = af::index(&x, seqs);
+            let mut tile_ex = af::index(&ex, seqs);
+            let mut tile_best_x = af::index(&best_x, seqs);
+            let mut tile_best_ex = af::index(&best_ex, seqs);
+            let mut interrupted = None;
+            let mut taken = 0;
+            for step in 1..=chain_length {
+                taken = step;
+                let n = options.project(neighbour_map(&tile_x));
+                let en = af::moddims(&energy(&n), tile_dims);
+                let diffs = match &tile_scale {
+                    Some(scale) => accept(
+                        &(&tile_ex * scale),
+                        &(&en * scale),
+                        k,
+                        &tile_temperature,
+                        tile_dims,
+                        &engine,
+                    ),
+                    None => accept(&tile_ex, &en, k, &tile_temperature, tile_dims, &engine),
+                };
+                tile_x = af::select(&n, &diffs, &tile_x);
+                tile_ex = af::select(&en, &diffs, &tile_ex);
+                track_best(&tile_x, &tile_ex, &mut tile_best_x, &mut tile_best_ex);
+                accepted += af::sum(&af::flat(&diffs.cast::<u32>()), 0);
+                options.flush(
+                    steps + step,
+                    [&tile_x, &tile_best_x],
+                    [&tile_ex, &tile_best_ex],
+                );
+
+                interrupted = options.interruption(started);
+                if interrupted.is_some() {
+                    break;
+                }
+            }
+            af::assign_seq(&mut x, seqs, &tile_x);
+            af::assign_seq(&mut ex, seqs, &tile_ex);
+            af::assign_seq(&mut best_x, seqs, &tile_best_x);
+            af::assign_seq(&mut best_ex, seqs, &tile_best_ex);
+
+            if let Some(reason) = interrupted {
+                acceptances.push(accepted);
+                steps += taken;
+                termination = reason;
+                break 'schedule;
+            }
         }
+        steps += chain_length;
+        acceptances.push(accepted);
 
-        let (index, _min_energy) = af::imin(&ex, 1);
-        let selected_xs = af::lookup(&x, &index, 1);
-        x = af::tile(&selected_xs, tile_dim);
+        if let Some(reason) = options.convergence(&ex, &best_ex) {
+            termination = reason;
+            migrate(
+                &mut x,
+                &mut ex,
+                options.migration,
+                batch_dim,
+                &engine,
+                |x: &af::Array<T>| evaluate(&mut energy, x),
+            );
+            break;
+        }
+        migrate(
+            &mut x,
+            &mut ex,
+            options.migration,
+            batch_dim,
+            &engine,
+            |x: &af::Array<T>| evaluate(&mut energy, x),
+        );
+    }
+
+    ParsaResult {
+        acceptances: host_counts(&acceptances),
+        ..summarize(
+            x,
+            &ex,
+            &best_x,
+            &best_ex,
+            batch_dim,
+            processed,
+            steps,
+            termination,
+        )
+    }
+}
+
+/// Performs data-parallel simulated annealing with the batch split across every available
+/// arrayfire device.
+///
+/// Each device anneals its share of the `options.batch_size` chains on its own thread, and the
+/// devices meet after every temperature to merge their best states: with
+/// [`Migration::Collapse`] every chain restarts from the best state over all devices, with
+/// [`Migration::Independent`] nothing is exchanged, and with the other policies each device
+/// first migrates its own chains, then replaces its worst chain by the overall best.
+/// [`Options::time_limit`], [`Options::cancel`] and [`Options::batch_dim`] apply as for
+/// [`minimize_numeric_with`], and [`Options::seed`] seeds the engine of the `i`-th device with
+/// `seed + i`, wrapping around past `u64::MAX`; resampling, the quench and [`Options::chain_k`]
+/// are not supported.
+///
+/// The returned arrays live on the first device, which is left active, and `states` holds the
+/// chains of the devices one after another. The device threads share the energy and neighbour
+/// functions, which must therefore be `Fn + Sync` rather than `FnMut`.
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `options.k` is not positive (must be > 0.0)
+/// * If `options.chain_k` is set
+/// * If `options.batch_dim` is not below 4, or the extent of `start` along it is neither 1
+///   nor the number of chains
+pub fn minimize_numeric_multi_device<T, E, F, G>(
+    start: &af::Array<T>,
+    energy: E,
+    neighbour_map: F,
+    temperatures: G,
+    options: &Options<T>,
+) -> ParsaResult<T>
+where
+    T: Element + Send + Sync,
+    E: Fn(&af::Array<T>) -> af::Array<T> + Sync,
+    F: Fn(&af::Array<T>) -> af::Array<T> + Sync,
+    G: Iterator<Item = T>,
+{
+    let Options {
+        batch_size,
+        k,
+        batch_dim,
+        ..
+    } = *options;
+    assert!(k.into() > 0.0, "Boltzmann constant must be positive");
+    assert!(
+        options.chain_k.is_none(),
+        "Multi-device annealing does not support per-chain Boltzmann constants"
+    );
+    let population = populate(start, batch_dim, chain_dims(start, batch_dim, batch_size));
+
+    let first_device = af::get_device();
+    let mut state_dims = *start.dims().get();
+    state_dims[batch_dim] = 1;
+    let state_dims = af::Dim4::new(&state_dims);
+
+    let devices = (af::device_count().max(1) as u64).min(batch_size.max(1));
+    let mut offset = 0;
+    let mut shards: Vec<Shard<T>> = (0..devices)
+        .map(|device| {
+            let chains = batch_size / devices + u64::from(device < batch_size % devices);
+            af::set_device(first_device);
+            let indices = af::range::<u32>(dim4!(chains), 0) + offset as u32;
+            let share = af::lookup(&population, &indices, batch_dim as i32);
+            let mut states = vec![T::fromf64(0.0); share.elements()];
+            share.host(&mut states);
+            offset += chains;
+
+            af::set_device(device as i32);
+            let x = af::Array::new(&states, share.dims());
+            let chain_dims = chain_dims(&x, batch_dim, chains);
+            let ex = af::moddims(&energy(&x), chain_dims);
+            let engine = match options.seed {
+                Some(seed) => af::RandomEngine::new(
+                    af::DEFAULT_RANDOM_ENGINE,
+                    Some(seed.wrapping_add(device)),
+                ),
+                None => af::get_default_random_engine(),
+            };
+            Shard {
+                device: device as i32,
+                chain_dims,
+                best_x: x.clone(),
+                best_ex: ex.clone(),
+                x,
+                ex,
+                engine,
+            }
+        })
+        .collect();
+
+    let started = Instant::now();
+    let mut processed = 0;
+    let mut steps = 0;
+    let mut termination = Termination::ScheduleExhausted;
+    for temperature in temperatures {
+        if options.stops_at(&temperature) {
+            termination = Termination::ZeroTemperature;
+            break;
+        }
+        let chain_length = options.chain_length_at(processed);
+        processed += 1;
+        let done = steps;
+        let outcomes: Vec<(usize, Option<Termination>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = shards
+                .iter_mut()
+                .map(|shard| {
+                    let (energy, neighbour_map) = (&energy, &neighbour_map);
+                    scope.spawn(move || {
+                        af::set_device(shard.device);
+                        for step in 1..=chain_length {
+                            let n = options.project(neighbour_map(&shard.x));
+                            let en = af::moddims(&energy(&n), shard.chain_dims);
+                            let diffs = accept(
+                                &shard.ex,
+                                &en,
+                                k,
+                                &temperature,
+                                shard.chain_dims,
+                                &shard.engine,
+                            );
+                            shard.x = af::select(&n, &diffs, &shard.x);
+                            shard.ex = af::select(&en, &diffs, &shard.ex);
+                            track_best(&shard.x, &shard.ex, &mut shard.best_x, &mut shard.best_ex);
+                            options.flush(
+                                done + step,
+                                [&shard.x, &shard.best_x],
+                                [&shard.ex, &shard.best_ex],
+                            );
+
+                            if let Some(reason) = options.interruption(started) {
+                                return (step, Some(reason));
+                            }
+                        }
+                        (chain_length, None)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Device worker panicked"))
+                .collect()
+        });
+
+        steps += outcomes.iter().map(|&(steps, _)| steps).max().unwrap_or(0);
+        merge(
+            &mut shards,
+            options.migration,
+            batch_dim,
+            state_dims,
+            &energy,
+        );
+        if let Some(reason) = outcomes.into_iter().find_map(|(_, reason)| reason) {
+            termination = reason;
+            break;
+        }
+    }
+
+    // Gather every shard on the first device.
+    let to_host = |array: &af::Array<T>| {
+        let mut data = vec![T::fromf64(0.0); array.elements()];
+        array.host(&mut data);
+        (data, array.dims())
+    };
+    let gathered: Vec<_> = shards
+        .iter()
+        .map(|shard| {
+            af::set_device(shard.device);
+            [
+                to_host(&shard.x),
+                to_host(&shard.ex),
+                to_host(&shard.best_x),
+                to_host(&shard.best_ex),
+            ]
+        })
+        .collect();
+    af::set_device(first_device);
+    let join = |field: usize| {
+        gathered
+            .iter()
+            .map(|arrays| af::Array::new(&arrays[field].0, arrays[field].1))
+            .reduce(|all, array| af::join(batch_dim as i32, &all, &array))
+            .expect("At least one device")
+    };
+
+    summarize(
+        join(0),
+        &join(1),
+        &join(2),
+        &join(3),
+        batch_dim,
+        processed,
+        steps,
+        termination,
+    )
+}
+
+/// Chains of one device in [`minimize_numeric_multi_device`].
+struct Shard<T: Element> {
+    device: i32,
+    chain_dims: af::Dim4,
+    x: af::Array<T>,
+    ex: af::Array<T>,
+    best_x: af::Array<T>,
+    best_ex: af::Array<T>,
+    engine: af::RandomEngine,
+}
+
+/// Share the best state over all shards according to `policy`.
+fn merge<T: Element, E: Fn(&af::Array<T>) -> af::Array<T>>(
+    shards: &mut [Shard<T>],
+    policy: Migration,
+    batch_dim: usize,
+    state_dims: af::Dim4,
+    energy: &E,
+) {
+    if policy == Migration::Independent {
+        return;
+    }
+
+    // Copy the best chain of every shard to the host, keeping the overall best.
+    let mut best: Option<(T, Vec<T>)> = None;
+    for shard in shards.iter() {
+        af::set_device(shard.device);
+        let (energy, _, index) = af::imin_all(&shard.ex);
+        if best.as_ref().is_none_or(|(best, _)| energy < *best) {
+            let chain = af::lookup(
+                &shard.x,
+                &af::Array::new(&[index], dim4!(1)),
+                batch_dim as i32,
+            );
+            let mut state = vec![T::fromf64(0.0); chain.elements()];
+            chain.host(&mut state);
+            best = Some((energy, state));
+        }
+    }
+    let Some((best_energy, best_state)) = best else {
+        return;
+    };
+
+    for shard in shards.iter_mut() {
+        af::set_device(shard.device);
+        let best_x = af::tile(&af::Array::new(&best_state, state_dims), shard.chain_dims);
+        let best_ex = af::constant(best_energy, shard.chain_dims);
+        if policy == Migration::Collapse {
+            shard.x = best_x;
+            shard.ex = best_ex;
+        } else {
+            migrate(
+                &mut shard.x,
+                &mut shard.ex,
+                policy,
+                batch_dim,
+                &shard.engine,
+                energy,
+            );
+            let (_, _, worst) = af::imax_all(&shard.ex);
+            let chains = af::range::<u32>(shard.chain_dims, batch_dim as i32);
+            let replaced = af::eq(&chains, &worst, false);
+            shard.x = af::select(&best_x, &replaced, &shard.x);
+            shard.ex = af::select(&best_ex, &replaced, &shard.ex);
+        }
+    }
+}
+
+/// Performs data-parallel simulated annealing lazily, yielding after every temperature of the
+/// schedule a `(temperature, best_state, best_energy)` triple, where `best_state` is the
+/// lowest-energy state seen so far by any chain, as a single column.
+///
+/// Counterpart of [`crate::seqsa::minimize_lazy`]: the run advances only as the iterator is
+/// consumed, so it can be monitored or stopped early with the usual iterator adaptors. The
+/// chains collapse onto the best one after every temperature, as in [`minimize_numeric`], and
+/// the iterator ends at the first non-positive temperature.
+///
+/// # Arguments
+///
+/// * `batch_size` - Number of parallel annealing chains to run
+/// * `chain_length` - Number of iterations at each temperature
+/// * `k` - Boltzmann constant used in acceptance probability calculation
+/// * `start` - Initial state.
+/// * `energy` - Function that computes the energy/cost of a state
+/// * `neighbour_map` - Function that generates neighboring states
+/// * `temperatures` - Iterator providing the temperature schedule
+///
+/// # Panics
+///
+/// * If the Boltzmann constant `k` is not positive (must be > 0.0)
+/// * If `start` has neither 1 nor `batch_size` columns
+pub fn minimize_numeric_lazy<'iter, T, E, F, G>(
+    batch_size: u64,
+    chain_length: usize,
+    k: T,
+    start: &af::Array<T>,
+    mut energy: E,
+    mut neighbour_map: F,
+    temperatures: G,
+) -> impl Iterator<Item = (T, af::Array<T>, T)> + 'iter
+where
+    T: Element + 'iter,
+    E: FnMut(&af::Array<T>) -> af::Array<T> + 'iter,
+    F: FnMut(&af::Array<T>) -> af::Array<T> + 'iter,
+    G: Iterator<Item = T> + 'iter,
+{
+    assert!(k.into() > 0.0, "Boltzmann constant must be positive");
+
+    let engine = af::get_default_random_engine();
+    let mut x = populate(start, 1, chain_dims(start, 1, batch_size));
+    let mut ex = energy(&x);
+    let mut best_x = x.clone();
+    let mut best_ex = ex.clone();
+
+    temperatures
+        .take_while(|&t| t.into() > 0.0)
+        .map(move |temperature| {
+            for _ in 0..chain_length {
+                let n = neighbour_map(&x);
+                let en = energy(&n);
+                let diffs = accept(&ex, &en, k, &temperature, dim4!(1, batch_size), &engine);
+                x = af::select(&n, &diffs, &x);
+                ex = af::select(&en, &diffs, &ex);
+                track_best(&x, &ex, &mut best_x, &mut best_ex);
+            }
+            migrate(
+                &mut x,
+                &mut ex,
+                Migration::Collapse,
+                1,
+                &engine,
+                &mut energy,
+            );
+
+            let (best_energy, _, best_chain) = af::imin_all(&best_ex);
+            (
+                temperature,
+                af::col(&best_x, i64::from(best_chain)),
+                best_energy,
+            )
+        })
+}
+
+/// Metropolis criterion for every chain at once: accept improvements, and uphill moves with
+/// probability `exp((ex - en) / (k * temperature))`.
+fn accept<T: Element>(
+    ex: &af::Array<T>,
+    en: &af::Array<T>,
+    k: T,
+    temperature: &impl Temperature<T>,
+    chain_dims: af::Dim4,
+    engine: &af::RandomEngine,
+) -> af::Array<bool> {
+    temperature.accept(ex, en, k, &af::random_uniform::<T>(chain_dims, engine))
+}
+
+/// Record, per chain, the states that improve on the best energy seen so far.
+fn track_best<S: af::HasAfEnum, T: Element>(
+    x: &af::Array<S>,
+    ex: &af::Array<T>,
+    best_x: &mut af::Array<S>,
+    best_ex: &mut af::Array<T>,
+) {
+    let improved = af::lt(ex, best_ex, false);
+    *best_x = af::select(x, &improved, best_x);
+    *best_ex = af::select(ex, &improved, best_ex);
+}
+
+/// Exchange states between chains, batched along `batch_dim`, according to `policy`.
+fn migrate<S, T, E>(
+    x: &mut af::Array<S>,
+    ex: &mut af::Array<T>,
+    policy: Migration,
+    batch_dim: usize,
+    engine: &af::RandomEngine,
+    mut energy: E,
+) where
+    S: af::HasAfEnum,
+    T: Element,
+    E: FnMut(&af::Array<S>) -> af::Array<T>,
+{
+    let batch_size = ex.elements() as u64;
+    // Column of `x` that every chain continues from.
+    let sources: af::Array<u32> = match policy {
+        Migration::Independent => return,
+        Migration::Collapse => {
+            let (_, index) = af::imin(&af::flat(ex), 0);
+            af::tile(&index, dim4!(batch_size))
+        }
+        Migration::TopK(k) => {
+            let k = u64::from(k).clamp(1, batch_size);
+            let best = lowest(&af::flat(ex), k);
+            let copies = af::tile(&best, dim4!(batch_size.div_ceil(k)));
+            af::rows(&copies, 0, batch_size as i64 - 1)
+        }
+        Migration::Tournament(size) => {
+            let size = u64::from(size).max(1);
+            let draws = dim4!(size, batch_size);
+            let entrants =
+                af::floor(&(af::random_uniform::<f32>(draws, engine) * batch_size as f32))
+                    .cast::<u32>();
+            let energies = af::moddims(&af::lookup(&af::flat(ex), &af::flat(&entrants), 0), draws);
+            let (_, winner) = af::imin(&energies, 0);
+            let offsets = af::range::<u32>(dim4!(1, batch_size), 1) * size as u32;
+            af::lookup(&af::flat(&entrants), &af::flat(&(winner + offsets)), 0)
+        }
+        Migration::ReplaceWorst(fraction) => {
+            let replaced =
+                ((fraction.clamp(0.0, 1.0) * batch_size as f32).round() as u64).min(batch_size - 1);
+            if replaced == 0 {
+                return;
+            }
+            let (_, order) = af::sort_index(&af::flat(ex), 0, true);
+            let kept = af::rows(&order, 0, (batch_size - replaced) as i64 - 1);
+            let best = af::tile(&af::row(&order, 0), dim4!(replaced));
+            af::join(0, &kept, &best)
+        }
+        Migration::UniformCrossover(parents) | Migration::SinglePointCrossover(parents) => {
+            let uniform = matches!(policy, Migration::UniformCrossover(_));
+            *x = crossover(x, ex, parents, uniform, batch_dim, engine);
+            *ex = af::moddims(&energy(x), ex.dims());
+            return;
+        }
+    };
+
+    *x = af::lookup(x, &sources, batch_dim as i32);
+    *ex = af::lookup(ex, &sources, batch_dim as i32);
+}
+
+/// Breed a child for every chain of `x`, batched along `batch_dim`, from two distinct parents
+/// among its `parents` lowest-energy chains; the best chain is its own child.
+fn crossover<S: af::HasAfEnum, T: Element>(
+    x: &af::Array<S>,
+    ex: &af::Array<T>,
+    parents: u32,
+    uniform: bool,
+    batch_dim: usize,
+    engine: &af::RandomEngine,
+) -> af::Array<S> {
+    let dims = x.dims();
+    let batch_size = ex.elements() as u64;
+    let parents = u64::from(parents).clamp(1, batch_size);
+    let pool = lowest(&af::flat(ex), parents);
+
+    // Second parents sit a random non-zero offset away from the first in the pool.
+    let draw = |range: u64| {
+        af::floor(&(af::random_uniform::<f32>(dim4!(batch_size), engine) * range as f32))
+            .cast::<u32>()
+    };
+    let first = draw(parents);
+    let second = af::rem(
+        &(&first + 1u32 + draw(parents - 1)),
+        &(parents as u32),
+        false,
+    );
+    let (_, best) = af::imin(&af::flat(ex), 0);
+    let best = af::tile(&best, dim4!(batch_size));
+    let elite = af::eq(&af::range::<u32>(dim4!(batch_size), 0), &best, false);
+    let pick = |choice: &af::Array<u32>| {
+        let chains = af::select(&best, &elite, &af::lookup(&pool, choice, 0));
+        af::lookup(x, &chains, batch_dim as i32)
+    };
+
+    let mut chain_dims = [1; 4];
+    chain_dims[batch_dim] = batch_size;
+    let chain_dims = af::Dim4::new(&chain_dims);
+    let from_first = if uniform {
+        af::lt(&af::random_uniform::<f32>(dims, engine), &0.5f32, false)
+    } else {
+        // Position of every element within its chain, and a cut in [1, len - 1] per chain.
+        let mut position = af::constant(0u32, dims);
+        let mut len = 1;
+        for axis in (0..4).filter(|&axis| axis != batch_dim) {
+            position += af::range::<u32>(dims, axis as i32) * len as u32;
+            len *= dims[axis];
+        }
+        let cut = af::floor(
+            &(af::random_uniform::<f32>(chain_dims, engine) * len.saturating_sub(1) as f32),
+        )
+        .cast::<u32>()
+            + 1u32;
+        af::lt(&position, &cut, true)
+    };
+    af::select(&pick(&first), &from_first, &pick(&second))
+}
+
+/// Fallible counterpart of [`minimize_numeric`] that reports invalid parameters as an [`Error`]
+/// instead of panicking.
+///
+/// # Errors
+///
+/// * [`Error::EmptyBatch`] if `batch_size` is zero
+/// * [`Error::NonPositiveBoltzmann`] if `k` is not positive
+/// * [`Error::EmptySchedule`] if `temperatures` yields no temperature
+/// * [`Error::NanInitialEnergy`] if the initial energy of any chain is NaN, checked before
+///   the first step without evaluating `start` again
+pub fn try_minimize_numeric<T, E, F, G>(
+    batch_size: u64,
+    chain_length: usize,
+    k: T,
+    start: &af::Array<T>,
+    energy: E,
+    mut neighbour_map: F,
+    temperatures: G,
+) -> Result<ParsaResult<T>, Error>
+where
+    T: Element,
+    E: FnMut(&af::Array<T>) -> af::Array<T>,
+    F: FnMut(&af::Array<T>) -> af::Array<T>,
+    G: Iterator,
+    G::Item: Temperature<T>,
+{
+    if batch_size == 0 {
+        return Err(Error::EmptyBatch);
+    }
+    let k_value: f64 = k.into();
+    if k_value <= 0.0 || k_value.is_nan() {
+        return Err(Error::NonPositiveBoltzmann);
     }
-    x
+
+    let mut temperatures = temperatures.peekable();
+    if temperatures.peek().is_none() {
+        return Err(Error::EmptySchedule);
+    }
+
+    let options = Options::new(batch_size, chain_length, k);
+    try_anneal(
+        start,
+        energy,
+        |x: &af::Array<T>, _: &af::Array<T>| neighbour_map(x),
+        temperatures,
+        &options,
+        None,
+        |_: &mut Progress<T, T, G::Item>| ControlFlow::Continue(()),
+        || {},
+        |_, _| {},
+        true,
+    )
 }
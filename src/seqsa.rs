@@ -1,77 +1,2302 @@
 //! Sequential Simulated Annealing.
 
+use std::cell::{Cell, RefCell};
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use num_traits::Float;
 use tinyrand::{Probability, Rand, Seeded, StdRand};
 
-/// Minimize an objective function through sequential simulated annealing.
-/// It works by iteratively exploring the solution space while gradually
-/// "cooling" the system according to a temperature schedule.
+use crate::lsops::{AdaptiveNeighbour, Context, LocalSearchOp, TemperatureNeighbour};
+use crate::Error;
+
+/// Reason an annealing run stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// Every temperature in the schedule was processed.
+    ScheduleExhausted,
+    /// The schedule reached a non-positive temperature, see [`NonPositiveTemperature`].
+    ZeroTemperature,
+    /// The observer returned [`ControlFlow::Break`].
+    Observer,
+    /// The best energy stopped improving, see [`EarlyStopping`].
+    Converged,
+    /// The best energy reached [`Options::target_energy`].
+    TargetReached,
+    /// [`Options::time_limit`] elapsed.
+    TimeLimit,
+    /// [`Options::cancel`] was set.
+    Cancelled,
+    /// The evaluation budget ([`Options::max_evaluations`] or
+    /// [`AsaOptions::max_evaluations`]) was used up.
+    BudgetExhausted,
+}
+
+impl Termination {
+    /// Whether the run ended in the regular way, after which the greedy quench runs.
+    fn quenches(self) -> bool {
+        matches!(
+            self,
+            Self::ScheduleExhausted | Self::ZeroTemperature | Self::Converged
+        )
+    }
+}
+
+/// Outcome of a sequential annealing run, along with statistics useful for tuning.
+#[derive(Debug, Clone)]
+pub struct RunResult<T, N = f32> {
+    /// Lowest-energy state seen during the run.
+    pub best_state: T,
+    /// Energy of `best_state`.
+    pub best_energy: N,
+    /// Total number of calls made to the energy function, including the initial state.
+    pub evaluations: usize,
+    /// Number of accepted proposals at each processed temperature, in schedule order.
+    pub acceptances: Vec<usize>,
+    /// Why the run stopped.
+    pub termination: Termination,
+    /// Number of times the chain was reset to the best state, see [`Options::restart`].
+    pub restarts: usize,
+}
+
+/// Outcome of [`minimize_multistart`]: every independent run, and which one did best.
+#[derive(Debug, Clone)]
+pub struct Multistart<T, N = f32> {
+    /// Result of each run, in the order of the starting points.
+    pub runs: Vec<RunResult<T, N>>,
+    /// Index into `runs` of the run with the lowest best energy.
+    pub best_run: usize,
+}
+
+impl<T, N> Multistart<T, N> {
+    /// The result of the run that reached the lowest energy.
+    pub fn best(&self) -> &RunResult<T, N> {
+        &self.runs[self.best_run]
+    }
+}
+
+/// Per-temperature `(temperature, best energy, acceptance rate)` trajectory recorded by
+/// [`minimize_with_history`].
+pub type History<N = f32> = Vec<(N, N, N)>;
+
+/// Convergence criterion: stop once the best energy has improved by no more than
+/// `tolerance` over `patience` consecutive temperatures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EarlyStopping<N = f32> {
+    /// Number of consecutive temperatures without sufficient improvement to tolerate.
+    pub patience: usize,
+    /// Minimum decrease in best energy that counts as an improvement.
+    pub tolerance: N,
+}
+
+/// Step-size control for [`minimize_adaptive`] and [`crate::parsa::minimize_numeric_adaptive`].
+///
+/// After every temperature the step scale is multiplied by `factor` if the acceptance ratio
+/// was above `target_acceptance`, divided by it if it was below, and clamped to
+/// `[min_scale, max_scale]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepAdaptation {
+    /// Scale passed to the neighbour at the first temperature.
+    pub initial_scale: f32,
+    /// Desired ratio of accepted to proposed moves.
+    pub target_acceptance: f32,
+    /// Multiplicative adjustment applied to the scale, must be greater than one.
+    pub factor: f32,
+    /// Lower bound of the scale.
+    pub min_scale: f32,
+    /// Upper bound of the scale.
+    pub max_scale: f32,
+}
+
+impl StepAdaptation {
+    /// Adaptation starting at `initial_scale` that aims for the classic 44% acceptance ratio,
+    /// adjusting the scale by 20% per temperature without bounds.
+    pub fn new(initial_scale: f32) -> Self {
+        Self {
+            initial_scale,
+            target_acceptance: 0.44,
+            factor: 1.2,
+            min_scale: 0.0,
+            max_scale: f32::INFINITY,
+        }
+    }
+
+    /// Rechenberg's 1/5-success rule starting at `initial_scale`: the scale grows by 22% per
+    /// temperature while more than a fifth of the moves are accepted, and shrinks otherwise.
+    pub fn one_fifth(initial_scale: f32) -> Self {
+        Self {
+            target_acceptance: 0.2,
+            factor: 1.22,
+            ..Self::new(initial_scale)
+        }
+    }
+
+    fn adapt(&self, scale: f32, acceptance: f32) -> f32 {
+        let scale = if acceptance > self.target_acceptance {
+            scale * self.factor
+        } else if acceptance < self.target_acceptance {
+            scale / self.factor
+        } else {
+            scale
+        };
+        scale.clamp(self.min_scale, self.max_scale)
+    }
+}
+
+/// Re-evaluation scheme for stochastic objectives, see [`Options::resampling`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resampling {
+    /// Number of evaluations averaged for every energy estimate.
+    pub samples: usize,
+    /// Number of iterations after which the current state's energy is estimated afresh, so a
+    /// luckily low estimate cannot trap the chain. Zero disables re-evaluation.
+    pub reevaluate_interval: usize,
+}
+
+/// Stagnation criterion: reset the chain to the best state found so far once it has gone
+/// `patience` iterations without improving on it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Restart<N = f32> {
+    /// Number of consecutive iterations without a new best state to tolerate.
+    pub patience: usize,
+    /// Factor applied to the temperature after a restart, until the schedule moves on to the
+    /// next temperature. `1` keeps the schedule unchanged.
+    pub temperature_bump: N,
+}
+
+/// Tuning parameters for [`asa_minimize`].
+///
+/// The defaults follow Ingber's ASA package: temperatures decay as
+/// `T(k) = T0 * exp(-c * k^(1/D))` with `c = -ln(temperature_ratio_scale) *
+/// exp(-ln(temperature_anneal_scale) / D)` for a `D`-dimensional problem.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AsaOptions<N = f32> {
+    /// Number of energy evaluations after which the run stops.
+    pub max_evaluations: usize,
+    /// Initial temperature of every parameter's generating distribution.
+    pub initial_parameter_temperature: N,
+    /// Initial acceptance temperature; `None` uses the magnitude of the starting energy.
+    pub initial_acceptance_temperature: Option<N>,
+    /// Expected ratio of final to initial temperature.
+    pub temperature_ratio_scale: N,
+    /// Expected number of generated states over which that ratio is reached.
+    pub temperature_anneal_scale: N,
+    /// Number of accepted states between reannealing passes; `0` disables reannealing.
+    pub reanneal_interval: usize,
+    /// Step used to estimate sensitivities, relative to each parameter's range.
+    pub sensitivity_delta: N,
+}
+
+impl<N: Float> AsaOptions<N> {
+    /// Options stopping after `max_evaluations` evaluations, with ASA's default schedule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the defaults are not representable in `N`.
+    pub fn new(max_evaluations: usize) -> Self {
+        let cast = |x: f64| N::from(x).expect("ASA default not representable");
+        Self {
+            max_evaluations,
+            initial_parameter_temperature: N::one(),
+            initial_acceptance_temperature: None,
+            temperature_ratio_scale: cast(1e-5),
+            temperature_anneal_scale: cast(100.0),
+            reanneal_interval: 100,
+            sensitivity_delta: cast(1e-3),
+        }
+    }
+}
+
+/// What an annealer does when the schedule reaches a temperature of zero or below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonPositiveTemperature {
+    /// Stop the run with [`Termination::ZeroTemperature`].
+    Terminate,
+    /// Keep going, accepting only improvements, as in a quench.
+    Greedy,
+}
+
+/// Tuning parameters for [`minimize_with`].
+#[derive(Debug, Clone)]
+pub struct Options<N = f32> {
+    /// Number of iterations to perform at each temperature.
+    pub chain_length: usize,
+    /// Number of iterations to perform at each temperature of the schedule, in order, e.g. to
+    /// refine longer once the system has cooled down. Temperatures past its end use
+    /// `chain_length`.
+    pub chain_lengths: Vec<usize>,
+    /// Boltzmann constant that scales the acceptance probability.
+    pub k: N,
+    /// Whether the observer is also invoked after every inner iteration,
+    /// rather than only once per temperature.
+    pub observe_iterations: bool,
+    /// Optional convergence criterion on the best energy.
+    pub early_stopping: Option<EarlyStopping<N>>,
+    /// Stop as soon as the best energy drops to or below this value.
+    pub target_energy: Option<N>,
+    /// Number of greedy iterations (a zero-temperature quench that only accepts improvements)
+    /// run from the best state once the schedule is exhausted or the run has converged. Their
+    /// accepted moves form the last entry of [`RunResult::acceptances`].
+    pub quench_iterations: usize,
+    /// Stop once this many energy evaluations (as counted by [`RunResult::evaluations`]) have
+    /// been made.
+    pub max_evaluations: Option<usize>,
+    /// Averaging of repeated evaluations for noisy objectives. Honoured by [`minimize_with`],
+    /// [`try_minimize_with`], [`minimize_multistart`], `minimize_par`, `minimize_async`,
+    /// [`minimize_resumable`], [`resume_from`], [`minimize_tempered`] and [`minimize_op`];
+    /// [`RunResult::evaluations`] then counts every individual call. Ignored by
+    /// [`minimize_moves`], [`minimize_delta`] and [`minimize_adaptive`].
+    pub resampling: Option<Resampling>,
+    /// Optional reset of the chain to the incumbent best state when it stagnates, to stop it
+    /// wandering off late in the run.
+    pub restart: Option<Restart<N>>,
+    /// Number of accepted moves after which [`minimize_delta`] recomputes the full energy to
+    /// discard accumulated drift. Zero disables recomputation.
+    pub recompute_interval: usize,
+    /// Wall-clock budget for the run; the best state found so far is returned once it elapses.
+    pub time_limit: Option<Duration>,
+    /// Cancellation token checked after every iteration; setting it to `true` (e.g. from a UI
+    /// or a Ctrl-C handler) stops the run cooperatively.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Handling of non-positive temperatures in the schedule.
+    pub non_positive_temperature: NonPositiveTemperature,
+}
+
+impl<N> Options<N> {
+    /// Create options with the given chain length and Boltzmann constant.
+    #[must_use]
+    pub fn new(chain_length: usize, k: N) -> Self {
+        Self {
+            chain_length,
+            chain_lengths: Vec::new(),
+            k,
+            observe_iterations: false,
+            early_stopping: None,
+            target_energy: None,
+            quench_iterations: 0,
+            max_evaluations: None,
+            resampling: None,
+            restart: None,
+            recompute_interval: 1000,
+            time_limit: None,
+            cancel: None,
+            non_positive_temperature: NonPositiveTemperature::Terminate,
+        }
+    }
+
+    /// Number of iterations to perform at the temperature with index `step` in the schedule.
+    #[must_use]
+    pub fn chain_length_at(&self, step: usize) -> usize {
+        self.chain_lengths
+            .get(step)
+            .copied()
+            .unwrap_or(self.chain_length)
+    }
+}
+
+/// Snapshot of an ongoing run, handed to observers.
+#[derive(Debug)]
+pub struct Progress<'a, T, N = f32> {
+    /// Current temperature.
+    pub temperature: N,
+    /// Index of the current temperature in the schedule.
+    pub step: usize,
+    /// Inner iteration that just finished, or `None` at the end of a temperature.
+    pub iteration: Option<usize>,
+    /// Current state of the chain.
+    pub state: &'a T,
+    /// Energy of `state`.
+    pub energy: N,
+    /// Lowest energy seen so far.
+    pub best_energy: N,
+    /// Proposals accepted so far at this temperature.
+    pub accepted: usize,
+    /// Proposals made so far at this temperature.
+    pub proposed: usize,
+}
+
+/// State of the chain after one inner iteration, yielded by [`minimize_lazy_steps`].
+#[derive(Debug, Clone)]
+pub struct Sample<T, N = f32> {
+    /// Temperature the iteration ran at.
+    pub temperature: N,
+    /// Index of the iteration within the current temperature.
+    pub iteration: usize,
+    /// State of the chain after the iteration.
+    pub state: T,
+    /// Energy of `state`.
+    pub energy: N,
+}
+
+/// Wyrand generator whose state can be saved in a [`Checkpoint`].
+///
+/// Implements the same algorithm as [`StdRand`], but exposes its state to serialization so that
+/// a resumed run continues the exact random stream of the interrupted one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResumableRng {
+    state: u64,
+}
+
+impl Seeded for ResumableRng {
+    type R = Self;
+
+    fn seed(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl Rand for ResumableRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0xa076_1d64_78bd_642f);
+        let t = u128::from(self.state) * u128::from(self.state ^ 0xe703_7ed1_a0b4_28db);
+        (t >> 64) as u64 ^ t as u64
+    }
+}
+
+/// Snapshot of a sequential run taken after a completed temperature, from which the run can be
+/// continued with [`resume_from`].
+///
+/// With the `serde` feature the snapshot can be serialized whenever `T` and `N` can. For
+/// `Array<f32>` states, convert with [`Checkpoint::map_state`] and [`crate::host::HostArray`]
+/// first.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint<T, N = f32> {
+    /// Current state of the chain.
+    pub state: T,
+    /// Energy of `state`.
+    pub energy: N,
+    /// Lowest-energy state seen so far.
+    pub best_state: T,
+    /// Energy of `best_state`.
+    pub best_energy: N,
+    /// Energy evaluations made so far.
+    pub evaluations: usize,
+    /// Accepted proposals at each completed temperature; its length is the number of
+    /// schedule entries already processed.
+    pub acceptances: Vec<usize>,
+    /// Iterations since the best state last improved, counted towards [`Options::restart`].
+    pub since_improvement: usize,
+    /// Restarts made so far.
+    pub restarts: usize,
+    /// Best energy at the last improvement that counted for [`Options::early_stopping`].
+    pub reference_energy: N,
+    /// Completed temperatures since then.
+    pub stalled: usize,
+    /// Iterations since the current state's energy was last estimated, counted towards
+    /// [`Resampling::reevaluate_interval`].
+    pub since_reevaluation: usize,
+    /// Random number generator, positioned where the run left off.
+    pub rng: ResumableRng,
+}
+
+impl<T, N> Checkpoint<T, N> {
+    /// Number of temperatures already processed, i.e. where the schedule resumes.
+    pub fn step(&self) -> usize {
+        self.acceptances.len()
+    }
+
+    /// Convert the current and best states, e.g. to and from a serializable representation.
+    pub fn map_state<U>(self, mut f: impl FnMut(T) -> U) -> Checkpoint<U, N> {
+        Checkpoint {
+            state: f(self.state),
+            energy: self.energy,
+            best_state: f(self.best_state),
+            best_energy: self.best_energy,
+            evaluations: self.evaluations,
+            acceptances: self.acceptances,
+            since_improvement: self.since_improvement,
+            restarts: self.restarts,
+            reference_energy: self.reference_energy,
+            stalled: self.stalled,
+            since_reevaluation: self.since_reevaluation,
+            rng: self.rng,
+        }
+    }
+}
+
+/// Adapter that lets any [`rand_core::RngCore`] generator drive the sequential minimizers.
+#[cfg(feature = "rand")]
+#[derive(Debug, Clone)]
+pub struct RngCoreAdapter<R>(pub R);
+
+#[cfg(feature = "rand")]
+impl<R: rand_core::RngCore> Rand for RngCoreAdapter<R> {
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+}
+
+/// Minimize an objective function through sequential simulated annealing.
+/// It works by iteratively exploring the solution space while gradually
+/// "cooling" the system according to a temperature schedule.
+///
+/// This is a shorthand for [`minimize_with`] without an observer.
+///
+/// # Arguments
+///
+/// * `chain_length` - Number of iterations to perform at each temperature
+/// * `k` - Boltzmann constant that scales the acceptance probability
+/// * `start` - Initial state/solution
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
+/// * `neighbour` - Function that randomly picks a neighboring state from the current one
+/// * `temperatures` - Iterator providing the cooling schedule temperatures
+/// * `random_seed` - Seed for the random number generator
+///
+/// # Returns
+///
+/// A [`RunResult`] holding the best state found, its energy, and run statistics.
+///
+/// # Type Parameters
+///
+/// * `T` - Type representing a state/solution in the search space, must implement Clone
+///   to keep track of the best state
+/// * `N` - Floating point type of energies, temperatures and `k` (`f32` or `f64`)
+/// * `E` - Type of the energy function `FnMut(&T) -> N`
+/// * `F` - Type of the neighbor function `FnMut(&T) -> T`
+/// * `G` - Type of the temperature iterator `Iterator<Item = N>`
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn minimize<T, N, E, F, G>(
+    chain_length: usize,
+    k: N,
+    start: T,
+    energy: E,
+    neighbour: F,
+    temperatures: G,
+    random_seed: u64,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+{
+    minimize_with(
+        start,
+        energy,
+        neighbour,
+        temperatures,
+        &Options::new(chain_length, k),
+        &mut StdRand::seed(random_seed),
+        |_| ControlFlow::Continue(()),
+    )
+}
+
+/// Minimize an objective function through sequential simulated annealing, recording how
+/// the run progressed.
+///
+/// Takes the same arguments as [`minimize`]. Alongside the [`RunResult`], returns one
+/// `(temperature, best energy, acceptance rate)` entry per processed temperature, which is
+/// usually enough to judge whether a cooling schedule is too fast or too slow. The acceptance
+/// rate is 0 with a `chain_length` of 0.
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn minimize_with_history<T, N, E, F, G>(
+    chain_length: usize,
+    k: N,
+    start: T,
+    energy: E,
+    neighbour: F,
+    temperatures: G,
+    random_seed: u64,
+) -> (RunResult<T, N>, History<N>)
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+{
+    let mut history = Vec::new();
+    let result = minimize_with(
+        start,
+        energy,
+        neighbour,
+        temperatures,
+        &Options::new(chain_length, k),
+        &mut StdRand::seed(random_seed),
+        |progress| {
+            // Without proposals, none were accepted either.
+            let acceptance = if progress.proposed == 0 {
+                N::zero()
+            } else {
+                N::from(progress.accepted).unwrap_or_else(N::nan)
+                    / N::from(progress.proposed).unwrap_or_else(N::nan)
+            };
+            history.push((progress.temperature, progress.best_energy, acceptance));
+            ControlFlow::Continue(())
+        },
+    );
+    (result, history)
+}
+
+/// Maximize an objective function through sequential simulated annealing.
+///
+/// Equivalent to [`minimize`] on the negated objective, except that
+/// [`RunResult::best_energy`] holds the highest objective value found rather than its negation.
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn maximize<T, N, E, F, G>(
+    chain_length: usize,
+    k: N,
+    start: T,
+    mut objective: E,
+    neighbour: F,
+    temperatures: G,
+    random_seed: u64,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+{
+    let mut result = minimize(
+        chain_length,
+        k,
+        start,
+        |x| -objective(x),
+        neighbour,
+        temperatures,
+        random_seed,
+    );
+    result.best_energy = -result.best_energy;
+    result
+}
+
+/// Minimize an objective function through sequential simulated annealing,
+/// reporting progress to an observer.
+///
+/// The observer is called after every temperature (and after every inner iteration if
+/// [`Options::observe_iterations`] is set) with a [`Progress`] snapshot. Returning
+/// [`ControlFlow::Break`] stops the run early with [`Termination::Observer`].
+///
+/// The run can also stop early through [`Options::early_stopping`] or
+/// [`Options::target_energy`]; [`RunResult::termination`] reports which criterion fired.
+///
+/// # Arguments
+///
+/// * `start` - Initial state/solution
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
+/// * `neighbour` - Function that randomly picks a neighboring state from the current one
+/// * `temperatures` - Iterator providing the cooling schedule temperatures
+/// * `options` - Chain length, Boltzmann constant, stopping and observation settings
+/// * `rng` - Random number generator driving the acceptance test; any [`Rand`] implementation
+///   can be used, so one generator may be shared across components
+/// * `observer` - Callback receiving progress snapshots
+///
+/// # Type Parameters
+///
+/// * `T` - Type representing a state/solution in the search space, must implement Clone
+///   to keep track of the best state
+/// * `N` - Floating point type of energies, temperatures and `k` (`f32` or `f64`)
+/// * `E` - Type of the energy function `FnMut(&T) -> N`
+/// * `F` - Type of the neighbor function `FnMut(&T) -> T`
+/// * `G` - Type of the temperature iterator `Iterator<Item = N>`
+/// * `O` - Type of the observer `FnMut(&Progress<T, N>) -> ControlFlow<()>`
+/// * `R` - Type of the random number generator
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn minimize_with<T, N, E, F, G, O, R>(
+    start: T,
+    mut energy: E,
+    neighbour: F,
+    temperatures: G,
+    options: &Options<N>,
+    rng: &mut R,
+    observer: O,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+{
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    let start_energy = estimate(&mut energy, &start, samples);
+    anneal_from(
+        Chain {
+            evaluations: samples,
+            ..Chain::new(start, start_energy)
+        },
+        replace_kernel(energy, plain_neighbour(neighbour), options.resampling),
+        temperatures,
+        options,
+        rng,
+        observer,
+        |_, _| {},
+    )
+}
+
+/// Fallible counterpart of [`minimize`] that reports invalid parameters as an [`Error`]
+/// instead of panicking.
+///
+/// # Errors
+///
+/// * [`Error::NonPositiveBoltzmann`] if `k` is not positive
+/// * [`Error::EmptySchedule`] if `temperatures` yields no temperature
+/// * [`Error::NanInitialEnergy`] if the energy of `start` is NaN
+pub fn try_minimize<T, N, E, F, G>(
+    chain_length: usize,
+    k: N,
+    start: T,
+    energy: E,
+    neighbour: F,
+    temperatures: G,
+    random_seed: u64,
+) -> Result<RunResult<T, N>, Error>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+{
+    try_minimize_with(
+        start,
+        energy,
+        neighbour,
+        temperatures,
+        &Options::new(chain_length, k),
+        &mut StdRand::seed(random_seed),
+        |_| ControlFlow::Continue(()),
+    )
+}
+
+/// Fallible counterpart of [`minimize_with`] that reports invalid parameters as an [`Error`]
+/// instead of panicking.
+///
+/// # Errors
+///
+/// * [`Error::NonPositiveBoltzmann`] if `options.k` is not positive
+/// * [`Error::EmptySchedule`] if `temperatures` yields no temperature
+/// * [`Error::NanInitialEnergy`] if the energy of `start` is NaN
+pub fn try_minimize_with<T, N, E, F, G, O, R>(
+    start: T,
+    mut energy: E,
+    neighbour: F,
+    temperatures: G,
+    options: &Options<N>,
+    rng: &mut R,
+    observer: O,
+) -> Result<RunResult<T, N>, Error>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+{
+    if options.k <= N::zero() || options.k.is_nan() {
+        return Err(Error::NonPositiveBoltzmann);
+    }
+
+    let mut temperatures = temperatures.peekable();
+    if temperatures.peek().is_none() {
+        return Err(Error::EmptySchedule);
+    }
+
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    let start_energy = estimate(&mut energy, &start, samples);
+    if start_energy.is_nan() {
+        return Err(Error::NanInitialEnergy);
+    }
+
+    Ok(anneal_from(
+        Chain {
+            evaluations: samples,
+            ..Chain::new(start, start_energy)
+        },
+        replace_kernel(energy, plain_neighbour(neighbour), options.resampling),
+        temperatures,
+        options,
+        rng,
+        observer,
+        |_, _| {},
+    ))
+}
+
+/// Run several independent annealing runs and keep the best.
+///
+/// One run is started from each element of `starts`; starting points can be given explicitly
+/// or generated, e.g. `(0..restarts).map(|_| random_state(&mut rng))`. Every run uses its own
+/// [`StdRand`] whose seed is derived from `random_seed`, so the outcome does not depend on
+/// `threads`.
+///
+/// # Arguments
+///
+/// * `starts` - Initial states, one per run
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
+/// * `neighbour` - Function that randomly picks a neighboring state from the current one
+/// * `schedule` - Function creating the cooling schedule, called once per run
+/// * `options` - Settings shared by all runs; a [`Options::cancel`] token stops all of them
+/// * `random_seed` - Master seed from which per-run seeds are derived
+/// * `threads` - Number of OS threads to spread the runs over; `0` or `1` runs them on the
+///   calling thread
+///
+/// # Panics
+///
+/// Panics if `starts` is empty or the Boltzmann constant `k` is not positive.
+pub fn minimize_multistart<T, N, S, E, F, G, H>(
+    starts: S,
+    energy: E,
+    neighbour: F,
+    schedule: H,
+    options: &Options<N>,
+    random_seed: u64,
+    threads: usize,
+) -> Multistart<T, N>
+where
+    T: Clone + Send,
+    N: Float + Send + Sync,
+    S: IntoIterator<Item = T>,
+    E: Fn(&T) -> N + Sync,
+    F: Fn(&T) -> T + Sync,
+    G: Iterator<Item = N>,
+    H: Fn() -> G + Sync,
+{
+    let mut seeder = StdRand::seed(random_seed);
+    let jobs: Vec<(T, u64)> = starts
+        .into_iter()
+        .map(|start| (start, seeder.next_u64()))
+        .collect();
+    assert!(!jobs.is_empty(), "at least one starting point is required");
+
+    let run = |(start, seed): (T, u64)| {
+        minimize_with(
+            start,
+            &energy,
+            &neighbour,
+            schedule(),
+            options,
+            &mut StdRand::seed(seed),
+            |_| ControlFlow::Continue(()),
+        )
+    };
+
+    let runs: Vec<_> = if threads <= 1 {
+        jobs.into_iter().map(run).collect()
+    } else {
+        let per_thread = jobs.len().div_ceil(threads);
+        let mut jobs = jobs.into_iter();
+        let mut batches = Vec::new();
+        while jobs.len() > 0 {
+            batches.push(jobs.by_ref().take(per_thread).collect::<Vec<_>>());
+        }
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = batches
+                .into_iter()
+                .map(|batch| scope.spawn(|| batch.into_iter().map(run).collect::<Vec<_>>()))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        })
+    };
+
+    let best_run = (1..runs.len()).fold(0, |best, i| {
+        if runs[i].best_energy < runs[best].best_energy {
+            i
+        } else {
+            best
+        }
+    });
+    Multistart { runs, best_run }
+}
+
+/// Minimize an objective function with several annealing chains running in parallel on the
+/// rayon thread pool.
+///
+/// Fills the gap between [`minimize_with`] (one chain) and [`crate::parsa`] (arrayfire arrays
+/// only) for states that are plain Rust values. All chains start from `start` and walk the
+/// schedule in lockstep, each with its own [`StdRand`] derived from `random_seed`. Every
+/// `exchange_interval` temperatures, all chains jump to the best state found by any of them;
+/// `0` keeps the chains fully independent.
+///
+/// Every chain honours [`Options::chain_lengths`], [`Options::non_positive_temperature`],
+/// [`Options::early_stopping`], [`Options::target_energy`], [`Options::quench_iterations`],
+/// [`Options::max_evaluations`], [`Options::resampling`], [`Options::restart`],
+/// [`Options::time_limit`] and [`Options::cancel`] as in [`minimize_with`], and stops on its
+/// own; the run ends once every chain has. There is no observer, so
+/// [`Options::observe_iterations`] has no effect. Chains that have stopped take no part in
+/// exchanges.
+///
+/// # Panics
+///
+/// Panics if `chains` is zero or the Boltzmann constant `k` is not positive.
+#[cfg(feature = "rayon")]
+#[allow(clippy::too_many_arguments)]
+pub fn minimize_par<T, N, E, F, G>(
+    chains: usize,
+    start: T,
+    energy: E,
+    neighbour: F,
+    temperatures: G,
+    options: &Options<N>,
+    random_seed: u64,
+    exchange_interval: usize,
+) -> Multistart<T, N>
+where
+    T: Clone + Send,
+    N: Float + Send + Sync,
+    E: Fn(&T) -> N + Sync,
+    F: Fn(&T) -> T + Sync,
+    G: Iterator<Item = N>,
+{
+    use rayon::prelude::*;
+
+    assert!(chains > 0, "at least one chain is required");
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    let start_energy = estimate(&mut &energy, &start, samples);
+    let mut seeder = StdRand::seed(random_seed);
+    let mut workers: Vec<_> = (0..chains)
+        .map(|_| {
+            let chain = Chain {
+                evaluations: samples,
+                ..Chain::new(start.clone(), start_energy)
+            };
+            (chain, StdRand::seed(seeder.next_u64()))
+        })
+        .collect();
+
+    // Chains stop on their own, e.g. once their evaluation budget is used up; the run ends
+    // when every chain has.
+    let mut stopped: Vec<Option<Termination>> = vec![None; chains];
+    let started = Instant::now();
+    for (step, temperature) in temperatures.enumerate() {
+        workers
+            .par_iter_mut()
+            .zip(&mut stopped)
+            .filter(|(_, stopped)| stopped.is_none())
+            .for_each(|((chain, rng), stopped)| {
+                let mut transition =
+                    replace_kernel(&energy, plain_neighbour(&neighbour), options.resampling);
+                *stopped = match anneal_temperature(
+                    chain,
+                    &mut transition,
+                    temperature,
+                    step,
+                    options,
+                    rng,
+                    &mut |_: &Progress<T, N>| ControlFlow::Continue(()),
+                    started,
+                ) {
+                    ControlFlow::Continue(false) => None,
+                    ControlFlow::Continue(true) => Some(Termination::Converged),
+                    ControlFlow::Break(reason) => Some(reason),
+                };
+            });
+        if stopped.iter().all(Option::is_some) {
+            break;
+        }
+
+        if exchange_interval > 0 && (step + 1) % exchange_interval == 0 {
+            let best = best_worker(&workers);
+            let (best_state, best_energy) = {
+                let chain = &workers[best].0;
+                (chain.best_state.clone(), chain.best_energy)
+            };
+            let running = workers
+                .iter_mut()
+                .zip(&stopped)
+                .filter(|(_, s)| s.is_none());
+            for ((chain, _), _) in running {
+                chain.state = best_state.clone();
+                chain.energy = best_energy;
+                // The chain carries on from a state it has not stagnated at, whose energy
+                // was just estimated.
+                chain.since_improvement = 0;
+                chain.since_reevaluation = 0;
+            }
+        }
+    }
+
+    let runs: Vec<_> = workers
+        .into_par_iter()
+        .zip(stopped)
+        .map(|((mut chain, mut rng), stopped)| {
+            let termination = stopped.unwrap_or(Termination::ScheduleExhausted);
+            if termination.quenches() {
+                let mut transition =
+                    replace_kernel(&energy, plain_neighbour(&neighbour), options.resampling);
+                quench(&mut chain, &mut transition, options, &mut rng);
+            }
+            chain.finish(termination)
+        })
+        .collect();
+    let best_run = (1..runs.len()).fold(0, |best, i| {
+        if runs[i].best_energy < runs[best].best_energy {
+            i
+        } else {
+            best
+        }
+    });
+    Multistart { runs, best_run }
+}
+
+/// Index of the chain with the lowest best energy.
+#[cfg(feature = "rayon")]
+fn best_worker<T, N: Float, R>(workers: &[(Chain<T, N>, R)]) -> usize {
+    (1..workers.len()).fold(0, |best, i| {
+        if workers[i].0.best_energy < workers[best].0.best_energy {
+            i
+        } else {
+            best
+        }
+    })
+}
+
+/// Minimize an objective function whose evaluations are asynchronous, e.g. requests to a
+/// remote simulator or a job queue.
+///
+/// Up to `concurrency` proposals are drawn from the current state and evaluated concurrently.
+/// They are then put through the acceptance test in order; once one is accepted, the
+/// remaining ones were proposed from a stale state and are discarded. With a `concurrency` of
+/// `1` the run is identical to [`minimize_with`] given the same generator. Discarded
+/// evaluations still count towards [`RunResult::evaluations`].
+///
+/// The options are honoured as in [`minimize_with`], with [`Options::max_evaluations`],
+/// [`Options::time_limit`] and [`Options::cancel`] checked after every batch of concurrent
+/// evaluations. With [`Options::resampling`] the samples of every candidate are evaluated
+/// concurrently as well. There is no observer, so [`Options::observe_iterations`] has no effect.
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+#[cfg(feature = "async")]
+pub async fn minimize_async<T, N, E, Fut, F, G, R>(
+    start: T,
+    mut energy: E,
+    mut neighbour: F,
+    temperatures: G,
+    options: &Options<N>,
+    rng: &mut R,
+    concurrency: usize,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> Fut,
+    Fut: std::future::Future<Output = N>,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+    R: Rand,
+{
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let concurrency = concurrency.max(1);
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    let start_energy = evaluate_async(&mut energy, [&start], samples).await[0];
+    let mut chain = Chain {
+        evaluations: samples,
+        ..Chain::new(start, start_energy)
+    };
+    let mut termination = Termination::ScheduleExhausted;
+    let started = Instant::now();
+
+    'schedule: for (step, temperature) in temperatures.enumerate() {
+        if temperature <= N::zero()
+            && options.non_positive_temperature == NonPositiveTemperature::Terminate
+        {
+            termination = Termination::ZeroTemperature;
+            break;
+        }
+
+        let kt = options.k * temperature;
+        let mut bump = N::one();
+        let mut accepted = 0;
+        let mut proposed = 0;
+        let chain_length = options.chain_length_at(step);
+        while proposed < chain_length {
+            let batch = concurrency.min(chain_length - proposed);
+            let candidates =
+                propose_async(&mut chain, &mut energy, &mut neighbour, options, batch).await;
+
+            let mut considered = 0;
+            for (candidate, en) in candidates {
+                proposed += 1;
+                considered += 1;
+                chain.since_improvement += 1;
+                let accept = !en.is_nan() && metropolis(rng, chain.energy, en, kt * bump);
+                if accept {
+                    chain.state = candidate;
+                    chain.energy = en;
+                    accepted += 1;
+                    if chain.energy < chain.best_energy {
+                        chain.best_state = chain.state.clone();
+                        chain.best_energy = chain.energy;
+                        chain.since_improvement = 0;
+
+                        if options
+                            .target_energy
+                            .is_some_and(|target| chain.best_energy <= target)
+                        {
+                            chain.acceptances.push(accepted);
+                            termination = Termination::TargetReached;
+                            break 'schedule;
+                        }
+                    }
+                }
+
+                let mut restarted = false;
+                if let Some(restart) = options.restart {
+                    if chain.since_improvement >= restart.patience {
+                        chain.state = chain.best_state.clone();
+                        chain.energy = chain.best_energy;
+                        chain.since_improvement = 0;
+                        chain.restarts += 1;
+                        bump = restart.temperature_bump;
+                        restarted = true;
+                    }
+                }
+                // The remaining candidates were proposed from a state the chain has left.
+                if accept || restarted {
+                    break;
+                }
+            }
+            chain.since_reevaluation += considered - 1;
+
+            if options
+                .max_evaluations
+                .is_some_and(|budget| chain.evaluations >= budget)
+            {
+                chain.acceptances.push(accepted);
+                termination = Termination::BudgetExhausted;
+                break 'schedule;
+            }
+            if let Some(reason) = interruption(options, started) {
+                chain.acceptances.push(accepted);
+                termination = reason;
+                break 'schedule;
+            }
+        }
+        chain.acceptances.push(accepted);
+
+        if let Some(criterion) = options.early_stopping {
+            if chain.reference_energy - chain.best_energy > criterion.tolerance {
+                chain.reference_energy = chain.best_energy;
+                chain.stalled = 0;
+            } else {
+                chain.stalled += 1;
+                if chain.stalled >= criterion.patience {
+                    termination = Termination::Converged;
+                    break;
+                }
+            }
+        }
+    }
+
+    if termination.quenches() && options.quench_iterations > 0 {
+        chain.state = chain.best_state.clone();
+        chain.energy = chain.best_energy;
+        let mut accepted = 0;
+        for _ in 0..options.quench_iterations {
+            let candidates =
+                propose_async(&mut chain, &mut energy, &mut neighbour, options, 1).await;
+            for (candidate, en) in candidates {
+                if !en.is_nan() && metropolis(rng, chain.energy, en, N::zero()) {
+                    chain.state = candidate;
+                    chain.energy = en;
+                    accepted += 1;
+                    if chain.energy < chain.best_energy {
+                        chain.best_state = chain.state.clone();
+                        chain.best_energy = chain.energy;
+                    }
+                }
+            }
+        }
+        chain.acceptances.push(accepted);
+    }
+
+    chain.finish(termination)
+}
+
+/// Draw `count` candidates from the current state of `chain` and evaluate them concurrently,
+/// first re-estimating the current state's energy if [`Resampling::reevaluate_interval`] is
+/// due. Counts one iteration towards it; callers add the candidates considered beyond the
+/// first.
+#[cfg(feature = "async")]
+async fn propose_async<T, N, E, Fut, F>(
+    chain: &mut Chain<T, N>,
+    energy: &mut E,
+    neighbour: &mut F,
+    options: &Options<N>,
+    count: usize,
+) -> Vec<(T, N)>
+where
+    N: Float,
+    E: FnMut(&T) -> Fut,
+    Fut: std::future::Future<Output = N>,
+    F: FnMut(&T) -> T,
+{
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    if let Some(resampling) = options.resampling {
+        chain.since_reevaluation += 1;
+        if resampling.reevaluate_interval > 0
+            && chain.since_reevaluation >= resampling.reevaluate_interval
+        {
+            chain.since_reevaluation = 0;
+            chain.energy = evaluate_async(energy, [&chain.state], samples).await[0];
+            chain.evaluations += samples;
+        }
+    }
+
+    let candidates: Vec<T> = (0..count).map(|_| neighbour(&chain.state)).collect();
+    let energies = evaluate_async(energy, &candidates, samples).await;
+    chain.evaluations += count * samples;
+    candidates.into_iter().zip(energies).collect()
+}
+
+/// Mean of `samples` concurrent evaluations of `energy` at each of `states`.
+#[cfg(feature = "async")]
+async fn evaluate_async<'a, T, N, E, Fut>(
+    energy: &mut E,
+    states: impl IntoIterator<Item = &'a T>,
+    samples: usize,
+) -> Vec<N>
+where
+    T: 'a,
+    N: Float,
+    E: FnMut(&T) -> Fut,
+    Fut: std::future::Future<Output = N>,
+{
+    let mut pending = Vec::new();
+    for state in states {
+        pending.extend((0..samples).map(|_| energy(state)));
+    }
+    let values = futures::future::join_all(pending).await;
+    if samples == 1 {
+        return values;
+    }
+    let divisor = N::from(samples).unwrap_or_else(N::nan);
+    values
+        .chunks(samples)
+        .map(|chunk| chunk.iter().fold(N::zero(), |total, &e| total + e) / divisor)
+        .collect()
+}
+
+/// Minimize an objective function through sequential simulated annealing, handing out a
+/// [`Checkpoint`] after every temperature.
+///
+/// Persisting the checkpoints lets very long optimizations survive process restarts: pass the
+/// last one, together with the same schedule, to [`resume_from`] to carry on where the run
+/// stopped. Producing a checkpoint clones the current and best states.
+///
+/// # Arguments
+///
+/// * `start` - Initial state/solution
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
+/// * `neighbour` - Function that randomly picks a neighboring state from the current one
+/// * `temperatures` - Iterator providing the cooling schedule temperatures
+/// * `options` - Chain length, Boltzmann constant and stopping settings
+/// * `random_seed` - Seed for the [`ResumableRng`]
+/// * `on_checkpoint` - Callback receiving a checkpoint after every completed temperature
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn minimize_resumable<T, N, E, F, G, C>(
+    start: T,
+    mut energy: E,
+    neighbour: F,
+    temperatures: G,
+    options: &Options<N>,
+    random_seed: u64,
+    on_checkpoint: C,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+    C: FnMut(&Checkpoint<T, N>),
+{
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    let start_energy = estimate(&mut energy, &start, samples);
+    anneal_resumable(
+        Chain {
+            evaluations: samples,
+            ..Chain::new(start, start_energy)
+        },
+        ResumableRng::seed(random_seed),
+        energy,
+        neighbour,
+        temperatures,
+        options,
+        on_checkpoint,
+    )
+}
+
+/// Continue a run of [`minimize_resumable`] from a [`Checkpoint`].
+///
+/// `temperatures` must be the full schedule of the original run; the entries processed before
+/// the checkpoint was taken are skipped. Only the time limit starts afresh. The checkpoint of
+/// a run's last temperature is taken before the run stops, so resuming from it carries on.
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn resume_from<T, N, E, F, G, C>(
+    checkpoint: Checkpoint<T, N>,
+    energy: E,
+    neighbour: F,
+    temperatures: G,
+    options: &Options<N>,
+    on_checkpoint: C,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+    C: FnMut(&Checkpoint<T, N>),
+{
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let chain = Chain {
+        state: checkpoint.state,
+        energy: checkpoint.energy,
+        best_state: checkpoint.best_state,
+        best_energy: checkpoint.best_energy,
+        evaluations: checkpoint.evaluations,
+        acceptances: checkpoint.acceptances,
+        since_improvement: checkpoint.since_improvement,
+        restarts: checkpoint.restarts,
+        reference_energy: checkpoint.reference_energy,
+        stalled: checkpoint.stalled,
+        since_reevaluation: checkpoint.since_reevaluation,
+    };
+    anneal_resumable(
+        chain,
+        checkpoint.rng,
+        energy,
+        neighbour,
+        temperatures,
+        options,
+        on_checkpoint,
+    )
+}
+
+/// Anneal `chain` from where it stopped, handing out a [`Checkpoint`] after every temperature.
+fn anneal_resumable<T, N, E, F, G, C>(
+    chain: Chain<T, N>,
+    mut rng: ResumableRng,
+    energy: E,
+    neighbour: F,
+    temperatures: G,
+    options: &Options<N>,
+    mut on_checkpoint: C,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T) -> T,
+    G: Iterator<Item = N>,
+    C: FnMut(&Checkpoint<T, N>),
+{
+    let step = chain.acceptances.len();
+    anneal_from(
+        chain,
+        replace_kernel(energy, plain_neighbour(neighbour), options.resampling),
+        temperatures.skip(step),
+        options,
+        &mut rng,
+        |_| ControlFlow::Continue(()),
+        |chain, rng| {
+            on_checkpoint(&Checkpoint {
+                state: chain.state.clone(),
+                energy: chain.energy,
+                best_state: chain.best_state.clone(),
+                best_energy: chain.best_energy,
+                evaluations: chain.evaluations,
+                acceptances: chain.acceptances.clone(),
+                since_improvement: chain.since_improvement,
+                restarts: chain.restarts,
+                reference_energy: chain.reference_energy,
+                stalled: chain.stalled,
+                since_reevaluation: chain.since_reevaluation,
+                rng: *rng,
+            });
+        },
+    )
+}
+
+/// A reversible, in-place modification of a state.
+///
+/// Used by [`minimize_moves`] for states that are expensive to clone: a proposal is applied
+/// directly to the current state and undone if it is rejected.
+pub trait Move<T> {
+    /// Apply the move to `state`.
+    fn apply(&self, state: &mut T);
+    /// Revert a previous [`Move::apply`] of this move on `state`.
+    fn undo(&self, state: &mut T);
+}
+
+/// Minimize an objective function through sequential simulated annealing, using moves that
+/// are applied and undone in place instead of neighbour states that are built from scratch.
+///
+/// Rejected proposals never allocate a new state; the state is only cloned when a new best
+/// is found. Otherwise behaves like [`minimize_with`], except that [`Options::resampling`] is
+/// ignored and every proposal is evaluated once.
+///
+/// # Arguments
+///
+/// * `start` - Initial state/solution
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
+/// * `propose` - Function that randomly picks a [`Move`] from the current state
+/// * `temperatures` - Iterator providing the cooling schedule temperatures
+/// * `options` - Chain length, Boltzmann constant, stopping and observation settings
+/// * `rng` - Random number generator driving the acceptance test
+/// * `observer` - Callback receiving progress snapshots
+///
+/// # Type Parameters
+///
+/// * `T` - Type representing a state/solution in the search space
+/// * `N` - Floating point type of energies, temperatures and `k` (`f32` or `f64`)
+/// * `M` - Type of the moves, implementing [`Move<T>`]
+/// * `E` - Type of the energy function `FnMut(&T) -> N`
+/// * `P` - Type of the move generator `FnMut(&T) -> M`
+/// * `G` - Type of the temperature iterator `Iterator<Item = N>`
+/// * `O` - Type of the observer `FnMut(&Progress<T, N>) -> ControlFlow<()>`
+/// * `R` - Type of the random number generator
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn minimize_moves<T, N, M, E, P, G, O, R>(
+    start: T,
+    mut energy: E,
+    mut propose: P,
+    temperatures: G,
+    options: &Options<N>,
+    rng: &mut R,
+    observer: O,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    M: Move<T>,
+    E: FnMut(&T) -> N,
+    P: FnMut(&T) -> M,
+    G: Iterator<Item = N>,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+{
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let start_energy = energy(&start);
+    let transition = |chain: &mut Chain<T, N>, kt: N, rng: &mut R| {
+        let proposal = propose(&chain.state);
+        proposal.apply(&mut chain.state);
+        let en = energy(&chain.state);
+        chain.evaluations += 1;
+        if !en.is_nan() && metropolis(rng, chain.energy, en, kt) {
+            chain.energy = en;
+            true
+        } else {
+            proposal.undo(&mut chain.state);
+            false
+        }
+    };
+
+    anneal(
+        start,
+        start_energy,
+        transition,
+        temperatures,
+        options,
+        rng,
+        observer,
+    )
+}
+
+/// Minimize an objective function through sequential simulated annealing, evaluating
+/// proposals incrementally.
+///
+/// Instead of recomputing the full objective, `delta_energy(state, &move)` returns the change
+/// in energy that applying `move` to `state` would cause, which is O(1) for many combinatorial
+/// problems (TSP, QAP, ...). Moves are only applied once accepted. The running energy is
+/// replaced by a full `energy` evaluation every [`Options::recompute_interval`] accepted
+/// moves to guard against floating point drift. [`Options::resampling`] is ignored, since
+/// deltas are taken as exact.
+///
+/// [`RunResult::evaluations`] counts proposals, i.e. calls to `delta_energy`, plus the
+/// initial energy evaluation.
 ///
 /// # Arguments
 ///
-/// * `chain_length` - Number of iterations to perform at each temperature
-/// * `k` - Boltzmann constant that scales the acceptance probability
 /// * `start` - Initial state/solution
-/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
-/// * `neighbour` - Function that randomly picks a neighboring state from the current one
+/// * `energy` - Objective function that evaluates the full "energy" (cost) of a state
+/// * `delta_energy` - Function returning the energy change caused by a move
+/// * `propose` - Function that randomly picks a [`Move`] from the current state
 /// * `temperatures` - Iterator providing the cooling schedule temperatures
+/// * `options` - Chain length, Boltzmann constant, stopping and observation settings
+/// * `rng` - Random number generator driving the acceptance test
+/// * `observer` - Callback receiving progress snapshots
 ///
 /// # Type Parameters
 ///
 /// * `T` - Type representing a state/solution in the search space
-/// * `E` - Type of the energy function `Fn(&T) -> f32`
-/// * `F` - Type of the neighbor function `Fn(&T) -> T`
-/// * `G` - Type of the temperature iterator `Iterator<Item = f32>`
+/// * `N` - Floating point type of energies, temperatures and `k` (`f32` or `f64`)
+/// * `M` - Type of the moves, implementing [`Move<T>`]
+/// * `E` - Type of the energy function `FnMut(&T) -> N`
+/// * `D` - Type of the incremental energy function `FnMut(&T, &M) -> N`
+/// * `P` - Type of the move generator `FnMut(&T) -> M`
+/// * `G` - Type of the temperature iterator `Iterator<Item = N>`
+/// * `O` - Type of the observer `FnMut(&Progress<T, N>) -> ControlFlow<()>`
+/// * `R` - Type of the random number generator
 ///
 /// # Panics
 ///
 /// Panics if the Boltzmann constant `k` is not positive.
-pub fn minimize<T, E, F, G>(
-    chain_length: usize,
-    k: f32,
+#[allow(clippy::too_many_arguments)]
+pub fn minimize_delta<T, N, M, E, D, P, G, O, R>(
     start: T,
-    energy: E,
-    neighbour: F,
+    mut energy: E,
+    mut delta_energy: D,
+    mut propose: P,
     temperatures: G,
-    random_seed: u64,
-) -> T
+    options: &Options<N>,
+    rng: &mut R,
+    observer: O,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    M: Move<T>,
+    E: FnMut(&T) -> N,
+    D: FnMut(&T, &M) -> N,
+    P: FnMut(&T) -> M,
+    G: Iterator<Item = N>,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+{
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let start_energy = energy(&start);
+    let mut since_recompute = 0;
+    let transition = |chain: &mut Chain<T, N>, kt: N, rng: &mut R| {
+        let proposal = propose(&chain.state);
+        let en = chain.energy + delta_energy(&chain.state, &proposal);
+        chain.evaluations += 1;
+        if en.is_nan() || !metropolis(rng, chain.energy, en, kt) {
+            return false;
+        }
+
+        proposal.apply(&mut chain.state);
+        since_recompute += 1;
+        if options.recompute_interval > 0 && since_recompute >= options.recompute_interval {
+            since_recompute = 0;
+            chain.energy = energy(&chain.state);
+        } else {
+            chain.energy = en;
+        }
+        true
+    };
+
+    anneal(
+        start,
+        start_energy,
+        transition,
+        temperatures,
+        options,
+        rng,
+        observer,
+    )
+}
+
+/// Minimize an objective function through sequential simulated annealing with an adaptive
+/// step size.
+///
+/// The neighbour receives a step scale alongside the current state. After every temperature
+/// the scale is grown when the acceptance ratio exceeds [`StepAdaptation::target_acceptance`]
+/// and shrunk when it falls short, so large steps are taken while the system is hot and
+/// finer ones once it cools down. Temperatures without iterations and the final quench keep
+/// the current scale. [`Options::resampling`] is ignored and every proposal is evaluated once.
+///
+/// # Arguments
+///
+/// * `start` - Initial state/solution
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
+/// * `neighbour` - [`AdaptiveNeighbour`] sampling a neighbour at a given scale
+/// * `temperatures` - Iterator providing the cooling schedule temperatures
+/// * `options` - Chain length, Boltzmann constant, stopping and observation settings
+/// * `adaptation` - Initial scale, target acceptance ratio and adjustment rate
+/// * `rng` - Random number generator driving the acceptance test
+/// * `observer` - Callback receiving progress snapshots
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+#[allow(clippy::too_many_arguments)]
+pub fn minimize_adaptive<T, N, E, A, G, O, R>(
+    start: T,
+    mut energy: E,
+    mut neighbour: A,
+    temperatures: G,
+    options: &Options<N>,
+    adaptation: &StepAdaptation,
+    rng: &mut R,
+    observer: O,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    A: AdaptiveNeighbour<T>,
+    G: Iterator<Item = N>,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+{
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let start_energy = energy(&start);
+    // Read by every proposal and adapted once per completed temperature.
+    let scale = Cell::new(adaptation.initial_scale);
+    let transition = |chain: &mut Chain<T, N>, kt: N, rng: &mut R| {
+        let n = neighbour.neighbour(&chain.state, scale.get());
+        let en = energy(&n);
+        chain.evaluations += 1;
+        let accept = !en.is_nan() && metropolis(rng, chain.energy, en, kt);
+        if accept {
+            chain.state = n;
+            chain.energy = en;
+        }
+        accept
+    };
+    let adapt = |chain: &Chain<T, N>, _: &R| {
+        let step = chain.acceptances.len() - 1;
+        let proposed = options.chain_length_at(step);
+        // A temperature without proposals says nothing about the step size.
+        if proposed > 0 {
+            let acceptance = chain.acceptances[step] as f32 / proposed as f32;
+            scale.set(adaptation.adapt(scale.get(), acceptance));
+        }
+    };
+
+    anneal_from(
+        Chain::new(start, start_energy),
+        transition,
+        temperatures,
+        options,
+        rng,
+        observer,
+        adapt,
+    )
+}
+
+/// Minimize an objective function through sequential simulated annealing with a neighbour
+/// function that is told the current temperature.
+///
+/// Operators can then shrink their steps as the system cools, e.g.
+/// `|x, t| random_perturbation(x, 0.1 * t.sqrt())`. The temperature passed is the schedule's,
+/// including any [`Restart::temperature_bump`], and zero while quenching. Otherwise behaves
+/// like [`minimize_with`].
+///
+/// # Arguments
+///
+/// * `start` - Initial state/solution
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
+/// * `neighbour` - [`TemperatureNeighbour`] sampling a neighbour at a given temperature
+/// * `temperatures` - Iterator providing the cooling schedule temperatures
+/// * `options` - Chain length, Boltzmann constant, stopping and observation settings
+/// * `rng` - Random number generator driving the acceptance test
+/// * `observer` - Callback receiving progress snapshots
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn minimize_tempered<T, N, E, A, G, O, R>(
+    start: T,
+    mut energy: E,
+    mut neighbour: A,
+    temperatures: G,
+    options: &Options<N>,
+    rng: &mut R,
+    observer: O,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    A: TemperatureNeighbour<T, N>,
+    G: Iterator<Item = N>,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+{
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let k = options.k;
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    let start_energy = estimate(&mut energy, &start, samples);
+    anneal_from(
+        Chain {
+            evaluations: samples,
+            ..Chain::new(start, start_energy)
+        },
+        replace_kernel(
+            energy,
+            |x: &T, kt: N, _: &mut R| neighbour.neighbour(x, kt / k),
+            options.resampling,
+        ),
+        temperatures,
+        options,
+        rng,
+        observer,
+        |_, _| {},
+    )
+}
+
+/// Minimize an objective function through sequential simulated annealing with a
+/// [`LocalSearchOp`], such as a closure, a boxed operator or [`crate::lsops::Mixture`].
+///
+/// Every proposal gets a [`Context`] holding `rng`, which also drives the acceptance test,
+/// and the current temperature as in [`minimize_tempered`], and the operator receives its
+/// energy decrease through [`LocalSearchOp::feedback`]. Otherwise behaves like
+/// [`minimize_with`].
+///
+/// # Arguments
+///
+/// * `start` - Initial state/solution
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
+/// * `operator` - [`LocalSearchOp`] sampling a neighbour of the current state
+/// * `temperatures` - Iterator providing the cooling schedule temperatures
+/// * `options` - Chain length, Boltzmann constant, stopping and observation settings
+/// * `rng` - Random number generator driving the proposals and the acceptance test
+/// * `observer` - Callback receiving progress snapshots
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn minimize_op<T, N, E, P, G, O, R>(
+    start: T,
+    mut energy: E,
+    operator: P,
+    temperatures: G,
+    options: &Options<N>,
+    rng: &mut R,
+    observer: O,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    E: FnMut(&T) -> N,
+    P: LocalSearchOp<T>,
+    G: Iterator<Item = N>,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+{
+    assert!(options.k > N::zero(), "Boltzmann constant must be positive");
+
+    let k = options.k;
+    let samples = options.resampling.map_or(1, |r| r.samples.max(1));
+    let start_energy = estimate(&mut energy, &start, samples);
+    // Proposals and feedback take turns at the operator.
+    let operator = RefCell::new(operator);
+    anneal_from(
+        Chain {
+            evaluations: samples,
+            ..Chain::new(start, start_energy)
+        },
+        feedback_kernel(
+            energy,
+            |x: &T, kt: N, rng: &mut R| {
+                let mut context = Context {
+                    temperature: (kt / k).to_f32(),
+                    ..Context::new(rng)
+                };
+                operator.borrow_mut().propose(x, &mut context)
+            },
+            options.resampling,
+            |improvement: N| {
+                if let Some(improvement) = improvement.to_f32() {
+                    operator.borrow_mut().feedback(improvement);
+                }
+            },
+        ),
+        temperatures,
+        options,
+        rng,
+        observer,
+        |_, _| {},
+    )
+}
+
+/// Minimize a bounded continuous function with Ingber's Adaptive Simulated Annealing
+/// (ASA, formerly Very Fast Simulated Reannealing).
+///
+/// Every parameter has its own temperature. A candidate is drawn per dimension from ASA's
+/// fat-tailed generating distribution, scaled to the parameter's range and resampled until it
+/// lies within `bounds`. Temperatures decay as `T0 * exp(-c * k^(1/D))`, which is far faster
+/// than Boltzmann annealing while still allowing global exploration. Every
+/// [`AsaOptions::reanneal_interval`] accepted states, the sensitivity of the energy to each
+/// parameter is estimated around the best state, and the parameter temperatures are rescaled
+/// so that insensitive parameters keep searching wider ranges.
+///
+/// [`RunResult::acceptances`] holds the number of accepted states per reannealing cycle, and
+/// the run ends with [`Termination::BudgetExhausted`].
+///
+/// # Arguments
+///
+/// * `start` - Initial point, within `bounds`
+/// * `bounds` - Inclusive `(lower, upper)` bounds of every parameter
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a point
+/// * `options` - Evaluation budget and schedule parameters
+/// * `rng` - Random number generator driving generation and acceptance
+///
+/// # Panics
+///
+/// Panics if `start` and `bounds` differ in length, or if a lower bound is not below its
+/// upper bound.
+pub fn asa_minimize<N, E, R>(
+    start: Vec<N>,
+    bounds: &[(N, N)],
+    mut energy: E,
+    options: &AsaOptions<N>,
+    rng: &mut R,
+) -> RunResult<Vec<N>, N>
 where
-    E: Fn(&T) -> f32,
-    F: Fn(&T) -> T,
-    G: Iterator<Item = f32>,
+    N: Float,
+    E: FnMut(&[N]) -> N,
+    R: Rand,
 {
+    assert_eq!(
+        start.len(),
+        bounds.len(),
+        "one bound is required per parameter"
+    );
+    assert!(
+        bounds.iter().all(|&(lo, hi)| lo < hi),
+        "lower bounds must be below upper bounds"
+    );
+
+    let cast = |x: f64| N::from(x).unwrap_or_else(N::nan);
+    let dimension = cast(bounds.len() as f64);
+    let c = -options.temperature_ratio_scale.ln()
+        * (-options.temperature_anneal_scale.ln() / dimension).exp();
+    let decay = |t0: N, k: N| t0 * (-c * k.powf(dimension.recip())).exp();
+
     let mut x = start;
     let mut ex = energy(&x);
-    let mut rand = StdRand::seed(random_seed);
+    let mut best_state = x.clone();
+    let mut best_energy = ex;
+    let mut evaluations = 1;
+
+    let mut initial_temperatures = vec![options.initial_parameter_temperature; bounds.len()];
+    let mut temperatures = initial_temperatures.clone();
+    let mut generated = vec![N::zero(); bounds.len()];
+    let acceptance_t0 = options.initial_acceptance_temperature.unwrap_or_else(|| {
+        if ex.is_zero() {
+            N::one()
+        } else {
+            ex.abs()
+        }
+    });
+    let mut acceptance_temperature = acceptance_t0;
+    let mut acceptance_count = N::zero();
+
+    let mut acceptances = Vec::new();
+    let mut accepted = 0;
+    while evaluations < options.max_evaluations {
+        let candidate: Vec<N> = x
+            .iter()
+            .zip(bounds)
+            .zip(&temperatures)
+            .map(|((&xi, &(lo, hi)), &t)| {
+                // Bounded retries guard against degenerate temperatures; the last draw is clamped.
+                let mut yi = xi;
+                for _ in 0..100 {
+                    let u = cast(uniform(rng));
+                    let step = cast(2.0) * u - N::one();
+                    let y =
+                        step.signum() * t * ((N::one() + t.recip()).powf(step.abs()) - N::one());
+                    yi = xi + y * (hi - lo);
+                    if lo <= yi && yi <= hi {
+                        break;
+                    }
+                }
+                yi.max(lo).min(hi)
+            })
+            .collect();
+
+        let en = energy(&candidate);
+        evaluations += 1;
+        if !en.is_nan() && metropolis(rng, ex, en, acceptance_temperature) {
+            x = candidate;
+            ex = en;
+            accepted += 1;
+            if ex < best_energy {
+                best_state = x.clone();
+                best_energy = ex;
+            }
+        }
+
+        for ((t, &t0), k) in temperatures
+            .iter_mut()
+            .zip(&initial_temperatures)
+            .zip(&mut generated)
+        {
+            *k = *k + N::one();
+            *t = decay(t0, *k);
+        }
+        acceptance_count = acceptance_count + N::one();
+        acceptance_temperature = decay(acceptance_t0, acceptance_count);
+
+        if options.reanneal_interval > 0 && accepted >= options.reanneal_interval {
+            acceptances.push(accepted);
+            accepted = 0;
+
+            // Sensitivity of the energy to each parameter around the best state.
+            let mut sensitivities = Vec::with_capacity(bounds.len());
+            for (i, &(lo, hi)) in bounds.iter().enumerate() {
+                let delta = options.sensitivity_delta * (hi - lo);
+                let mut probe = best_state.clone();
+                probe[i] = if probe[i] + delta <= hi {
+                    probe[i] + delta
+                } else {
+                    probe[i] - delta
+                };
+                let ep = energy(&probe);
+                evaluations += 1;
+                sensitivities.push(((ep - best_energy) / delta).abs());
+            }
+
+            let max_sensitivity = sensitivities
+                .iter()
+                .copied()
+                .filter(|s| s.is_finite())
+                .fold(N::zero(), N::max);
+            for (i, &sensitivity) in sensitivities.iter().enumerate() {
+                if !sensitivity.is_finite() || sensitivity.is_zero() {
+                    continue;
+                }
+                let t = temperatures[i] * max_sensitivity / sensitivity;
+                if t > initial_temperatures[i] {
+                    initial_temperatures[i] = t;
+                }
+                temperatures[i] = t;
+                generated[i] = ((initial_temperatures[i] / t).ln() / c).powf(dimension);
+            }
+        }
+    }
+    if accepted > 0 || acceptances.is_empty() {
+        acceptances.push(accepted);
+    }
+
+    RunResult {
+        best_state,
+        best_energy,
+        evaluations,
+        acceptances,
+        termination: Termination::BudgetExhausted,
+        restarts: 0,
+    }
+}
+
+/// Uniform sample from `[0, 1)` with 53 bits of precision.
+pub(crate) fn uniform<R: Rand + ?Sized>(rng: &mut R) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Transition kernel that proposes a whole new state and replaces the current one on acceptance.
+///
+/// `neighbour(x, kT, rng)` receives the scaled temperature of the proposal and the chain's
+/// random number generator. With `resampling`,
+/// every energy is the mean of several evaluations and the current state's energy is
+/// periodically re-estimated.
+fn replace_kernel<T, N, E, F, R>(
+    energy: E,
+    neighbour: F,
+    resampling: Option<Resampling>,
+) -> impl FnMut(&mut Chain<T, N>, N, &mut R) -> bool
+where
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T, N, &mut R) -> T,
+    R: Rand,
+{
+    feedback_kernel(energy, neighbour, resampling, |_| {})
+}
+
+/// Like [`replace_kernel`], but hands `feedback` the energy decrease of every proposal once it
+/// went through the acceptance test.
+fn feedback_kernel<T, N, E, F, B, R>(
+    mut energy: E,
+    mut neighbour: F,
+    resampling: Option<Resampling>,
+    mut feedback: B,
+) -> impl FnMut(&mut Chain<T, N>, N, &mut R) -> bool
+where
+    N: Float,
+    E: FnMut(&T) -> N,
+    F: FnMut(&T, N, &mut R) -> T,
+    B: FnMut(N),
+    R: Rand,
+{
+    let samples = resampling.map_or(1, |r| r.samples.max(1));
+    move |chain, kt, rng| {
+        if let Some(resampling) = resampling {
+            chain.since_reevaluation += 1;
+            if resampling.reevaluate_interval > 0
+                && chain.since_reevaluation >= resampling.reevaluate_interval
+            {
+                chain.since_reevaluation = 0;
+                chain.energy = estimate(&mut energy, &chain.state, samples);
+                chain.evaluations += samples;
+            }
+        }
+
+        let n = neighbour(&chain.state, kt, rng);
+        let en = estimate(&mut energy, &n, samples);
+        chain.evaluations += samples;
+        let accept = !en.is_nan() && metropolis(rng, chain.energy, en, kt);
+        feedback(chain.energy - en);
+        if accept {
+            chain.state = n;
+            chain.energy = en;
+        }
+        accept
+    }
+}
+
+/// Adapt a plain neighbour function to [`replace_kernel`].
+fn plain_neighbour<T, N, R>(mut neighbour: impl FnMut(&T) -> T) -> impl FnMut(&T, N, &mut R) -> T {
+    move |x, _, _| neighbour(x)
+}
+
+/// Mean of `samples` evaluations of `energy` at `x`.
+fn estimate<T, N, E>(energy: &mut E, x: &T, samples: usize) -> N
+where
+    N: Float,
+    E: FnMut(&T) -> N,
+{
+    if samples == 1 {
+        return energy(x);
+    }
+    let total = (0..samples).fold(N::zero(), |total, _| total + energy(x));
+    total / N::from(samples).unwrap_or_else(N::nan)
+}
+
+/// Annealing loop shared by all sequential entry points.
+///
+/// `transition(chain, kT, rng)` makes one proposal and counts the energy evaluations it
+/// spends. When the proposal is accepted it updates the chain's state and energy and returns
+/// `true`; otherwise it leaves the state untouched and returns `false`.
+fn anneal<T, N, K, G, O, R>(
+    start: T,
+    start_energy: N,
+    transition: K,
+    temperatures: G,
+    options: &Options<N>,
+    rng: &mut R,
+    observer: O,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    K: FnMut(&mut Chain<T, N>, N, &mut R) -> bool,
+    G: Iterator<Item = N>,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+{
+    anneal_from(
+        Chain::new(start, start_energy),
+        transition,
+        temperatures,
+        options,
+        rng,
+        observer,
+        |_, _| {},
+    )
+}
+
+/// Progress of an annealing run that is carried across temperatures.
+struct Chain<T, N> {
+    state: T,
+    energy: N,
+    best_state: T,
+    best_energy: N,
+    evaluations: usize,
+    acceptances: Vec<usize>,
+    since_improvement: usize,
+    restarts: usize,
+    reference_energy: N,
+    stalled: usize,
+    since_reevaluation: usize,
+}
+
+impl<T, N> Chain<T, N> {
+    fn finish(self, termination: Termination) -> RunResult<T, N> {
+        RunResult {
+            best_state: self.best_state,
+            best_energy: self.best_energy,
+            evaluations: self.evaluations,
+            acceptances: self.acceptances,
+            termination,
+            restarts: self.restarts,
+        }
+    }
+}
+
+impl<T: Clone, N: Copy> Chain<T, N> {
+    fn new(start: T, start_energy: N) -> Self {
+        Self {
+            best_state: start.clone(),
+            state: start,
+            energy: start_energy,
+            best_energy: start_energy,
+            evaluations: 1,
+            acceptances: Vec::new(),
+            since_improvement: 0,
+            restarts: 0,
+            reference_energy: start_energy,
+            stalled: 0,
+            since_reevaluation: 0,
+        }
+    }
+}
+
+/// Continue annealing `chain` through `temperatures`, calling `on_step` after every
+/// completed temperature.
+///
+/// Steps are numbered from the number of temperatures `chain` has already processed.
+fn anneal_from<T, N, K, G, O, R, S>(
+    mut chain: Chain<T, N>,
+    mut transition: K,
+    temperatures: G,
+    options: &Options<N>,
+    rng: &mut R,
+    mut observer: O,
+    mut on_step: S,
+) -> RunResult<T, N>
+where
+    T: Clone,
+    N: Float,
+    K: FnMut(&mut Chain<T, N>, N, &mut R) -> bool,
+    G: Iterator<Item = N>,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+    S: FnMut(&Chain<T, N>, &R),
+{
+    let mut termination = Termination::ScheduleExhausted;
+    let started = Instant::now();
 
-    assert!(k > 0.0, "Boltzmann constant must be positive");
+    let first_step = chain.acceptances.len();
+    for (step, temperature) in (first_step..).zip(temperatures) {
+        let converged = match anneal_temperature(
+            &mut chain,
+            &mut transition,
+            temperature,
+            step,
+            options,
+            rng,
+            &mut observer,
+            started,
+        ) {
+            ControlFlow::Continue(converged) => converged,
+            ControlFlow::Break(reason) => {
+                termination = reason;
+                break;
+            }
+        };
+        on_step(&chain, rng);
 
-    for temperature in temperatures {
-        if temperature == 0.0 {
+        let progress = Progress {
+            temperature,
+            step,
+            iteration: None,
+            state: &chain.state,
+            energy: chain.energy,
+            best_energy: chain.best_energy,
+            accepted: chain.acceptances[step],
+            proposed: options.chain_length_at(step),
+        };
+        if observer(&progress).is_break() {
+            termination = Termination::Observer;
+            break;
+        }
+        if converged {
+            termination = Termination::Converged;
             break;
         }
+    }
+
+    if termination.quenches() {
+        quench(&mut chain, &mut transition, options, rng);
+    }
+    chain.finish(termination)
+}
+
+/// Anneal `chain` at the temperature with index `step` of the schedule, and update its
+/// early stopping criterion once it completed.
+///
+/// Breaks with the reason the run stops if it does before the temperature completes;
+/// otherwise continues with whether the run has converged.
+#[allow(clippy::too_many_arguments)]
+fn anneal_temperature<T, N, K, O, R>(
+    chain: &mut Chain<T, N>,
+    transition: &mut K,
+    temperature: N,
+    step: usize,
+    options: &Options<N>,
+    rng: &mut R,
+    observer: &mut O,
+    started: Instant,
+) -> ControlFlow<Termination, bool>
+where
+    T: Clone,
+    N: Float,
+    K: FnMut(&mut Chain<T, N>, N, &mut R) -> bool,
+    O: FnMut(&Progress<T, N>) -> ControlFlow<()>,
+    R: Rand,
+{
+    if temperature <= N::zero()
+        && options.non_positive_temperature == NonPositiveTemperature::Terminate
+    {
+        return ControlFlow::Break(Termination::ZeroTemperature);
+    }
 
-        for _ in 0..chain_length {
-            let n = neighbour(&x);
-            let en = energy(&n);
+    let kt = options.k * temperature;
+    let mut accepted = 0;
+    let mut bump = N::one();
+    for iteration in 0..options.chain_length_at(step) {
+        chain.since_improvement += 1;
+
+        if transition(chain, kt * bump, rng) {
+            accepted += 1;
+
+            if chain.energy < chain.best_energy {
+                chain.best_state = chain.state.clone();
+                chain.best_energy = chain.energy;
+                chain.since_improvement = 0;
+
+                if options
+                    .target_energy
+                    .is_some_and(|target| chain.best_energy <= target)
+                {
+                    chain.acceptances.push(accepted);
+                    return ControlFlow::Break(Termination::TargetReached);
+                }
+            }
+        }
 
-            if en.is_nan() {
-                continue;
+        if let Some(restart) = options.restart {
+            if chain.since_improvement >= restart.patience {
+                chain.state = chain.best_state.clone();
+                chain.energy = chain.best_energy;
+                chain.since_improvement = 0;
+                chain.restarts += 1;
+                bump = restart.temperature_bump;
             }
+        }
+
+        if options
+            .max_evaluations
+            .is_some_and(|budget| chain.evaluations >= budget)
+        {
+            chain.acceptances.push(accepted);
+            return ControlFlow::Break(Termination::BudgetExhausted);
+        }
+
+        if let Some(reason) = interruption(options, started) {
+            chain.acceptances.push(accepted);
+            return ControlFlow::Break(reason);
+        }
 
-            if en < ex {
-                x = n;
-                ex = en;
-                continue;
+        if options.observe_iterations {
+            let progress = Progress {
+                temperature,
+                step,
+                iteration: Some(iteration),
+                state: &chain.state,
+                energy: chain.energy,
+                best_energy: chain.best_energy,
+                accepted,
+                proposed: iteration + 1,
+            };
+            if observer(&progress).is_break() {
+                chain.acceptances.push(accepted);
+                return ControlFlow::Break(Termination::Observer);
             }
+        }
+    }
+    chain.acceptances.push(accepted);
+
+    let mut converged = false;
+    if let Some(criterion) = options.early_stopping {
+        if chain.reference_energy - chain.best_energy > criterion.tolerance {
+            chain.reference_energy = chain.best_energy;
+            chain.stalled = 0;
+        } else {
+            chain.stalled += 1;
+            converged = chain.stalled >= criterion.patience;
+        }
+    }
+    ControlFlow::Continue(converged)
+}
 
-            let p = f64::exp(f64::from((ex - en) / (k * temperature)));
-            if rand.next_bool(Probability::new(p)) {
-                x = n;
-                ex = en;
+/// Greedy zero-temperature quench of `chain` from its best state, see
+/// [`Options::quench_iterations`].
+fn quench<T, N, K, R>(
+    chain: &mut Chain<T, N>,
+    transition: &mut K,
+    options: &Options<N>,
+    rng: &mut R,
+) where
+    T: Clone,
+    N: Float,
+    K: FnMut(&mut Chain<T, N>, N, &mut R) -> bool,
+{
+    if options.quench_iterations == 0 {
+        return;
+    }
+    chain.state = chain.best_state.clone();
+    chain.energy = chain.best_energy;
+    let mut accepted = 0;
+    for _ in 0..options.quench_iterations {
+        if transition(chain, N::zero(), rng) {
+            accepted += 1;
+            if chain.energy < chain.best_energy {
+                chain.best_state = chain.state.clone();
+                chain.best_energy = chain.energy;
             }
         }
     }
-    x
+    chain.acceptances.push(accepted);
 }
 
 /// Minimize an objective function through sequential simulated annealing,
@@ -94,55 +2319,46 @@ where
 /// # Type Parameters
 ///
 /// * `T` - Type representing a state/solution in the search space, must implement Clone
-///         to allow copying solutions between iterations
-/// * `E` - Type of the energy function `Fn(&T) -> f32`, must be callable and 'iter-lifetime bounded
-/// * `F` - Type of the neighbor function `Fn(&T) -> T`, must be callable and 'iter-lifetime bounded
-/// * `G` - Type of the temperature iterator `Iterator<Item = f32>`, must implement Iterator
-///         and be 'iter-lifetime bounded
+///   to allow copying solutions between iterations
+/// * `N` - Floating point type of energies, temperatures and `k` (`f32` or `f64`)
+/// * `E` - Type of the energy function `FnMut(&T) -> N`, must be callable and 'iter-lifetime bounded
+/// * `F` - Type of the neighbor function `FnMut(&T) -> T`, must be callable and 'iter-lifetime bounded
+/// * `G` - Type of the temperature iterator `Iterator<Item = N>`, must implement Iterator
+///   and be 'iter-lifetime bounded
 ///
 /// # Panics
 ///
 /// Panics if the Boltzmann constant `k` is not positive.
-pub fn minimize_lazy<'iter, T, E, F, G>(
+pub fn minimize_lazy<'iter, T, N, E, F, G>(
     chain_length: usize,
-    k: f32,
+    k: N,
     start: T,
-    energy: E,
-    neighbour: F,
+    mut energy: E,
+    mut neighbour: F,
     temperatures: G,
     random_seed: u64,
 ) -> impl Iterator<Item = T> + 'iter
 where
     T: Clone + 'iter,
-    E: Fn(&T) -> f32 + 'iter,
-    F: Fn(&T) -> T + 'iter,
-    G: Iterator<Item = f32> + 'iter,
+    N: Float + 'iter,
+    E: FnMut(&T) -> N + 'iter,
+    F: FnMut(&T) -> T + 'iter,
+    G: Iterator<Item = N> + 'iter,
 {
     let mut x = start;
     let mut ex = energy(&x);
     let mut rand = StdRand::seed(random_seed);
 
-    assert!(k > 0.0, "Boltzmann constant must be positive");
+    assert!(k > N::zero(), "Boltzmann constant must be positive");
 
     temperatures
-        .take_while(|&t| t > 0.0)
+        .take_while(|&t| t > N::zero())
         .map(move |temperature| {
             for _ in 0..chain_length {
                 let n = neighbour(&x);
                 let en = energy(&n);
 
-                if en.is_nan() {
-                    continue;
-                }
-
-                if en < ex {
-                    x = n;
-                    ex = en;
-                    continue;
-                }
-
-                let p = f64::exp(f64::from((ex - en) / (k * temperature)));
-                if rand.next_bool(Probability::new(p)) {
+                if !en.is_nan() && metropolis(&mut rand, ex, en, k * temperature) {
                     x = n;
                     ex = en;
                 }
@@ -150,3 +2366,119 @@ where
             x.clone()
         })
 }
+
+/// Minimize an objective function through sequential simulated annealing,
+/// returning an iterator that yields a [`Sample`] after every inner iteration.
+///
+/// Finer-grained counterpart of [`minimize_lazy`], useful to build detailed traces or
+/// animations of the search trajectory. Each temperature of the schedule produces
+/// `chain_length` samples; the iterator ends when the schedule is exhausted or reaches a
+/// non-positive temperature.
+///
+/// # Arguments
+///
+/// * `chain_length` - Number of iterations to perform at each temperature
+/// * `k` - Boltzmann constant that scales the acceptance probability
+/// * `start` - Initial state/solution
+/// * `energy` - Objective function that evaluates the "energy" (cost) of a state
+/// * `neighbour` - Function that randomly picks a neighboring state from the current one
+/// * `temperatures` - Iterator providing the cooling schedule temperatures
+/// * `random_seed` - Seed for the random number generator
+///
+/// # Type Parameters
+///
+/// * `T` - Type representing a state/solution in the search space, must implement Clone
+///   to allow copying solutions between iterations
+/// * `N` - Floating point type of energies, temperatures and `k` (`f32` or `f64`)
+/// * `E` - Type of the energy function `FnMut(&T) -> N`, must be 'iter-lifetime bounded
+/// * `F` - Type of the neighbor function `FnMut(&T) -> T`, must be 'iter-lifetime bounded
+/// * `G` - Type of the temperature iterator `Iterator<Item = N>`, must be 'iter-lifetime bounded
+///
+/// # Panics
+///
+/// Panics if the Boltzmann constant `k` is not positive.
+pub fn minimize_lazy_steps<'iter, T, N, E, F, G>(
+    chain_length: usize,
+    k: N,
+    start: T,
+    mut energy: E,
+    mut neighbour: F,
+    temperatures: G,
+    random_seed: u64,
+) -> impl Iterator<Item = Sample<T, N>> + 'iter
+where
+    T: Clone + 'iter,
+    N: Float + 'iter,
+    E: FnMut(&T) -> N + 'iter,
+    F: FnMut(&T) -> T + 'iter,
+    G: Iterator<Item = N> + 'iter,
+{
+    let mut x = start;
+    let mut ex = energy(&x);
+    let mut rand = StdRand::seed(random_seed);
+
+    assert!(k > N::zero(), "Boltzmann constant must be positive");
+
+    let mut temperatures = temperatures.take_while(|&t| t > N::zero());
+    let mut temperature = N::zero();
+    let mut iteration = chain_length;
+
+    std::iter::from_fn(move || {
+        while iteration >= chain_length {
+            temperature = temperatures.next()?;
+            iteration = 0;
+        }
+
+        let n = neighbour(&x);
+        let en = energy(&n);
+        if !en.is_nan() && metropolis(&mut rand, ex, en, k * temperature) {
+            x = n;
+            ex = en;
+        }
+
+        let sample = Sample {
+            temperature,
+            iteration,
+            state: x.clone(),
+            energy: ex,
+        };
+        iteration += 1;
+        Some(sample)
+    })
+}
+
+/// Check the cooperative stop conditions of `options`.
+fn interruption<N>(options: &Options<N>, started: Instant) -> Option<Termination> {
+    if options
+        .cancel
+        .as_ref()
+        .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    {
+        Some(Termination::Cancelled)
+    } else if options
+        .time_limit
+        .is_some_and(|limit| started.elapsed() >= limit)
+    {
+        Some(Termination::TimeLimit)
+    } else {
+        None
+    }
+}
+
+/// Metropolis criterion: always accept improvements, otherwise accept with
+/// probability `exp((ex - en) / kt)`, or never if `kt` is not positive. The exponent is
+/// evaluated in the energy's precision, then clamped to non-positive values and exponentiated
+/// in `f64`, so that the probability stays within `[0, 1]`; a NaN exponent rejects the move.
+fn metropolis<N: Float, R: Rand>(rand: &mut R, ex: N, en: N, kt: N) -> bool {
+    if en < ex {
+        return true;
+    }
+    if kt <= N::zero() {
+        return false;
+    }
+    let exponent = ((ex - en) / kt).to_f64().unwrap_or(f64::NAN);
+    if exponent.is_nan() {
+        return false;
+    }
+    rand.next_bool(Probability::new(exponent.min(0.0).exp()))
+}
@@ -1,9 +1,50 @@
 //! Implement test functions for use as objectives in optmization.
+//!
+//! Every function is generic over the element type of its points, see [`Real`], so that the
+//! same definitions serve fast single-precision runs and double-precision validation.
 
-use std::f32::consts::{E, PI};
+use std::f64::consts::{E, PI};
 
 use arrayfire as af;
 
+use crate::parsa::Element;
+
+/// Element types the test functions are defined over: `f32` and `f64`.
+///
+/// The constants of every function are written in double precision, and rounded to the element
+/// type before they reach the device.
+pub trait Real:
+    Element
+    + af::FloatingPoint
+    + af::HasAfEnum<AbsOutType = Self, AggregateOutType = Self, ProductOutType = Self>
+{
+}
+
+impl Real for f32 {}
+impl Real for f64 {}
+
+/// Multiply every element of `x` by the constant `a`.
+fn scaled<T: Real>(x: &af::Array<T>, a: f64) -> af::Array<T> {
+    af::mul(x, &T::fromf64(a), false)
+}
+
+/// Add the constant `b` to every element of `x`.
+fn plus<T: Real>(x: &af::Array<T>, b: f64) -> af::Array<T> {
+    af::add(x, &T::fromf64(b), false)
+}
+
+/// Copy host constants to a device array of the element type `T`.
+fn constants<T: Real>(values: &[f64], dims: af::Dim4) -> af::Array<T> {
+    let values: Vec<T> = values.iter().map(|&v| T::fromf64(v)).collect();
+    af::Array::new(&values, dims)
+}
+
+/// Column of n values that is 1 in row `i` and 0 elsewhere, to single out one row of a batch.
+fn unit_row<T: Real>(n: u64, i: u64) -> af::Array<T> {
+    let values: Vec<f64> = (0..n).map(|row| if row == i { 1.0 } else { 0.0 }).collect();
+    constants(&values, af::dim4!(n))
+}
+
 /// The Ackley function is a continuous, non-convex and widely used for testing
 /// optimization algorithms. It has a global minimum of 0 at x = 0.
 /// Mathematically,
@@ -12,36 +53,66 @@ use arrayfire as af;
 /// ```
 /// # Parameters
 /// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
-///     So, an input array of dim4(3,2) will evaluate the ackley funciton on two 3d vectors.
+///   So, an input array of dim4(3,2) will evaluate the ackley funciton on two 3d vectors.
 ///
 /// # Returns
 /// - Array containing the Ackley function value applied along the first dimension.
 ///
-pub fn ackley(x: &af::Array<f32>) -> af::Array<f32> {
-    const A: f32 = 20.0;
-    const B: f32 = 0.2;
-    const C: f32 = 2.0 * PI;
+pub fn ackley<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    const A: f64 = 20.0;
+    const B: f64 = 0.2;
+    const C: f64 = 2.0 * PI;
 
     let x2 = x * x;
-    let n = x.dims()[0] as f32;
+    let n = x.dims()[0] as f64;
 
-    let rmx2 = af::sqrt(&(af::sum(&x2, 0) / n));
+    let rmx2 = af::sqrt(&scaled(&af::sum(&x2, 0), 1.0 / n));
 
-    let mcosx = af::sum(&af::cos(&(C * x)), 0) / n;
+    let mcosx = scaled(&af::sum(&af::cos(&scaled(x, C)), 0), 1.0 / n);
 
     // Formula
-    -A * af::exp(&(-B * rmx2)) - af::exp(&mcosx) + A + E
+    plus(&scaled(&af::exp(&scaled(&rmx2, -B)), -A), A + E) - af::exp(&mcosx)
 }
 
-/// "Flat" variant of the Ackley function that returns a single f32 value after flattening the input.
-pub fn ackley_flat(x: &af::Array<f32>) -> f32 {
+/// "Flat" variant of the Ackley function that returns a single value after flattening the input.
+pub fn ackley_flat<T: Real>(x: &af::Array<T>) -> T {
     let x = af::flat(x);
     let result = ackley(&x);
-    let mut host_val = [0.0f32];
+    let mut host_val = [T::fromf64(0.0)];
     result.host(&mut host_val);
     host_val[0]
 }
 
+/// Gradient of the Ackley function. At the origin, where the function is not differentiable,
+/// the first term is taken to be 0.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = 4 * exp(-0.2 * r) * x_i / (n * r) + 2π/n * exp(1/n * sum(cos(2π*x_j))) * sin(2π*x_i)
+/// ```
+/// where r = sqrt(1/n * sum(x_j^2)) and n is the dimension of x.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn ackley_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    const A: f64 = 20.0;
+    const B: f64 = 0.2;
+    const C: f64 = 2.0 * PI;
+
+    let n = x.dims()[0] as f64;
+    let rmx2 = af::sqrt(&scaled(&af::sum(&(x * x), 0), 1.0 / n));
+    let mcosx = scaled(&af::sum(&af::cos(&scaled(x, C)), 0), 1.0 / n);
+
+    // The norm only vanishes where every x_i does, and the radial term with it.
+    let radial = scaled(&(af::exp(&scaled(&rmx2, -B)) / &rmx2), A * B / n);
+    let radial = af::selectl(0.0, &af::eq(&rmx2, &T::fromf64(0.0), false), &radial);
+    let angular = af::mul(&af::sin(&scaled(x, C)), &af::exp(&mcosx), true);
+    af::mul(x, &radial, true) + scaled(&angular, C / n)
+}
+
 /// The Rastrigin function is a non-convex function used as a performance test problem for optimization algorithms.
 /// It has a global minimum of 0 at x = 0 and many local minima.
 /// Mathematically,
@@ -56,24 +127,43 @@ pub fn ackley_flat(x: &af::Array<f32>) -> f32 {
 /// # Returns
 /// - Array containing the Rastrigin function value applied along the first dimension.
 ///
-pub fn rastrigin(x: &af::Array<f32>) -> af::Array<f32> {
-    const A: f32 = 10.0;
-    let n = x.dims()[0] as f32;
-    let v = x * x - A * af::cos(&(2.0 * PI * x));
-    A * n + af::sum(&v, 0)
+pub fn rastrigin<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    const A: f64 = 10.0;
+    let n = x.dims()[0] as f64;
+    let v = x * x - scaled(&af::cos(&scaled(x, 2.0 * PI)), A);
+    plus(&af::sum(&v, 0), A * n)
 }
 
-/// "Flat" variant of the Rastrigin function that returns a single f32 value after flattening the input.
-pub fn rastrigin_flat(x: &af::Array<f32>) -> f32 {
+/// "Flat" variant of the Rastrigin function that returns a single value after flattening the input.
+pub fn rastrigin_flat<T: Real>(x: &af::Array<T>) -> T {
     let x = af::flat(x);
     let result = rastrigin(&x);
-    let mut host_val = [0.0f32];
+    let mut host_val = [T::fromf64(0.0)];
     result.host(&mut host_val);
     host_val[0]
 }
 
+/// Gradient of the Rastrigin function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = 2 * x_i + 2πA * sin(2π*x_i)
+/// ```
+/// where A = 10.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn rastrigin_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    const A: f64 = 10.0;
+    scaled(x, 2.0) + scaled(&af::sin(&scaled(x, 2.0 * PI)), 2.0 * PI * A)
+}
+
 /// The Schwefel function is a continuous, multimodal function used as a benchmark for optimization algorithms.
-/// It has a global minimum of 0 at x = 420.9687 and many local minima.
+/// It has a global minimum of 0 at x = 420.9687 and many local minima, over the usual domain
+/// [-500, 500]ⁿ.
 /// Mathematically,
 /// ```other
 /// f(x) = 418.9829n - sum(x_i * sin(sqrt(|x_i|)))
@@ -86,19 +176,1889 @@ pub fn rastrigin_flat(x: &af::Array<f32>) -> f32 {
 /// # Returns
 /// - Array containing the Schwefel function value applied along the first dimension.
 ///
-pub fn schwefel(x: &af::Array<f32>) -> af::Array<f32> {
-    const A: f32 = 418.9829;
-    let n = x.dims()[0] as f32;
+pub fn schwefel<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    const A: f64 = 418.9829;
+    let n = x.dims()[0] as f64;
 
-    let v = x * af::sin(&af::sqrt(x));
-    A * n - af::sum(&v, 0)
+    let v = x * af::sin(&af::sqrt(&af::abs(x)));
+    plus(&-af::sum(&v, 0), A * n)
 }
 
-/// "Flat" variant of the Schwefel function that returns a single f32 value after flattening the input.
-pub fn schwefel_flat(x: &af::Array<f32>) -> f32 {
+/// "Flat" variant of the Schwefel function that returns a single value after flattening the input.
+pub fn schwefel_flat<T: Real>(x: &af::Array<T>) -> T {
     let x = af::flat(x);
     let result = schwefel(&x);
-    let mut host_val = [0.0f32];
+    let mut host_val = [T::fromf64(0.0)];
+    result.host(&mut host_val);
+    host_val[0]
+}
+
+/// Gradient of the Schwefel function. It is continuous, and defined at x_i = 0 too.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = -sin(sqrt(|x_i|)) - sqrt(|x_i|) * cos(sqrt(|x_i|)) / 2
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn schwefel_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let root = af::sqrt(&af::abs(x));
+    -(af::sin(&root) + scaled(&(&root * af::cos(&root)), 0.5))
+}
+
+/// The Levy function is a multimodal function whose variables are mapped through
+/// `w_i = 1 + (x_i - 1) / 4` before entering squared sines. It has a global minimum of 0 at
+/// x = (1, ..., 1).
+/// Mathematically,
+/// ```other
+/// f(x) = sin²(π*w_1) + sum_{i<n}((w_i - 1)² * (1 + 10 * sin²(π*w_i + 1))) + (w_n - 1)² * (1 + sin²(2π*w_n))
+/// ```
+/// where n is the dimension of x.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Levy function value applied along the first dimension.
+///
+pub fn levy<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0] as i64;
+    let w = plus(&scaled(x, 0.25), 0.75);
+    let square = |a: &af::Array<T>| a * a;
+
+    let first = square(&af::sin(&scaled(&af::row(&w, 0), PI)));
+    // Every row has a coupling term, but the last one's is replaced by its own tail term.
+    let ripple = square(&af::sin(&plus(&scaled(&w, PI), 1.0)));
+    let terms = square(&plus(&w, -1.0)) * plus(&scaled(&ripple, 10.0), 1.0);
+    let last = af::row(&w, n - 1);
+    let tail = square(&plus(&last, -1.0)) * plus(&square(&af::sin(&scaled(&last, 2.0 * PI))), 1.0);
+
+    first + af::sum(&terms, 0) - af::row(&terms, n - 1) + tail
+}
+
+/// "Flat" variant of the Levy function that returns a single value after flattening the input.
+pub fn levy_flat<T: Real>(x: &af::Array<T>) -> T {
+    let x = af::flat(x);
+    let result = levy(&x);
+    let mut host_val = [T::fromf64(0.0)];
+    result.host(&mut host_val);
+    host_val[0]
+}
+
+/// Gradient of the Levy function, through the chain rule for w_i = 1 + (x_i - 1)/4.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = 1/4 * ∂f/∂w_i
+/// ∂f/∂w_i = 2 * (w_i - 1) * (1 + 10 * sin²(π*w_i + 1)) + 10π * (w_i - 1)² * sin(2π*w_i + 2), for i < n
+/// ∂f/∂w_n = 2 * (w_n - 1) * (1 + sin²(2π*w_n)) + 2π * (w_n - 1)² * sin(4π*w_n)
+/// ```
+/// where ∂f/∂w_1 gains a further π * sin(2π*w_1) from the first term.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn levy_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    let w = plus(&scaled(x, 0.25), 0.75);
+    let w1 = plus(&w, -1.0);
+    let square = |a: &af::Array<T>| a * a;
+
+    // Every derivative is evaluated on every row, and unit rows pick out where each applies.
+    let first = scaled(&af::sin(&scaled(&w, 2.0 * PI)), PI);
+    let phase = plus(&scaled(&w, PI), 1.0);
+    let coupling = scaled(&w1, 2.0) * plus(&scaled(&square(&af::sin(&phase)), 10.0), 1.0)
+        + scaled(&(square(&w1) * af::sin(&scaled(&phase, 2.0))), 10.0 * PI);
+    let tail = scaled(&w1, 2.0) * plus(&square(&af::sin(&scaled(&w, 2.0 * PI))), 1.0)
+        + scaled(&(square(&w1) * af::sin(&scaled(&w, 4.0 * PI))), 2.0 * PI);
+
+    let (head, last) = (unit_row::<T>(n, 0), unit_row::<T>(n, n - 1));
+    let body = af::mul(&plus(&-&last, 1.0), &coupling, true);
+    let grad = body + af::mul(&last, &tail, true) + af::mul(&head, &first, true);
+    scaled(&grad, 0.25)
+}
+
+/// The sphere function is the sum of squares, a convex and separable baseline. Any optimizer
+/// should solve it easily, which makes it useful for smoke tests and for measuring overhead.
+/// It has a global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = sum(x_i^2)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the sphere function value applied along the first dimension.
+///
+pub fn sphere<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    af::sum(&(x * x), 0)
+}
+
+/// "Flat" variant of the sphere function that returns a single value after flattening the input.
+pub fn sphere_flat<T: Real>(x: &af::Array<T>) -> T {
+    let x = af::flat(x);
+    let result = sphere(&x);
+    let mut host_val = [T::fromf64(0.0)];
     result.host(&mut host_val);
     host_val[0]
 }
+
+/// Gradient of the sphere function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = 2 * x_i
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn sphere_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    scaled(x, 2.0)
+}
+
+/// The sum of different powers function is convex and unimodal, but every variable enters with
+/// a higher power than the previous one, so the later variables are very flat near the optimum.
+/// It has a global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = sum(|x_i|^(i + 1))
+/// ```
+/// for i = 1, ..., n.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the sum of different powers function value applied along the first dimension.
+///
+pub fn sum_of_different_powers<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let powers = plus(&af::range::<T>(af::dim4!(x.dims()[0]), 0), 2.0);
+    af::sum(&af::pow(&af::abs(x), &powers, true), 0)
+}
+
+/// "Flat" variant of the sum of different powers function that returns a single value after flattening the input.
+pub fn sum_of_different_powers_flat<T: Real>(x: &af::Array<T>) -> T {
+    let x = af::flat(x);
+    let result = sum_of_different_powers(&x);
+    let mut host_val = [T::fromf64(0.0)];
+    result.host(&mut host_val);
+    host_val[0]
+}
+
+/// Gradient of the sum of different powers function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = (i + 1) * x_i * |x_i|^(i - 1)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn sum_of_different_powers_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let exponents = af::range::<T>(af::dim4!(x.dims()[0]), 0);
+    let powers = af::pow(&af::abs(x), &exponents, true);
+    af::mul(&plus(&exponents, 2.0), &(x * powers), true)
+}
+
+/// Split a batch of 2D points into its x and y rows.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+fn planar<T: Real>(x: &af::Array<T>, name: &str) -> (af::Array<T>, af::Array<T>) {
+    assert!(
+        x.dims()[0] == 2,
+        "the {name} function is only defined in two dimensions"
+    );
+    (af::row(x, 0), af::row(x, 1))
+}
+
+/// Evaluate `f` on `x` flattened to a single point and copy the value to the host.
+fn evaluate_flat<T: Real>(f: fn(&af::Array<T>) -> af::Array<T>, x: &af::Array<T>) -> T {
+    let mut host_val = [T::fromf64(0.0)];
+    f(&af::flat(x)).host(&mut host_val);
+    host_val[0]
+}
+
+/// Himmelblau's function is a 2D polynomial with four global minima of 0, at
+/// (3, 2), (-2.805118, 3.131312), (-3.779310, -3.283186) and (3.584428, -1.848126),
+/// and a local maximum at (-0.270845, -0.923039).
+/// Mathematically,
+/// ```other
+/// f(x, y) = (x^2 + y - 11)^2 + (x + y^2 - 7)^2
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Himmelblau function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn himmelblau<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Himmelblau");
+    let a = plus(&(&x * &x + &y), -11.0);
+    let b = plus(&(&x + &y * &y), -7.0);
+    &a * &a + &b * &b
+}
+
+/// "Flat" variant of the Himmelblau function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn himmelblau_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(himmelblau, x)
+}
+
+/// Gradient of Himmelblau's function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x = 4x * (x^2 + y - 11) + 2 * (x + y^2 - 7)
+/// ∂f/∂y = 2 * (x^2 + y - 11) + 4y * (x + y^2 - 7)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn himmelblau_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Himmelblau");
+    let a = plus(&(&x * &x + &y), -11.0);
+    let b = plus(&(&x + &y * &y), -7.0);
+    let dx = scaled(&(&x * &a), 4.0) + scaled(&b, 2.0);
+    let dy = scaled(&a, 2.0) + scaled(&(&y * &b), 4.0);
+    af::join(0, &dx, &dy)
+}
+
+/// The Booth function is a 2D quadratic with a single global minimum of 0 at (1, 3).
+/// Mathematically,
+/// ```other
+/// f(x, y) = (x + 2y - 7)^2 + (2x + y - 5)^2
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Booth function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn booth<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Booth");
+    let a = plus(&(&x + scaled(&y, 2.0)), -7.0);
+    let b = plus(&(scaled(&x, 2.0) + &y), -5.0);
+    &a * &a + &b * &b
+}
+
+/// "Flat" variant of the Booth function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn booth_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(booth, x)
+}
+
+/// Gradient of the Booth function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x = 2 * (x + 2y - 7) + 4 * (2x + y - 5)
+/// ∂f/∂y = 4 * (x + 2y - 7) + 2 * (2x + y - 5)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn booth_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Booth");
+    let a = plus(&(&x + scaled(&y, 2.0)), -7.0);
+    let b = plus(&(scaled(&x, 2.0) + &y), -5.0);
+    let dx = scaled(&a, 2.0) + scaled(&b, 4.0);
+    let dy = scaled(&a, 4.0) + scaled(&b, 2.0);
+    af::join(0, &dx, &dy)
+}
+
+/// The Beale function is a 2D polynomial with sharp peaks at the corners of the usual domain
+/// [-4.5, 4.5]². It has a single global minimum of 0 at (3, 0.5).
+/// Mathematically,
+/// ```other
+/// f(x, y) = (1.5 - x + xy)^2 + (2.25 - x + xy^2)^2 + (2.625 - x + xy^3)^2
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Beale function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn beale<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Beale");
+    let xy = &x * &y;
+    let xy2 = &xy * &y;
+    let xy3 = &xy2 * &y;
+    let a = plus(&(xy - &x), 1.5);
+    let b = plus(&(xy2 - &x), 2.25);
+    let c = plus(&(xy3 - &x), 2.625);
+    &a * &a + &b * &b + &c * &c
+}
+
+/// "Flat" variant of the Beale function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn beale_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(beale, x)
+}
+
+/// Gradient of the Beale function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x = 2a * (y - 1) + 2b * (y^2 - 1) + 2c * (y^3 - 1)
+/// ∂f/∂y = 2a * x + 4b * xy + 6c * xy^2
+/// ```
+/// where a = 1.5 - x + xy, b = 2.25 - x + xy^2 and c = 2.625 - x + xy^3.
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn beale_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Beale");
+    let y2 = &y * &y;
+    let y3 = &y2 * &y;
+    let a = plus(&(&x * &y - &x), 1.5);
+    let b = plus(&(&x * &y2 - &x), 2.25);
+    let c = plus(&(&x * &y3 - &x), 2.625);
+    let dx = scaled(
+        &(&a * plus(&y, -1.0) + &b * plus(&y2, -1.0) + &c * plus(&y3, -1.0)),
+        2.0,
+    );
+    let dy = &x * (scaled(&a, 2.0) + scaled(&(&b * &y), 4.0) + scaled(&(&c * &y2), 6.0));
+    af::join(0, &dx, &dy)
+}
+
+/// The Eggholder function is a 2D function with a deep, irregular landscape of many local
+/// minima, usually evaluated on [-512, 512]². It has a global minimum of about -959.6407 at
+/// (512, 404.2319), on the boundary of that domain.
+/// Mathematically,
+/// ```other
+/// f(x, y) = -(y + 47) * sin(sqrt(|x/2 + y + 47|)) - x * sin(sqrt(|x - (y + 47)|))
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Eggholder function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn eggholder<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Eggholder");
+    let shifted = plus(&y, 47.0);
+    // Both square roots take absolute values, so points left of or below the centre are valid.
+    let inner = af::sin(&af::sqrt(&af::abs(&(scaled(&x, 0.5) + &shifted))));
+    let outer = af::sin(&af::sqrt(&af::abs(&(&x - &shifted))));
+    -(shifted * inner) - x * outer
+}
+
+/// "Flat" variant of the Eggholder function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn eggholder_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(eggholder, x)
+}
+
+/// The Drop-Wave function is a 2D function of concentric ripples that decay away from the
+/// origin. It has a global minimum of -1 at (0, 0).
+/// Mathematically,
+/// ```other
+/// f(x, y) = -(1 + cos(12 * sqrt(x^2 + y^2))) / (0.5 * (x^2 + y^2) + 2)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Drop-Wave function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn drop_wave<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Drop-Wave");
+    let r2 = &x * &x + &y * &y;
+    -plus(&af::cos(&scaled(&af::sqrt(&r2), 12.0)), 1.0) / plus(&scaled(&r2, 0.5), 2.0)
+}
+
+/// "Flat" variant of the Drop-Wave function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn drop_wave_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(drop_wave, x)
+}
+
+/// The Shubert function is a 2D function with 760 local minima, 18 of which are global with a
+/// value of about -186.7309, e.g. at (-7.0835, 4.8580) and (-7.0835, -7.7083).
+/// Mathematically,
+/// ```other
+/// f(x, y) = sum_{j=1..5}(j * cos((j + 1) * x + j)) * sum_{j=1..5}(j * cos((j + 1) * y + j))
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Shubert function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn shubert<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Shubert");
+    // A column of j = 1, ..., 5, broadcast against the row of every coordinate.
+    let j = plus(&af::range::<T>(af::dim4!(5), 0), 1.0);
+    let series = |v: &af::Array<T>| {
+        let phase = af::add(&af::mul(&plus(&j, 1.0), v, true), &j, true);
+        af::sum(&af::mul(&j, &af::cos(&phase), true), 0)
+    };
+    series(&x) * series(&y)
+}
+
+/// "Flat" variant of the Shubert function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn shubert_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(shubert, x)
+}
+
+/// The Branin (or Branin-Hoo) function is a 2D function commonly used to benchmark surrogate
+/// and Bayesian optimization, usually on [-5, 10] × [0, 15]. It has three global minima of
+/// about 0.397887, at (-π, 12.275), (π, 2.275) and (9.42478, 2.475).
+/// Mathematically,
+/// ```other
+/// f(x, y) = a * (y - b*x^2 + c*x - r)^2 + s * (1 - t) * cos(x) + s
+/// ```
+/// where a = 1, b = 5.1 / (4π²), c = 5 / π, r = 6, s = 10 and t = 1 / (8π).
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Branin function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn branin<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    const A: f64 = 1.0;
+    const B: f64 = 5.1 / (4.0 * PI * PI);
+    const C: f64 = 5.0 / PI;
+    const R: f64 = 6.0;
+    const S: f64 = 10.0;
+    const T: f64 = 1.0 / (8.0 * PI);
+
+    let (x, y) = planar(x, "Branin");
+    let v = plus(&(&y - scaled(&(&x * &x), B) + scaled(&x, C)), -R);
+    plus(
+        &(scaled(&(&v * &v), A) + scaled(&af::cos(&x), S * (1.0 - T))),
+        S,
+    )
+}
+
+/// "Flat" variant of the Branin function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn branin_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(branin, x)
+}
+
+/// Gradient of the Branin function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x = 2a * (y - b*x^2 + c*x - r) * (c - 2b*x) - s * (1 - t) * sin(x)
+/// ∂f/∂y = 2a * (y - b*x^2 + c*x - r)
+/// ```
+/// with the constants of [`branin`].
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn branin_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    const A: f64 = 1.0;
+    const B: f64 = 5.1 / (4.0 * PI * PI);
+    const C: f64 = 5.0 / PI;
+    const R: f64 = 6.0;
+    const S: f64 = 10.0;
+    const T: f64 = 1.0 / (8.0 * PI);
+
+    let (x, y) = planar(x, "Branin");
+    let v = plus(&(&y - scaled(&(&x * &x), B) + scaled(&x, C)), -R);
+    let dx = scaled(&(&v * plus(&scaled(&x, -2.0 * B), C)), 2.0 * A)
+        - scaled(&af::sin(&x), S * (1.0 - T));
+    let dy = scaled(&v, 2.0 * A);
+    af::join(0, &dx, &dy)
+}
+
+/// Weights of the four exponential terms of the Hartmann functions.
+const HARTMANN_ALPHA: [f64; 4] = [1.0, 1.2, 3.0, 3.2];
+
+/// Evaluate a Hartmann function with the given `a` and `p` matrices, given row by row.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not `D`.
+fn hartmann<T: Real, const D: usize>(
+    x: &af::Array<T>,
+    a: &[[f64; D]; 4],
+    p: &[[f64; D]; 4],
+    name: &str,
+) -> af::Array<T> {
+    let dims = x.dims();
+    assert!(
+        dims[0] == D as u64,
+        "the {name} function is only defined in {D} dimensions"
+    );
+    // The constants become 4 × D device matrices, transposed from their row-major layout.
+    let matrix = |rows: &[[f64; D]; 4]| {
+        af::transpose(
+            &constants::<T>(rows.as_flattened(), af::dim4!(D as u64, 4)),
+            false,
+        )
+    };
+    let alpha = constants::<T>(&HARTMANN_ALPHA, af::dim4!(4));
+
+    // Every point is a 1 × D slice along the third dimension, against all four rows at once.
+    let points = dims.elements() / D as u64;
+    let x = af::moddims(x, af::dim4!(1, D as u64, points));
+    let diff = af::sub(&x, &matrix(p), true);
+    let inner = af::sum(&af::mul(&matrix(a), &(&diff * &diff), true), 1);
+    let value = -af::sum(&af::mul(&af::exp(&-inner), &alpha, true), 0);
+    af::moddims(&value, af::dim4!(1, dims[1], dims[2], dims[3]))
+}
+
+/// The 3D Hartmann function is a sum of four Gaussian-like wells on the unit cube [0, 1]³.
+/// It has a global minimum of about -3.86278 at (0.114614, 0.555649, 0.852547).
+/// Mathematically,
+/// ```other
+/// f(x) = -sum_{i=1..4}(α_i * exp(-sum_{j=1..3}(A_ij * (x_j - P_ij)^2)))
+/// ```
+/// with the constants α, A and P of Hartmann (1973) as tabulated by Dixon and Szegö.
+///
+/// # Parameters
+/// - x: Input array of 3D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Hartmann 3D function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 3.
+pub fn hartmann3<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    const A: [[f64; 3]; 4] = [
+        [3.0, 10.0, 30.0],
+        [0.1, 10.0, 35.0],
+        [3.0, 10.0, 30.0],
+        [0.1, 10.0, 35.0],
+    ];
+    const P: [[f64; 3]; 4] = [
+        [0.3689, 0.1170, 0.2673],
+        [0.4699, 0.4387, 0.7470],
+        [0.1091, 0.8732, 0.5547],
+        [0.03815, 0.5743, 0.8828],
+    ];
+    hartmann(x, &A, &P, "Hartmann 3D")
+}
+
+/// "Flat" variant of the Hartmann 3D function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 3 elements.
+pub fn hartmann3_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(hartmann3, x)
+}
+
+/// The 6D Hartmann function is a sum of four Gaussian-like wells on the unit hypercube
+/// [0, 1]⁶. It has a global minimum of about -3.32237 at
+/// (0.20169, 0.150011, 0.476874, 0.275332, 0.311652, 0.6573).
+/// Mathematically,
+/// ```other
+/// f(x) = -sum_{i=1..4}(α_i * exp(-sum_{j=1..6}(A_ij * (x_j - P_ij)^2)))
+/// ```
+/// with the constants α, A and P of Hartmann (1973) as tabulated by Dixon and Szegö.
+///
+/// # Parameters
+/// - x: Input array of 6D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Hartmann 6D function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 6.
+pub fn hartmann6<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    const A: [[f64; 6]; 4] = [
+        [10.0, 3.0, 17.0, 3.5, 1.7, 8.0],
+        [0.05, 10.0, 17.0, 0.1, 8.0, 14.0],
+        [3.0, 3.5, 1.7, 10.0, 17.0, 8.0],
+        [17.0, 8.0, 0.05, 10.0, 0.1, 14.0],
+    ];
+    const P: [[f64; 6]; 4] = [
+        [0.1312, 0.1696, 0.5569, 0.0124, 0.8283, 0.5886],
+        [0.2329, 0.4135, 0.8307, 0.3736, 0.1004, 0.9991],
+        [0.2348, 0.1451, 0.3522, 0.2883, 0.3047, 0.6650],
+        [0.4047, 0.8828, 0.8732, 0.5743, 0.1091, 0.0381],
+    ];
+    hartmann(x, &A, &P, "Hartmann 6D")
+}
+
+/// "Flat" variant of the Hartmann 6D function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 6 elements.
+pub fn hartmann6_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(hartmann6, x)
+}
+
+/// The Dixon-Price function is a valley-shaped function whose terms couple neighbouring
+/// variables with weights growing along the index. It has a global minimum of 0 at
+/// x_i = 2^(-(2^i - 2) / 2^i) for i = 1, ..., n.
+/// Mathematically,
+/// ```other
+/// f(x) = (x_1 - 1)^2 + sum_{i=2..n}(i * (2*x_i^2 - x_{i-1})^2)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Dixon-Price function value applied along the first dimension.
+///
+pub fn dixon_price<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    // Row i of the shifted array holds x_{i-1}; row 0 wraps around and is replaced below.
+    let previous = af::shift(x, &[1, 0, 0, 0]);
+    let weights = plus(&af::range::<T>(af::dim4!(n), 0), 1.0);
+    let coupling = scaled(&(x * x), 2.0) - previous;
+    let terms = af::mul(&weights, &(&coupling * &coupling), true);
+    let first = plus(&af::row(x, 0), -1.0);
+    af::sum(&terms, 0) - af::row(&terms, 0) + &first * &first
+}
+
+/// "Flat" variant of the Dixon-Price function that returns a single value after flattening the input.
+pub fn dixon_price_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(dixon_price, x)
+}
+
+/// Gradient of the Dixon-Price function, where the terms of missing neighbours are left out.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = 8i * x_i * (2*x_i^2 - x_{i-1}) - 2(i + 1) * (2*x_{i+1}^2 - x_i)
+/// ```
+/// and ∂f/∂x_1 gains a further 2 * (x_1 - 1) from the first term.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn dixon_price_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    let head = unit_row::<T>(n, 0);
+    let weights = plus(&af::range::<T>(af::dim4!(n), 0), 1.0) - &head;
+    // Row i holds i * (2*x_i^2 - x_{i-1}), and the wrapped-around row 0 is weighted by 0.
+    let coupling = scaled(&(x * x), 2.0) - af::shift(x, &[1, 0, 0, 0]);
+    let terms = af::mul(&weights, &coupling, true);
+    // Shifting the terms up pairs each x_i with the term of x_{i+1}; the last row receives row 0.
+    let grad = scaled(&(x * &terms), 8.0) - scaled(&af::shift(&terms, &[-1, 0, 0, 0]), 2.0);
+    grad + af::mul(&head, &scaled(&plus(x, -1.0), 2.0), true)
+}
+
+/// The Trid function is a convex quadratic whose neighbouring variables are coupled. It has a
+/// global minimum of -n(n + 4)(n - 1)/6 at x_i = i(n + 1 - i), and is usually evaluated on
+/// [-n², n²]ⁿ.
+/// Mathematically,
+/// ```other
+/// f(x) = sum_{i=1..n}((x_i - 1)^2) - sum_{i=2..n}(x_i * x_{i-1})
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Trid function value applied along the first dimension.
+///
+pub fn trid<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0] as i64;
+    let centred = plus(x, -1.0);
+    // The wrapped-around product x_1 * x_n of the shifted array is not part of the sum.
+    let products = af::sum(&(x * af::shift(x, &[1, 0, 0, 0])), 0);
+    let wrapped = af::row(x, 0) * af::row(x, n - 1);
+    af::sum(&(&centred * &centred), 0) - products + wrapped
+}
+
+/// "Flat" variant of the Trid function that returns a single value after flattening the input.
+pub fn trid_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(trid, x)
+}
+
+/// Gradient of the Trid function, where the terms of missing neighbours are left out.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = 2 * (x_i - 1) - x_{i-1} - x_{i+1}
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn trid_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    // Both shifts wrap around, so the first and last rows drop their missing neighbour.
+    let previous = af::mul(
+        &plus(&-unit_row::<T>(n, 0), 1.0),
+        &af::shift(x, &[1, 0, 0, 0]),
+        true,
+    );
+    let next = af::mul(
+        &plus(&-unit_row::<T>(n, n - 1), 1.0),
+        &af::shift(x, &[-1, 0, 0, 0]),
+        true,
+    );
+    scaled(&plus(x, -1.0), 2.0) - previous - next
+}
+
+/// The Salomon function is a radially symmetric, multimodal function whose value only depends
+/// on the distance from the origin, with ripples on concentric spheres. It has a global minimum
+/// of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = 1 - cos(2π * ||x||) + 0.1 * ||x||
+/// ```
+/// where ||x|| is the Euclidean norm of x.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Salomon function value applied along the first dimension.
+///
+pub fn salomon<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let norm = af::sqrt(&af::sum(&(x * x), 0));
+    plus(
+        &(scaled(&norm, 0.1) - af::cos(&scaled(&norm, 2.0 * PI))),
+        1.0,
+    )
+}
+
+/// "Flat" variant of the Salomon function that returns a single value after flattening the input.
+pub fn salomon_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(salomon, x)
+}
+
+/// The Alpine N.1 function is a separable, multimodal and non-differentiable function. It has
+/// a global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = sum(|x_i * sin(x_i) + 0.1 * x_i|)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Alpine N.1 function value applied along the first dimension.
+///
+pub fn alpine<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    af::sum(&af::abs(&(x * af::sin(x) + scaled(x, 0.1))), 0)
+}
+
+/// "Flat" variant of the Alpine N.1 function that returns a single value after flattening the input.
+pub fn alpine_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(alpine, x)
+}
+
+/// Terms of the series of [`weierstrass`].
+const WEIERSTRASS_K_MAX: u32 = 20;
+
+/// The Weierstrass function is a truncated series of cosines of geometrically growing
+/// frequency: continuous, but nowhere differentiable in the limit, and extremely rugged. It
+/// has a global minimum of 0 at x = 0. This variant keeps 20 terms; see
+/// [`weierstrass_truncated`] for other truncations.
+/// Mathematically,
+/// ```other
+/// f(x) = sum_i(sum_{k=0..k_max}(a^k * cos(2π * b^k * (x_i + 0.5)))) - n * sum_{k=0..k_max}(a^k * cos(π * b^k))
+/// ```
+/// where a = 0.5, b = 3, k_max = 20 and n is the dimension of x.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Weierstrass function value applied along the first dimension.
+///
+pub fn weierstrass<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    weierstrass_truncated(x, WEIERSTRASS_K_MAX)
+}
+
+/// The Weierstrass function truncated after the term `k_max`, as [`weierstrass`]. Fewer terms
+/// give a smoother landscape. High frequencies exceed the precision of `f32` arguments, so in
+/// single precision terms beyond about `k_max = 15` add a little noise, of the order of
+/// `0.5^k_max`, rather than detail; `f64` arguments resolve them.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+/// - `k_max`: Index of the last term of the series.
+///
+/// # Returns
+/// - Array containing the Weierstrass function value applied along the first dimension.
+///
+pub fn weierstrass_truncated<T: Real>(x: &af::Array<T>, k_max: u32) -> af::Array<T> {
+    const A: f64 = 0.5;
+    const B: f64 = 3.0;
+
+    let dims = x.dims();
+    let n = dims[0];
+    let terms = u64::from(k_max) + 1;
+    let (amplitudes, frequencies): (Vec<f64>, Vec<f64>) =
+        (0..=k_max as i32).map(|k| (A.powi(k), B.powi(k))).unzip();
+    // The offset only depends on the dimension, so it is summed in double precision on the host.
+    let offset: f64 = (0..=k_max as i32)
+        .map(|k| A.powi(k) * (PI * B.powi(k)).cos())
+        .sum();
+
+    // Every term of the series is a slice along the third dimension.
+    let series = af::dim4!(1, 1, terms);
+    let amplitudes = constants::<T>(&amplitudes, series);
+    let frequencies = constants::<T>(&frequencies, series);
+    let x = plus(&af::moddims(x, af::dim4!(n, dims.elements() / n)), 0.5);
+    let phases = scaled(&af::mul(&frequencies, &x, true), 2.0 * PI);
+    let value = plus(
+        &af::sum(
+            &af::sum(&af::mul(&amplitudes, &af::cos(&phases), true), 2),
+            0,
+        ),
+        -(n as f64 * offset),
+    );
+    af::moddims(&value, af::dim4!(1, dims[1], dims[2], dims[3]))
+}
+
+/// "Flat" variant of the Weierstrass function that returns a single value after flattening the input.
+pub fn weierstrass_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(weierstrass, x)
+}
+
+/// The Katsuura function of the CEC benchmark suites is a product of fractal series of
+/// fractional parts, continuous but nowhere differentiable. It has a global minimum of 0 at
+/// x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = 10/n^2 * prod_{i=1..n}((1 + i * sum_{j=1..32}(|2^j * x_i - round(2^j * x_i)| / 2^j))^(10 / n^1.2)) - 10/n^2
+/// ```
+/// where n is the dimension of x.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Katsuura function value applied along the first dimension.
+///
+pub fn katsuura<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let dims = x.dims();
+    let n = dims[0];
+    let scale = 10.0 / (n * n) as f64;
+
+    // Every power of two 2^j is a slice along the third dimension.
+    let powers: Vec<f64> = (1..=32).map(|j| 2.0f64.powi(j)).collect();
+    let powers = constants::<T>(&powers, af::dim4!(1, 1, 32));
+    let x = af::moddims(x, af::dim4!(n, dims.elements() / n));
+    let stretched = af::mul(&powers, &x, true);
+    let fractions = af::div(
+        &af::abs(&(&stretched - af::round(&stretched))),
+        &powers,
+        true,
+    );
+    let series = af::sum(&fractions, 2);
+
+    let index = plus(&af::range::<T>(af::dim4!(n), 0), 1.0);
+    let factors = plus(&af::mul(&index, &series, true), 1.0);
+    let exponent = T::fromf64(10.0 / (n as f64).powf(1.2));
+    let product = af::product(&af::pow(&factors, &exponent, false), 0);
+    let value = plus(&scaled(&product, scale), -scale);
+    af::moddims(&value, af::dim4!(1, dims[1], dims[2], dims[3]))
+}
+
+/// "Flat" variant of the Katsuura function that returns a single value after flattening the input.
+pub fn katsuura_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(katsuura, x)
+}
+
+/// The HappyCat function of the CEC benchmark suites has a narrow, curved valley around the
+/// sphere ||x||² = n. It has a global minimum of 0 at x = (-1, ..., -1).
+/// Mathematically,
+/// ```other
+/// f(x) = |sum(x_i^2) - n|^(1/4) + (0.5 * sum(x_i^2) + sum(x_i)) / n + 0.5
+/// ```
+/// where n is the dimension of x.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the HappyCat function value applied along the first dimension.
+///
+pub fn happy_cat<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0] as f64;
+    let squares = af::sum(&(x * x), 0);
+    let valley = af::sqrt(&af::sqrt(&af::abs(&plus(&squares, -n))));
+    valley
+        + plus(
+            &scaled(&(scaled(&squares, 0.5) + af::sum(x, 0)), 1.0 / n),
+            0.5,
+        )
+}
+
+/// "Flat" variant of the HappyCat function that returns a single value after flattening the input.
+pub fn happy_cat_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(happy_cat, x)
+}
+
+/// The expanded Schaffer F6 function sums the 2D Schaffer F6 function over every pair of
+/// consecutive coordinates, cyclically, so that all variables are coupled to their neighbours.
+/// It has a global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = sum_{i=1..n}(g(x_i, x_{i+1})), with x_{n+1} = x_1
+/// g(x, y) = 0.5 + (sin²(sqrt(x^2 + y^2)) - 0.5) / (1 + 0.001 * (x^2 + y^2))^2
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the expanded Schaffer F6 function value applied along the first dimension.
+///
+pub fn expanded_schaffer_f6<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    // Row i of the shifted array holds x_{i+1}, and the last row wraps around to x_1.
+    let next = af::shift(x, &[-1, 0, 0, 0]);
+    let r2 = x * x + &next * &next;
+    let sine = af::sin(&af::sqrt(&r2));
+    let denominator = plus(&scaled(&r2, 0.001), 1.0);
+    let g = plus(
+        &(plus(&(&sine * &sine), -0.5) / (&denominator * &denominator)),
+        0.5,
+    );
+    af::sum(&g, 0)
+}
+
+/// "Flat" variant of the expanded Schaffer F6 function that returns a single value after flattening the input.
+pub fn expanded_schaffer_f6_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(expanded_schaffer_f6, x)
+}
+
+/// The Schaffer F7 function averages a rippled term over every pair of consecutive
+/// coordinates. It has a global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = (1/(n - 1) * sum_{i=1..n-1}(sqrt(s_i) + sqrt(s_i) * sin²(50 * s_i^0.2)))^2
+/// ```
+/// where s_i = sqrt(x_i^2 + x_{i+1}^2) and n is the dimension of x.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Schaffer F7 function value applied along the first dimension.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is less than 2.
+pub fn schaffer_f7<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    assert!(
+        n >= 2,
+        "the Schaffer F7 function needs at least two dimensions"
+    );
+    let next = af::shift(x, &[-1, 0, 0, 0]);
+    let s = af::sqrt(&(x * x + &next * &next));
+    let root = af::sqrt(&s);
+    let sine = af::sin(&scaled(&af::pow(&s, &T::fromf64(0.2), false), 50.0));
+    let terms = &root + &root * &sine * &sine;
+    // The wrapped-around pair (x_n, x_1) of the last row is not part of the sum.
+    let total = af::sum(&terms, 0) - af::row(&terms, n as i64 - 1);
+    let mean = scaled(&total, 1.0 / (n - 1) as f64);
+    &mean * &mean
+}
+
+/// "Flat" variant of the Schaffer F7 function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` has less than 2 elements.
+pub fn schaffer_f7_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(schaffer_f7, x)
+}
+
+/// Conditioning of [`bent_cigar`], [`discus`] and [`ellipsoid`].
+const CONDITIONING: f64 = 1e6;
+
+/// The Bent Cigar function is a quadratic with a single long axis: all but the first variable
+/// are a million times steeper. It has a global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = x_1^2 + 10^6 * sum_{i=2..n}(x_i^2)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Bent Cigar function value applied along the first dimension.
+///
+pub fn bent_cigar<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let first = af::row(x, 0);
+    let first2 = &first * &first;
+    scaled(&(af::sum(&(x * x), 0) - &first2), CONDITIONING) + first2
+}
+
+/// "Flat" variant of the Bent Cigar function that returns a single value after flattening the input.
+pub fn bent_cigar_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(bent_cigar, x)
+}
+
+/// Gradient of the Bent Cigar function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_1 = 2 * x_1
+/// ∂f/∂x_i = 2 * 10^6 * x_i, for i > 1
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn bent_cigar_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    let weights = plus(
+        &scaled(&unit_row::<T>(n, 0), 1.0 - CONDITIONING),
+        CONDITIONING,
+    );
+    scaled(&af::mul(&weights, x, true), 2.0)
+}
+
+/// The Discus function is a quadratic with a single short axis: the first variable is a
+/// million times steeper than the others. It has a global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = 10^6 * x_1^2 + sum_{i=2..n}(x_i^2)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Discus function value applied along the first dimension.
+///
+pub fn discus<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let first = af::row(x, 0);
+    let first2 = &first * &first;
+    af::sum(&(x * x), 0) + scaled(&first2, CONDITIONING - 1.0)
+}
+
+/// "Flat" variant of the Discus function that returns a single value after flattening the input.
+pub fn discus_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(discus, x)
+}
+
+/// Gradient of the Discus function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_1 = 2 * 10^6 * x_1
+/// ∂f/∂x_i = 2 * x_i, for i > 1
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn discus_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    let weights = plus(&scaled(&unit_row::<T>(n, 0), CONDITIONING - 1.0), 1.0);
+    scaled(&af::mul(&weights, x, true), 2.0)
+}
+
+/// The (high-conditioned) ellipsoid function is a separable quadratic whose curvature grows
+/// geometrically from 1 along the first axis to 10^6 along the last. It has a global minimum of
+/// 0 at x = 0. See [`rotated_ellipsoid`] for a non-separable variant.
+/// Mathematically,
+/// ```other
+/// f(x) = sum_{i=1..n}(10^(6 * (i - 1)/(n - 1)) * x_i^2)
+/// ```
+/// where n is the dimension of x, and a single variable has weight 1.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the ellipsoid function value applied along the first dimension.
+///
+pub fn ellipsoid<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    let exponents = scaled(
+        &af::range::<T>(af::dim4!(n), 0),
+        1.0 / (n.max(2) - 1) as f64,
+    );
+    let weights = af::pow(&T::fromf64(CONDITIONING), &exponents, false);
+    af::sum(&af::mul(&weights, &(x * x), true), 0)
+}
+
+/// "Flat" variant of the ellipsoid function that returns a single value after flattening the input.
+pub fn ellipsoid_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(ellipsoid, x)
+}
+
+/// Gradient of the ellipsoid function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = 2 * 10^(6 * (i - 1)/(n - 1)) * x_i
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn ellipsoid_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    let exponents = scaled(
+        &af::range::<T>(af::dim4!(n), 0),
+        1.0 / (n.max(2) - 1) as f64,
+    );
+    let weights = af::pow(&T::fromf64(CONDITIONING), &exponents, false);
+    scaled(&af::mul(&weights, x, true), 2.0)
+}
+
+/// The ellipsoid function evaluated on `rotation * x`, so that its axes are no longer aligned
+/// with the coordinates and variables can't be optimized one at a time. `rotation` should be an
+/// orthogonal matrix; the global minimum stays 0 at x = 0.
+///
+/// # Parameters
+/// - x: Input array of points to evaluate, one per column.
+/// - rotation: Orthogonal n × n matrix, where n is the first dimension of `x`.
+///
+/// # Returns
+/// - Array containing the rotated ellipsoid function value of every point.
+///
+/// # Panics
+///
+/// Panics if `rotation` is not an n × n matrix.
+pub fn rotated_ellipsoid<T: Real>(x: &af::Array<T>, rotation: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    assert!(
+        rotation.dims() == af::dim4!(n, n),
+        "rotation must be an n × n matrix for points of dimension n"
+    );
+    ellipsoid(&af::matmul(
+        rotation,
+        x,
+        af::MatProp::NONE,
+        af::MatProp::NONE,
+    ))
+}
+
+/// The Bohachevsky 1 function is a 2D bowl with separate cosine ripples along each axis. It has a
+/// global minimum of 0 at (0, 0).
+/// Mathematically,
+/// ```other
+/// f(x, y) = x^2 + 2y^2 - 0.3 * cos(3π*x) - 0.4 * cos(4π*y) + 0.7
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Bohachevsky 1 function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn bohachevsky1<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Bohachevsky 1");
+    let bowl = &x * &x + scaled(&(&y * &y), 2.0);
+    let ripples =
+        scaled(&af::cos(&scaled(&x, 3.0 * PI)), 0.3) + scaled(&af::cos(&scaled(&y, 4.0 * PI)), 0.4);
+    plus(&(bowl - ripples), 0.7)
+}
+
+/// "Flat" variant of the Bohachevsky 1 function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn bohachevsky1_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(bohachevsky1, x)
+}
+
+/// The Bohachevsky 2 function is a 2D bowl with a product of cosine ripples. It has a
+/// global minimum of 0 at (0, 0).
+/// Mathematically,
+/// ```other
+/// f(x, y) = x^2 + 2y^2 - 0.3 * cos(3π*x) * cos(4π*y) + 0.3
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Bohachevsky 2 function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn bohachevsky2<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Bohachevsky 2");
+    let bowl = &x * &x + scaled(&(&y * &y), 2.0);
+    let ripples = af::cos(&scaled(&x, 3.0 * PI)) * af::cos(&scaled(&y, 4.0 * PI));
+    plus(&(bowl - scaled(&ripples, 0.3)), 0.3)
+}
+
+/// "Flat" variant of the Bohachevsky 2 function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn bohachevsky2_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(bohachevsky2, x)
+}
+
+/// The Bohachevsky 3 function is a 2D bowl with a diagonal cosine ripple. It has a
+/// global minimum of 0 at (0, 0).
+/// Mathematically,
+/// ```other
+/// f(x, y) = x^2 + 2y^2 - 0.3 * cos(3π*x + 4π*y) + 0.3
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Bohachevsky 3 function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn bohachevsky3<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Bohachevsky 3");
+    let bowl = &x * &x + scaled(&(&y * &y), 2.0);
+    let ripples = af::cos(&(scaled(&x, 3.0 * PI) + scaled(&y, 4.0 * PI)));
+    plus(&(bowl - scaled(&ripples, 0.3)), 0.3)
+}
+
+/// "Flat" variant of the Bohachevsky 3 function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn bohachevsky3_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(bohachevsky3, x)
+}
+
+/// The Cross-in-Tray function is a 2D function whose steep ridges along the axes divide the
+/// plane into four symmetric trays. It has four global minima of about -2.06261, at
+/// (±1.3491, ±1.3491).
+/// Mathematically,
+/// ```other
+/// f(x, y) = -0.0001 * (|sin(x) * sin(y) * exp(|100 - sqrt(x^2 + y^2)/π|)| + 1)^0.1
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Cross-in-Tray function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn cross_in_tray<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Cross-in-Tray");
+    let exponent = af::abs(&plus(
+        &scaled(&af::sqrt(&(&x * &x + &y * &y)), -1.0 / PI),
+        100.0,
+    ));
+    // The exponential overflows f32 near the origin, so the power is taken in log space:
+    // ln(|s| * e^a + 1) = softplus(ln|s| + a), which is 0 where the sines vanish.
+    let log = af::log(&af::abs(&(af::sin(&x) * af::sin(&y)))) + exponent;
+    let softplus = af::maxof(&log, &T::fromf64(0.0), false) + af::log1p(&af::exp(&-af::abs(&log)));
+    scaled(&af::exp(&scaled(&softplus, 0.1)), -0.0001)
+}
+
+/// "Flat" variant of the Cross-in-Tray function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn cross_in_tray_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(cross_in_tray, x)
+}
+
+/// The Holder Table function is a 2D function with many local minima, usually evaluated on
+/// [-10, 10]². It has four global minima of about -19.2085, at (±8.05502, ±9.66459).
+/// Mathematically,
+/// ```other
+/// f(x, y) = -|sin(x) * cos(y) * exp(|1 - sqrt(x^2 + y^2)/π|)|
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Holder Table function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn holder_table<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Holder Table");
+    let exponent = af::abs(&plus(
+        &scaled(&af::sqrt(&(&x * &x + &y * &y)), -1.0 / PI),
+        1.0,
+    ));
+    -af::abs(&(af::sin(&x) * af::cos(&y) * af::exp(&exponent)))
+}
+
+/// "Flat" variant of the Holder Table function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn holder_table_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(holder_table, x)
+}
+
+/// The Goldstein-Price function is a 2D polynomial with several local minima, usually
+/// evaluated on [-2, 2]². It has a global minimum of 3 at (0, -1).
+/// Mathematically,
+/// ```other
+/// f(x, y) = (1 + (x + y + 1)^2 * (19 - 14x + 3x^2 - 14y + 6xy + 3y^2))
+///         * (30 + (2x - 3y)^2 * (18 - 32x + 12x^2 + 48y - 36xy + 27y^2))
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Goldstein-Price function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn goldstein_price<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Goldstein-Price");
+    let (x2, y2, xy) = (&x * &x, &y * &y, &x * &y);
+    let a = plus(&(&x + &y), 1.0);
+    let b = scaled(&x, 2.0) - scaled(&y, 3.0);
+    let p = scaled(&x, -14.0) + scaled(&x2, 3.0) - scaled(&y, 14.0)
+        + scaled(&xy, 6.0)
+        + scaled(&y2, 3.0);
+    let q = scaled(&x, -32.0) + scaled(&x2, 12.0) + scaled(&y, 48.0) - scaled(&xy, 36.0)
+        + scaled(&y2, 27.0);
+    let first = plus(&(&a * &a * plus(&p, 19.0)), 1.0);
+    let second = plus(&(&b * &b * plus(&q, 18.0)), 30.0);
+    first * second
+}
+
+/// "Flat" variant of the Goldstein-Price function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn goldstein_price_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(goldstein_price, x)
+}
+
+/// The Six-Hump Camel (Camel Back) function is a 2D polynomial with six local minima, usually
+/// evaluated on [-3, 3] × [-2, 2]. Two of them are global, of about -1.0316, at
+/// (0.0898, -0.7126) and (-0.0898, 0.7126).
+/// Mathematically,
+/// ```other
+/// f(x, y) = (4 - 2.1x^2 + x^4/3) * x^2 + xy + (-4 + 4y^2) * y^2
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array containing the Six-Hump Camel function value of every point.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn six_hump_camel<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Six-Hump Camel");
+    let (x2, y2) = (&x * &x, &y * &y);
+    let quartic = plus(&(scaled(&x2, -2.1) + scaled(&(&x2 * &x2), 1.0 / 3.0)), 4.0);
+    quartic * &x2 + x * y + plus(&scaled(&y2, 4.0), -4.0) * y2
+}
+
+/// "Flat" variant of the Six-Hump Camel function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` does not have exactly 2 elements.
+pub fn six_hump_camel_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(six_hump_camel, x)
+}
+
+/// Gradient of the Six-Hump Camel function.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x = 8x - 8.4x^3 + 2x^5 + y
+/// ∂f/∂y = x - 8y + 16y^3
+/// ```
+///
+/// # Parameters
+/// - x: Input array of 2D points to evaluate, one per column.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is not 2.
+pub fn six_hump_camel_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let (x, y) = planar(x, "Six-Hump Camel");
+    let (x2, y2) = (&x * &x, &y * &y);
+    let quartic = plus(&(scaled(&x2, -8.4) + scaled(&(&x2 * &x2), 2.0)), 8.0);
+    let dx = &x * quartic + &y;
+    let dy = x + &y * plus(&scaled(&y2, 16.0), -8.0);
+    af::join(0, &dx, &dy)
+}
+
+/// Schwefel's problem 1.2, also known as the rotated hyper-ellipsoid or double sum, is a
+/// convex quadratic in which every variable is coupled to all the preceding ones. It has a
+/// global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = sum_{i=1..n}((sum_{j=1..i}(x_j))^2)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the function value applied along the first dimension.
+///
+pub fn schwefel_1_2<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let partial = af::accum(x, 0);
+    af::sum(&(&partial * &partial), 0)
+}
+
+/// "Flat" variant of Schwefel's problem 1.2 that returns a single value after flattening the input.
+pub fn schwefel_1_2_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(schwefel_1_2, x)
+}
+
+/// Gradient of Schwefel's problem 1.2: every variable appears in all the partial sums from its
+/// own onward.
+/// Mathematically,
+/// ```other
+/// ∂f/∂x_i = 2 * sum_{k=i..n}(sum_{j=1..k}(x_j))
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array of the same shape as `x`, holding the gradient of every point in its column.
+///
+pub fn schwefel_1_2_grad<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let partial = af::accum(x, 0);
+    let suffix = af::flip(&af::accum(&af::flip(&partial, 0), 0), 0);
+    scaled(&suffix, 2.0)
+}
+
+/// Schwefel's problem 2.21 is the largest absolute coordinate, a convex but non-smooth function
+/// whose value only depends on one variable at a time. It has a global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = max(|x_i|)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the function value applied along the first dimension.
+///
+pub fn schwefel_2_21<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    af::max(&af::abs(x), 0)
+}
+
+/// "Flat" variant of Schwefel's problem 2.21 that returns a single value after flattening the input.
+pub fn schwefel_2_21_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(schwefel_2_21, x)
+}
+
+/// Schwefel's problem 2.22 adds the sum and the product of the absolute coordinates. It has a
+/// global minimum of 0 at x = 0.
+/// Mathematically,
+/// ```other
+/// f(x) = sum(|x_i|) + prod(|x_i|)
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the function value applied along the first dimension.
+///
+pub fn schwefel_2_22<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let abs = af::abs(x);
+    af::sum(&abs, 0) + af::product(&abs, 0)
+}
+
+/// "Flat" variant of Schwefel's problem 2.22 that returns a single value after flattening the input.
+pub fn schwefel_2_22_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(schwefel_2_22, x)
+}
+
+/// Shift and rotate the domain of the test function `f`, as in the CEC benchmark suites:
+/// the result evaluates `f(rotation * (x - shift))`. Moving the optimum away from the origin
+/// and the axes keeps operators biased toward either from looking better than they are.
+///
+/// A minimum of `f` at `x*` moves to `shift + rotationᵀ * x*`; for the many functions with their
+/// minimum at the origin, that is `shift` itself. Any orthogonal matrix will do as `rotation`,
+/// e.g. the identity moved by a few calls of [`crate::lsops::random_rotation`].
+///
+/// # Parameters
+/// - f: Test function to transform.
+/// - shift: Column of n values, where n is the dimension of the points.
+/// - rotation: Orthogonal n × n matrix.
+///
+/// # Returns
+/// - Function evaluating `f` on the transformed points, one per column.
+///
+/// # Panics
+///
+/// Panics if `shift` is not a column, or if `rotation` is not a square matrix of matching size.
+/// The returned function panics if the points do not have the dimension of `shift`.
+pub fn transformed<T, F>(
+    f: F,
+    shift: &af::Array<T>,
+    rotation: &af::Array<T>,
+) -> impl Fn(&af::Array<T>) -> af::Array<T>
+where
+    T: Real,
+    F: Fn(&af::Array<T>) -> af::Array<T>,
+{
+    let n = shift.dims()[0];
+    assert!(
+        shift.dims() == af::dim4!(n),
+        "shift must be a column of one value per dimension"
+    );
+    assert!(
+        rotation.dims() == af::dim4!(n, n),
+        "rotation must be an n × n matrix for a shift of n values"
+    );
+    let (shift, rotation) = (shift.clone(), rotation.clone());
+    move |x| {
+        assert!(
+            x.dims()[0] == n,
+            "points must have the dimension of the shift"
+        );
+        let centred = af::sub(x, &shift, true);
+        f(&af::matmul(
+            &rotation,
+            &centred,
+            af::MatProp::NONE,
+            af::MatProp::NONE,
+        ))
+    }
+}
+
+/// Kind of noise added by [`noisy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Noise {
+    /// Additive Gaussian noise of standard deviation `sigma`: `f(x) + sigma * N(0, 1)`.
+    Gaussian,
+    /// Multiplicative uniform noise of relative half-width `sigma`:
+    /// `f(x) * (1 + sigma * U(-1, 1))`.
+    MultiplicativeUniform,
+}
+
+/// Add random noise to every value of the test function `f`, on the device, to benchmark the
+/// annealers on stochastic objectives (see [`crate::parsa::Options::resampling`]) while the
+/// noiseless `f` remains available as ground truth. Every call draws fresh noise.
+///
+/// # Parameters
+/// - f: Test function to perturb.
+/// - sigma: Scale of the noise, see [`Noise`].
+/// - kind: Kind of noise.
+///
+/// # Returns
+/// - Function evaluating `f` with noise, on points one per column.
+pub fn noisy<T, F>(f: F, sigma: T, kind: Noise) -> impl Fn(&af::Array<T>) -> af::Array<T>
+where
+    T: Real,
+    F: Fn(&af::Array<T>) -> af::Array<T>,
+{
+    noisy_with(f, sigma, kind, &af::get_default_random_engine())
+}
+
+/// Like [`noisy`], but draws the noise from `engine`, so that noisy runs can be reproduced.
+pub fn noisy_with<T, F>(
+    f: F,
+    sigma: T,
+    kind: Noise,
+    engine: &af::RandomEngine,
+) -> impl Fn(&af::Array<T>) -> af::Array<T>
+where
+    T: Real,
+    F: Fn(&af::Array<T>) -> af::Array<T>,
+{
+    let engine = engine.clone();
+    move |x| {
+        let value = f(x);
+        match kind {
+            Noise::Gaussian => {
+                let noise = af::random_normal::<T>(value.dims(), &engine);
+                value + af::mul(&noise, &sigma, false)
+            }
+            Noise::MultiplicativeUniform => {
+                let noise = plus(
+                    &scaled(&af::random_uniform::<T>(value.dims(), &engine), 2.0),
+                    -1.0,
+                );
+                &value + &value * af::mul(&noise, &sigma, false)
+            }
+        }
+    }
+}
+
+/// Boundary penalty `u(x, a, k, m)` of the generalized penalized functions, summed over every
+/// coordinate: `k * (|x_i| - a)^m` outside [-a, a], and 0 inside.
+fn boundary_penalty<T: Real>(x: &af::Array<T>, a: f64, k: f64, m: f64) -> af::Array<T> {
+    let excess = plus(&af::abs(x), -a);
+    let penalty = scaled(&af::pow(&excess, &T::fromf64(m), false), k);
+    let zero = af::constant(T::fromf64(0.0), x.dims());
+    af::sum(
+        &af::select(&penalty, &af::gt(&excess, &T::fromf64(0.0), false), &zero),
+        0,
+    )
+}
+
+/// The first generalized penalized function (P8 in the collection of Yao, Liu and Lin) maps
+/// the variables through `y_i = 1 + (x_i + 1)/4`, couples neighbours through squared sines, and
+/// penalizes leaving [-10, 10]ⁿ. It has a global minimum of 0 at x = (-1, ..., -1).
+/// Mathematically,
+/// ```other
+/// f(x) = π/n * (10 * sin²(π*y_1) + sum_{i<n}((y_i - 1)^2 * (1 + 10 * sin²(π*y_{i+1}))) + (y_n - 1)^2)
+///      + sum(u(x_i, 10, 100, 4))
+/// ```
+/// where u(x, a, k, m) is k(|x| - a)^m for |x| > a and 0 otherwise.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the penalized function value applied along the first dimension.
+///
+pub fn penalized1<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    let y = plus(&scaled(x, 0.25), 1.25);
+    let square = |a: &af::Array<T>| a * a;
+
+    // Row i of the shifted array holds y_{i+1}; the wrapped-around last row is left out.
+    let next = af::shift(&y, &[-1, 0, 0, 0]);
+    let ripple = square(&af::sin(&scaled(&next, PI)));
+    let terms = square(&plus(&y, -1.0)) * plus(&scaled(&ripple, 10.0), 1.0);
+    let coupling = af::sum(&terms, 0) - af::row(&terms, n as i64 - 1);
+    let first = scaled(&square(&af::sin(&scaled(&af::row(&y, 0), PI))), 10.0);
+    let last = square(&plus(&af::row(&y, n as i64 - 1), -1.0));
+
+    scaled(&(first + coupling + last), PI / n as f64) + boundary_penalty(x, 10.0, 100.0, 4.0)
+}
+
+/// "Flat" variant of the first generalized penalized function that returns a single value after flattening the input.
+pub fn penalized1_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(penalized1, x)
+}
+
+/// The second generalized penalized function (P16 in the collection of Yao, Liu and Lin)
+/// couples neighbours through squared sines and penalizes leaving [-5, 5]ⁿ. It has a global
+/// minimum of 0 at x = (1, ..., 1).
+/// Mathematically,
+/// ```other
+/// f(x) = 0.1 * (sin²(3π*x_1) + sum_{i<n}((x_i - 1)^2 * (1 + sin²(3π*x_{i+1}))) + (x_n - 1)^2 * (1 + sin²(2π*x_n)))
+///      + sum(u(x_i, 5, 100, 4))
+/// ```
+/// where u(x, a, k, m) is k(|x| - a)^m for |x| > a and 0 otherwise.
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the penalized function value applied along the first dimension.
+///
+pub fn penalized2<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0] as i64;
+    let square = |a: &af::Array<T>| a * a;
+
+    let next = af::shift(x, &[-1, 0, 0, 0]);
+    let ripple = square(&af::sin(&scaled(&next, 3.0 * PI)));
+    let terms = square(&plus(x, -1.0)) * plus(&ripple, 1.0);
+    let coupling = af::sum(&terms, 0) - af::row(&terms, n - 1);
+    let first = square(&af::sin(&scaled(&af::row(x, 0), 3.0 * PI)));
+    let x_n = af::row(x, n - 1);
+    let tail = plus(&square(&af::sin(&scaled(&x_n, 2.0 * PI))), 1.0);
+    let last = square(&plus(&x_n, -1.0)) * tail;
+
+    scaled(&(first + coupling + last), 0.1) + boundary_penalty(x, 5.0, 100.0, 4.0)
+}
+
+/// "Flat" variant of the second generalized penalized function that returns a single value after flattening the input.
+pub fn penalized2_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(penalized2, x)
+}
+
+/// The Rana function is a highly rugged, non-separable function of consecutive pairs of
+/// coordinates, usually evaluated on [-512, 512]ⁿ. Its best known minimum in two dimensions is
+/// about -511.7329, at (-488.63258, 512), near the boundary of that domain.
+/// Mathematically,
+/// ```other
+/// f(x) = sum_{i=1..n-1}(x_i * cos(sqrt(|x_{i+1} + x_i + 1|)) * sin(sqrt(|x_{i+1} - x_i + 1|))
+///        + (1 + x_{i+1}) * cos(sqrt(|x_{i+1} - x_i + 1|)) * sin(sqrt(|x_{i+1} + x_i + 1|)))
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Rana function value applied along the first dimension.
+///
+/// # Panics
+///
+/// Panics if the first dimension of `x` is less than 2.
+pub fn rana<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let n = x.dims()[0];
+    assert!(n >= 2, "the Rana function needs at least two dimensions");
+    // Row i of the shifted array holds x_{i+1}; the wrapped-around last row is left out.
+    let next = af::shift(x, &[-1, 0, 0, 0]);
+    let sum_root = af::sqrt(&af::abs(&plus(&(&next + x), 1.0)));
+    let difference_root = af::sqrt(&af::abs(&plus(&(&next - x), 1.0)));
+    let terms = x * af::cos(&sum_root) * af::sin(&difference_root)
+        + plus(&next, 1.0) * af::cos(&difference_root) * af::sin(&sum_root);
+    af::sum(&terms, 0) - af::row(&terms, n as i64 - 1)
+}
+
+/// "Flat" variant of the Rana function that returns a single value after flattening the input.
+///
+/// # Panics
+///
+/// Panics if `x` has less than 2 elements.
+pub fn rana_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(rana, x)
+}
+
+/// The Whitley function combines the Rosenbrock and Griewank functions over every ordered pair
+/// of coordinates, giving a rugged, non-separable landscape. It has a global minimum of 0 at
+/// x = (1, ..., 1).
+/// Mathematically,
+/// ```other
+/// f(x) = sum_i(sum_j(y_ij^2 / 4000 - cos(y_ij) + 1)), with y_ij = 100 * (x_i^2 - x_j)^2 + (1 - x_j)^2
+/// ```
+///
+/// # Parameters
+/// - x: Input array of values to evaluate. The first dimension specifies the number of `x_i` for `f(x)`.
+///
+/// # Returns
+/// - Array containing the Whitley function value applied along the first dimension.
+///
+pub fn whitley<T: Real>(x: &af::Array<T>) -> af::Array<T> {
+    let dims = x.dims();
+    let n = dims[0];
+    let points = dims.elements() / n;
+    // Every point becomes an n × n slice of pairs, x_i along the rows and x_j along the columns.
+    let xi = af::moddims(x, af::dim4!(n, 1, points));
+    let xj = af::moddims(x, af::dim4!(1, n, points));
+    let rosenbrock = af::sub(&(&xi * &xi), &xj, true);
+    let gap = plus(&xj, -1.0);
+    let y = af::add(
+        &scaled(&(&rosenbrock * &rosenbrock), 100.0),
+        &(&gap * &gap),
+        true,
+    );
+    let terms = plus(&(scaled(&(&y * &y), 1.0 / 4000.0) - af::cos(&y)), 1.0);
+    let value = af::sum(&af::sum(&terms, 1), 0);
+    af::moddims(&value, af::dim4!(1, dims[1], dims[2], dims[3]))
+}
+
+/// "Flat" variant of the Whitley function that returns a single value after flattening the input.
+pub fn whitley_flat<T: Real>(x: &af::Array<T>) -> T {
+    evaluate_flat(whitley, x)
+}
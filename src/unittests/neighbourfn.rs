@@ -1,5 +1,17 @@
 use super::*;
-use crate::lsops::random_perturbation;
+use crate::lsops::{
+    adjacent_swap, bounded, cooled_radius, cyclic_shift, differential_mutation, pack_step_sizes,
+    random_adjacent_swap, random_bit_flip, random_block_move, random_cyclic_shift,
+    random_group_exchange, random_k_opt, random_lattice_step, random_multi_bit_flip,
+    random_perturbation, random_perturbation_scaled, random_reassignment, random_rotation,
+    random_self_adaptive_perturbation, random_sphere_perturbation, random_subset_perturbation,
+    random_subset_perturbation_scaled, random_swap, random_three_opt,
+    random_two_scale_perturbation, random_uniform_perturbation, random_uniform_perturbation_scaled,
+    unpack_step_sizes, without_step_sizes, AdaptiveMixture, AdjacentSwap, Boundary, Bounds,
+    Context, Coordinates, CorrelatedPerturbation, CyclicShift, GrayCode, GroupExchange,
+    LocalSearchOp, Mixture, MultiBitFlip, SpherePerturbation,
+};
+use tinyrand::{Seeded, StdRand};
 
 #[test]
 fn test_random_perturbation_dims() {
@@ -31,3 +43,567 @@ fn test_random_perturbation_different() {
     perturbed2.host(&mut result2);
     assert_ne!(result1, result2);
 }
+
+#[test]
+fn test_random_bit_flip_flips_one_bit_per_column() {
+    let x = af::constant(0u32, af::Dim4::new(&[8, 4, 1, 1]));
+    let flipped = random_bit_flip(&x);
+    let mut result = vec![0u32; 32];
+    flipped.host(&mut result);
+    assert!(result
+        .chunks(8)
+        .all(|column| column.iter().sum::<u32>() == 1));
+}
+
+#[test]
+fn test_differential_mutation_moves_along_population() {
+    // Three chains on a line: every difference of two distinct chains is a multiple of (1, 1).
+    let x = af::Array::new(
+        &[0.0f32, 0.0, 1.0, 1.0, 3.0, 3.0],
+        af::Dim4::new(&[2, 3, 1, 1]),
+    );
+    let mutated = differential_mutation(&x, 0.5);
+    let mut result = vec![0.0f32; 6];
+    mutated.host(&mut result);
+    assert!(result.chunks(2).all(|column| column[0] == column[1]));
+    assert_ne!(result, vec![0.0, 0.0, 1.0, 1.0, 3.0, 3.0]);
+}
+
+#[test]
+fn test_random_swap_swaps_two_positions_per_column() {
+    let identity: Vec<u32> = (0..6).cycle().take(24).collect();
+    let x = af::Array::new(&identity, af::Dim4::new(&[6, 4, 1, 1]));
+    let swapped = random_swap(&x);
+    let mut result = vec![0u32; 24];
+    swapped.host(&mut result);
+    for column in result.chunks(6) {
+        let moved: Vec<usize> = (0..6).filter(|&i| column[i] != i as u32).collect();
+        assert_eq!(moved.len(), 2);
+        assert_eq!(column[moved[0]], moved[1] as u32);
+        assert_eq!(column[moved[1]], moved[0] as u32);
+    }
+}
+
+#[test]
+fn test_random_k_opt_keeps_permutations() {
+    let identity: Vec<u32> = (0..10).cycle().take(80).collect();
+    let x = af::Array::new(&identity, af::Dim4::new(&[10, 8, 1, 1]));
+    for moved in [
+        random_k_opt(&x, 2),
+        random_three_opt(&x),
+        random_k_opt(&x, 5),
+    ] {
+        let mut result = vec![0u32; 80];
+        moved.host(&mut result);
+        for column in result.chunks(10) {
+            let mut sorted = column.to_vec();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..10).collect::<Vec<u32>>());
+        }
+    }
+}
+
+#[test]
+fn test_random_two_opt_reverses_a_segment() {
+    let identity: Vec<u32> = (0..10).collect();
+    let x = af::Array::new(&identity, af::Dim4::new(&[10, 1, 1, 1]));
+    let mut result = vec![0u32; 10];
+    random_k_opt(&x, 2).host(&mut result);
+    // A segment of a single element reverses onto itself, leaving the tour unchanged.
+    let changed: Vec<usize> = (0..10).filter(|&i| result[i] != i as u32).collect();
+    if let (Some(&begin), Some(&end)) = (changed.first(), changed.last()) {
+        assert!((begin..=end).all(|i| result[i] as usize == begin + end - i));
+    }
+}
+
+#[test]
+fn test_random_adjacent_swap_moves_neighbours() {
+    let identity: Vec<u32> = (0..6).cycle().take(24).collect();
+    let x = af::Array::new(&identity, af::Dim4::new(&[6, 4, 1, 1]));
+    let mut result = vec![0u32; 24];
+    random_adjacent_swap(&x).host(&mut result);
+    for column in result.chunks(6) {
+        let changed: Vec<usize> = (0..6).filter(|&i| column[i] != i as u32).collect();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(changed[0] + 1, changed[1]);
+    }
+}
+
+#[test]
+fn test_adjacent_swap_host() {
+    let mut rng = StdRand::seed(7);
+    let sequence: Vec<u32> = (0..6).collect();
+    for _ in 0..20 {
+        let swapped = adjacent_swap(&sequence, &mut rng);
+        let changed: Vec<usize> = (0..6).filter(|&i| swapped[i] != sequence[i]).collect();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(swapped[changed[0]], sequence[changed[1]]);
+        assert_eq!(changed[0] + 1, changed[1]);
+    }
+    assert_eq!(adjacent_swap(&[1], &mut rng), vec![1]);
+}
+
+#[test]
+fn test_random_multi_bit_flip_radius() {
+    let x = af::constant(0u8, af::dim4!(64, 200));
+    let mut result = vec![0u8; 64 * 200];
+    random_multi_bit_flip(&x, 8.0).host(&mut result);
+    assert!(result.chunks(64).all(|column| column.contains(&1)));
+    let mean = result.iter().map(|&b| f32::from(b)).sum::<f32>() / 200.0;
+    assert_float_eq!(mean, 8.0, 1.0);
+}
+
+#[test]
+fn test_cooled_radius() {
+    assert_float_eq!(cooled_radius(9.0, 100.0, 100.0), 9.0);
+    assert_float_eq!(cooled_radius(9.0, 50.0, 100.0), 5.0);
+    assert_float_eq!(cooled_radius(9.0, 0.0, 100.0), 1.0);
+}
+
+#[test]
+fn test_random_uniform_perturbation_bounded() {
+    let x = af::constant(1.0f32, af::dim4!(4, 50));
+    let mut result = vec![0.0f32; 200];
+    random_uniform_perturbation(&x, 0.5).host(&mut result);
+    assert!(result.iter().all(|&v| (0.5..=1.5).contains(&v)));
+}
+
+#[test]
+fn test_random_uniform_perturbation_scaled_bounds() {
+    let x = af::constant(0.0f32, af::dim4!(2, 50));
+    let scales = af::Array::new(&[0.0f32, 3.0], af::dim4!(2));
+    let mut result = vec![0.0f32; 100];
+    random_uniform_perturbation_scaled(&x, &scales).host(&mut result);
+    for column in result.chunks(2) {
+        assert_float_eq!(column[0], 0.0);
+        assert!(column[1].abs() <= 3.0);
+    }
+}
+
+#[test]
+fn test_random_subset_perturbation_count() {
+    let x = af::constant(0.0f32, af::dim4!(10, 20));
+    let mut result = vec![0.0f32; 200];
+    random_subset_perturbation(&x, 1.0, Coordinates::Count(3)).host(&mut result);
+    for column in result.chunks(10) {
+        assert_eq!(column.iter().filter(|&&v| v != 0.0).count(), 3);
+    }
+}
+
+#[test]
+fn test_random_subset_perturbation_probability() {
+    let x = af::constant(0.0f32, af::dim4!(10, 20));
+    let mut result = vec![1.0f32; 200];
+    random_subset_perturbation(&x, 1.0, Coordinates::Probability(1.0)).host(&mut result);
+    assert!(result.iter().all(|&v| v != 0.0));
+}
+
+#[test]
+fn test_random_perturbation_scaled_per_dimension() {
+    let x = af::constant(0.0f32, af::dim4!(3, 40));
+    let scales = af::Array::new(&[0.0f32, 1.0, 0.0], af::dim4!(3));
+    let mut result = vec![0.0f32; 120];
+    random_perturbation_scaled(&x, &scales).host(&mut result);
+    for column in result.chunks(3) {
+        assert_float_eq!(column[0], 0.0);
+        assert_float_eq!(column[2], 0.0);
+    }
+    let mut subset = vec![0.0f32; 120];
+    random_subset_perturbation_scaled(&x, &scales, Coordinates::Count(2)).host(&mut subset);
+    assert!(subset
+        .chunks(3)
+        .all(|column| column[0] == 0.0 && column[2] == 0.0));
+}
+
+#[test]
+#[should_panic(expected = "expected one scale per dimension")]
+fn test_random_perturbation_scaled_mismatch() {
+    let x = af::constant(0.0f32, af::dim4!(3, 4));
+    let scales = af::constant(1.0f32, af::dim4!(2));
+    let _ = random_perturbation_scaled(&x, &scales);
+}
+
+#[test]
+fn test_correlated_perturbation_follows_covariance() {
+    let covariance = af::Array::new(&[1.0f32, 0.999, 0.999, 1.0], af::dim4!(2, 2));
+    let operator = CorrelatedPerturbation::new(&covariance);
+    let x = af::constant(0.0f32, af::dim4!(2, 100));
+    let mut result = vec![0.0f32; 200];
+    operator.perturb(&x).host(&mut result);
+    assert!(result.chunks(2).all(|c| (c[0] - c[1]).abs() < 0.3));
+}
+
+#[test]
+#[should_panic(expected = "covariance must be positive definite")]
+fn test_correlated_perturbation_rejects_indefinite() {
+    let covariance = af::Array::new(&[1.0f32, 2.0, 2.0, 1.0], af::dim4!(2, 2));
+    let _ = CorrelatedPerturbation::new(&covariance);
+}
+
+#[test]
+fn test_bounds_policies() {
+    let x = af::Array::new(&[-0.5f32, 0.25, 1.5, 2.75], af::dim4!(4));
+    let expected = [
+        (Boundary::Clamp, [0.0f32, 0.25, 1.0, 1.0]),
+        (Boundary::Reflect, [0.5, 0.25, 0.5, 0.75]),
+        (Boundary::Wrap, [0.5, 0.25, 0.5, 0.75]),
+    ];
+    for (boundary, values) in expected {
+        let mut result = vec![0.0f32; 4];
+        Bounds::uniform(0.0, 1.0, 4, boundary)
+            .apply(&x)
+            .host(&mut result);
+        for (r, v) in result.iter().zip(values) {
+            assert_float_eq!(*r, v);
+        }
+    }
+}
+
+#[test]
+fn test_bounded_neighbour_stays_inside() {
+    let bounds = Bounds::uniform(-1.0, 1.0, 3, Boundary::Reflect);
+    let mut neighbour = bounded(|x: &af::Array<f32>| random_perturbation(x, 10.0), bounds);
+    let mut result = vec![0.0f32; 3 * 50];
+    neighbour(&af::constant(0.0f32, af::dim4!(3, 50))).host(&mut result);
+    assert!(result.iter().all(|v| (-1.0..=1.0).contains(v)));
+}
+
+#[test]
+fn test_random_lattice_step_unit() {
+    let x = af::constant(5i32, af::dim4!(4, 30));
+    let mut result = vec![0i32; 120];
+    random_lattice_step(&x, 1.0, None).host(&mut result);
+    for column in result.chunks(4) {
+        let moved: Vec<i32> = column.iter().map(|v| v - 5).filter(|&d| d != 0).collect();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].abs(), 1);
+    }
+}
+
+#[test]
+fn test_random_lattice_step_bounds() {
+    let x = af::constant(0i32, af::dim4!(3, 30));
+    let mut result = vec![0i32; 90];
+    random_lattice_step(&x, 4.0, Some((0, 2))).host(&mut result);
+    assert!(result.iter().all(|v| (0..=2).contains(v)));
+}
+
+#[test]
+fn test_random_reassignment_changes_one_category() {
+    let assignment: Vec<u8> = (0..3).cycle().take(60).collect();
+    let x = af::Array::new(&assignment, af::dim4!(6, 10));
+    let mut result = vec![0u8; 60];
+    random_reassignment(&x, 3).host(&mut result);
+    for (column, original) in result.chunks(6).zip(assignment.chunks(6)) {
+        let changed = column.iter().zip(original).filter(|(a, b)| a != b).count();
+        assert_eq!(changed, 1);
+        assert!(column.iter().all(|&c| c < 3));
+    }
+}
+
+#[test]
+fn test_random_sphere_perturbation_keeps_norm() {
+    let x = af::Array::new(&[0.6f32, 0.8, 0.0, 3.0, 0.0, 4.0], af::dim4!(3, 2));
+    let mut result = vec![0.0f32; 6];
+    random_sphere_perturbation(&x, 0.3).host(&mut result);
+    let norms: Vec<f32> = result
+        .chunks(3)
+        .map(|c| c.iter().map(|v| v * v).sum::<f32>().sqrt())
+        .collect();
+    assert_float_eq!(norms[0], 1.0, 1e-4);
+    assert_float_eq!(norms[1], 5.0, 1e-4);
+}
+
+#[test]
+fn test_sphere_perturbation_op_scaled_keeps_norm() {
+    let x = af::Array::new(&[0.6f32, 0.8, 0.0, 3.0, 0.0, 4.0], af::dim4!(3, 2));
+    let scale = af::Array::new(&[0.5f32, 2.0], af::dim4!(1, 2));
+    let mut rng = StdRand::seed(3);
+    let mut context = Context {
+        scale: Some(&scale),
+        ..Context::new(&mut rng)
+    };
+    let mut result = vec![0.0f32; 6];
+    SpherePerturbation { scale: 0.3 }
+        .propose(&x, &mut context)
+        .host(&mut result);
+    let norms: Vec<f32> = result
+        .chunks(3)
+        .map(|c| c.iter().map(|v| v * v).sum::<f32>().sqrt())
+        .collect();
+    assert_float_eq!(norms[0], 1.0, 1e-4);
+    assert_float_eq!(norms[1], 5.0, 1e-4);
+}
+
+#[test]
+fn test_random_rotation_stays_orthogonal() {
+    let identity = af::identity::<f32>(af::dim4!(3, 3, 4));
+    let rotated = random_rotation(&identity, 0.5);
+    let mut result = vec![0.0f32; 36];
+    rotated.host(&mut result);
+    for matrix in result.chunks(9) {
+        for (i, a) in matrix.chunks(3).enumerate() {
+            for (j, b) in matrix.chunks(3).enumerate() {
+                let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+                assert_float_eq!(dot, if i == j { 1.0 } else { 0.0 }, 1e-4);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_mixture_follows_weights() {
+    let mut rng = StdRand::seed(11);
+    let mut mixture = Mixture::new().with(1.0, |_: &u32| 1).with(3.0, |_: &u32| 2);
+    let twos = (0..4000)
+        .filter(|_| mixture.propose(&0, &mut rng) == 2)
+        .count();
+    assert!((2800..3200).contains(&twos), "{twos}");
+}
+
+#[test]
+fn test_mixture_batch_picks_per_column() {
+    let mut mixture = Mixture::new()
+        .with(1.0, |x: &af::Array<f32>| x + 1.0f32)
+        .with(1.0, |x: &af::Array<f32>| x - 1.0f32);
+    let x = af::constant(0.0f32, af::dim4!(2, 200));
+    let mut result = vec![0.0f32; 400];
+    mixture.propose_batch(&x).host(&mut result);
+    assert!(result.chunks(2).all(|c| c[0] == c[1] && c[0].abs() == 1.0));
+    let ups = result.chunks(2).filter(|c| c[0] > 0.0).count();
+    assert!((60..140).contains(&ups));
+}
+
+#[test]
+fn test_adaptive_mixture_favours_rewarded_operator() {
+    let mut rng = StdRand::seed(5);
+    let mixture = Mixture::new()
+        .with(1.0, |x: &f32| x + 1.0)
+        .with(1.0, |x: &f32| x - 1.0);
+    let mut adaptive = AdaptiveMixture::new(mixture, 0.2, 0.05);
+    for _ in 0..200 {
+        let x = 0.0;
+        let proposal = adaptive.propose(&x, &mut rng);
+        adaptive.reward(x - proposal);
+    }
+    let probabilities: Vec<f32> = adaptive.probabilities().collect();
+    assert_float_eq!(probabilities.iter().sum::<f32>(), 1.0);
+    assert!(probabilities[1] > 0.9);
+    assert!(probabilities[0] >= 0.05 - 1e-6);
+}
+
+#[test]
+fn test_local_search_op_context() {
+    let mut rng = StdRand::seed(3);
+    let mut context = Context::new(&mut rng);
+    let mut closure = |x: &Vec<u32>| x.iter().map(|v| v + 1).collect::<Vec<u32>>();
+    assert_eq!(closure.propose(&vec![1, 2], &mut context), [2, 3]);
+    let swapped = AdjacentSwap.propose(&vec![1, 2], &mut context);
+    assert_eq!(swapped, [2, 1]);
+}
+
+#[test]
+fn test_multi_bit_flip_op_cools() {
+    let x = af::constant(0u8, af::dim4!(64, 100));
+    let mut operator = MultiBitFlip {
+        radius: 16.0,
+        initial_temperature: Some(10.0),
+    };
+    let mut rng = StdRand::seed(3);
+    let mut context = Context {
+        temperature: Some(0.0),
+        ..Context::new(&mut rng)
+    };
+    let mut result = vec![0u8; 6400];
+    operator.propose(&x, &mut context).host(&mut result);
+    assert!(result
+        .chunks(64)
+        .all(|c| c.iter().filter(|&&b| b == 1).count() == 1));
+}
+
+#[test]
+fn test_gray_code_round_trip() {
+    let code = GrayCode::new(4, 0.0, 15.0);
+    let x = af::Array::new(&[0.0f32, 3.0, 7.0, 8.0, 15.0, 20.0], af::dim4!(2, 3));
+    let mut bits = vec![0u8; 24];
+    code.encode(&x).host(&mut bits);
+    // 3 is 0011 in binary and 0010 in Gray code; 8 is 1000 and 1100.
+    assert_eq!(bits[4..8], [0, 0, 1, 0]);
+    assert_eq!(bits[12..16], [1, 1, 0, 0]);
+    let mut decoded = vec![0.0f32; 6];
+    code.decode(&code.encode(&x)).host(&mut decoded);
+    assert_eq!(decoded, [0.0, 3.0, 7.0, 8.0, 15.0, 15.0]);
+}
+
+#[test]
+fn test_gray_code_flip_changes_one_variable() {
+    let code = GrayCode::new(8, -1.0, 1.0);
+    let x = code.decode(&code.encode(&af::constant(0.3f32, af::dim4!(3, 20))));
+    let mut before = vec![0.0f32; 60];
+    let mut after = vec![0.0f32; 60];
+    x.host(&mut before);
+    code.random_flip(&x).host(&mut after);
+    for (a, b) in after.chunks(3).zip(before.chunks(3)) {
+        assert_eq!(a.iter().zip(b).filter(|(a, b)| a != b).count(), 1);
+    }
+}
+
+fn group_sizes(labels: &[u32], groups: u32) -> Vec<usize> {
+    (0..groups)
+        .map(|g| labels.iter().filter(|&&l| l == g).count())
+        .collect()
+}
+
+#[test]
+fn test_group_exchange_keeps_sizes_without_slack() {
+    let labels: Vec<u32> = (0..12).map(|i| i % 3).collect();
+    let x = af::tile(&af::Array::new(&labels, af::dim4!(12)), af::dim4!(1, 50));
+    let mut moved = vec![0u32; 600];
+    random_group_exchange(&x, 3, 0).host(&mut moved);
+    for column in moved.chunks(12) {
+        assert_eq!(group_sizes(column, 3), [4, 4, 4]);
+        let changed = column.iter().zip(&labels).filter(|(a, b)| a != b).count();
+        assert_eq!(changed, 2);
+    }
+}
+
+#[test]
+fn test_group_exchange_keeps_unbalanced_sizes_without_slack() {
+    // Relocating from the larger group would balance the two, yet still change their sizes.
+    let labels = [0u32, 0, 0, 1];
+    let x = af::tile(&af::Array::new(&labels, af::dim4!(4)), af::dim4!(1, 100));
+    let mut moved = vec![0u32; 400];
+    random_group_exchange(&x, 2, 0).host(&mut moved);
+    for column in moved.chunks(4) {
+        assert_eq!(group_sizes(column, 2), [3, 1]);
+    }
+}
+
+#[test]
+fn test_group_exchange_relocates_within_slack() {
+    let labels = [0u32, 0, 0, 1, 1, 1, 1];
+    let x = af::tile(&af::Array::new(&labels, af::dim4!(7)), af::dim4!(1, 200));
+    let mut op = GroupExchange {
+        groups: 2,
+        slack: 1,
+    };
+    let mut rng = StdRand::seed(5);
+    let mut moved = vec![0u32; 1400];
+    op.propose(&x, &mut Context::new(&mut rng)).host(&mut moved);
+    let mut relocations = 0;
+    for column in moved.chunks(7) {
+        let sizes = group_sizes(column, 2);
+        assert!(sizes[0].abs_diff(sizes[1]) <= 1);
+        let changed = column.iter().zip(&labels).filter(|(a, b)| a != b).count();
+        match changed {
+            1 => relocations += 1,
+            2 => {}
+            _ => panic!("expected a relocation or a swap, changed {changed} elements"),
+        }
+    }
+    // Only moving an element out of the larger group stays within the slack.
+    assert!(relocations > 0);
+}
+
+/// Whether `shifted` is `original` with one window rotated by one position.
+fn is_cyclic_shift(original: &[u32], shifted: &[u32]) -> bool {
+    let Some(start) = original.iter().zip(shifted).position(|(a, b)| a != b) else {
+        return false;
+    };
+    let end = original.len()
+        - original
+            .iter()
+            .zip(shifted)
+            .rev()
+            .position(|(a, b)| a != b)
+            .unwrap();
+    let mut left = original[start..end].to_vec();
+    let mut right = left.clone();
+    left.rotate_left(1);
+    right.rotate_right(1);
+    shifted[start..end] == left[..] || shifted[start..end] == right[..]
+}
+
+#[test]
+fn test_cyclic_shift_rotates_a_window() {
+    let sequence: Vec<u32> = (0..8).collect();
+    let x = af::tile(&af::Array::new(&sequence, af::dim4!(8)), af::dim4!(1, 100));
+    let mut shifted = vec![0u32; 800];
+    random_cyclic_shift(&x).host(&mut shifted);
+    assert!(shifted.chunks(8).all(|c| is_cyclic_shift(&sequence, c)));
+
+    let mut rng = StdRand::seed(3);
+    for _ in 0..100 {
+        assert!(is_cyclic_shift(
+            &sequence,
+            &cyclic_shift(&sequence, &mut rng)
+        ));
+    }
+    let mut context = Context::new(&mut rng);
+    let shifted = CyclicShift.propose(&sequence, &mut context);
+    assert!(is_cyclic_shift(&sequence, &shifted));
+}
+
+#[test]
+fn test_block_move_exchanges_adjacent_blocks() {
+    let sequence: Vec<u32> = (0..9).collect();
+    let x = af::tile(&af::Array::new(&sequence, af::dim4!(9)), af::dim4!(1, 100));
+    let mut moved = vec![0u32; 900];
+    random_block_move(&x).host(&mut moved);
+    for column in moved.chunks(9) {
+        let found = (0..9).any(|start| {
+            (start + 1..9).any(|middle| {
+                (middle + 1..=9).any(|end| {
+                    let mut expected = sequence.clone();
+                    expected[start..end].rotate_left(middle - start);
+                    expected == column
+                })
+            })
+        });
+        assert!(found, "{column:?} is not a block move");
+    }
+}
+
+#[test]
+fn test_two_scale_perturbation_mixes_step_sizes() {
+    let x = af::constant(0.0f32, af::dim4!(4, 2000));
+    let steps = random_two_scale_perturbation(&x, 0.01, 10.0, 0.8);
+    let mut columns = vec![0.0f32; 2000];
+    af::max(&af::abs(&steps), 0).host(&mut columns);
+    // Four draws of a 0.01 Gaussian stay far below 1, those of a 10 Gaussian rarely do.
+    let large = columns.iter().filter(|&&m| m > 1.0).count();
+    assert!((300..500).contains(&large), "{large} large steps");
+
+    let mut all_small = vec![0.0f32; 2000];
+    af::max(
+        &af::abs(&random_two_scale_perturbation(&x, 0.01, 10.0, 1.0)),
+        0,
+    )
+    .host(&mut all_small);
+    assert!(all_small.iter().all(|&m| m < 1.0));
+}
+
+#[test]
+fn test_self_adaptive_step_sizes() {
+    let x = af::constant(1.0f32, af::dim4!(3, 500));
+    let packed = pack_step_sizes(&x, 0.5);
+    assert_eq!(packed.dims(), af::dim4!(4, 500));
+    let (solutions, scales) = unpack_step_sizes(&packed);
+    assert_float_eq!(af::sum_all(&solutions).0, 1500.0, 1e-3);
+    assert_float_eq!(af::min_all(&scales).0, 0.5, 1e-6);
+    assert_float_eq!(af::max_all(&scales).0, 0.5, 1e-6);
+
+    let mut energy = without_step_sizes(|x: &af::Array<f32>| af::sum(x, 0));
+    assert_eq!(energy(&packed).dims(), af::dim4!(1, 500));
+    assert_float_eq!(af::max_all(&energy(&packed)).0, 3.0, 1e-5);
+
+    // Without a learning rate the step sizes stay put and the steps follow them.
+    let proposal = random_self_adaptive_perturbation(&packed, 0.0);
+    let (moved, scales) = unpack_step_sizes(&proposal);
+    assert_float_eq!(af::max_all(&scales).0, 0.5, 1e-6);
+    let deviation = (af::sum_all(&((&moved - 1.0f32) * (&moved - 1.0f32))).0 / 1500.0).sqrt();
+    assert!((deviation - 0.5).abs() < 0.05, "deviation {deviation}");
+
+    let (_, scales) = unpack_step_sizes(&random_self_adaptive_perturbation(&packed, 0.3));
+    assert!(af::max_all(&scales).0 > 0.5 && af::min_all(&scales).0 < 0.5);
+}
@@ -1,4 +1,5 @@
 use super::*;
+use std::f32::consts::PI;
 
 #[test]
 fn test_rastrigin_global_minimum() {
@@ -145,6 +146,22 @@ fn test_schwefel_origin() {
     assert_float_eq!(host_result, 418.9829);
 }
 
+#[test]
+fn test_schwefel_negative_coordinates() {
+    // Test that the whole domain [-500, 500] is usable: sqrt takes |x_i|
+    let input = vec![-420.9687f32, 0.0, -302.5232, 420.9687];
+    let x = af::Array::new(&input, af::dim4!(2, 2));
+    let result = testfunctions::schwefel(&x);
+
+    let mut host_result = [0.0f32, 0.0];
+    result.host(&mut host_result);
+
+    // Pre-calculated values; the second point is the best one with a negative coordinate
+    assert!(host_result.iter().all(|v| v.is_finite()));
+    assert_float_eq!(host_result[0], 1256.9487f32, 1e-2);
+    assert_float_eq!(host_result[1], 118.43836f32, 1e-2);
+}
+
 #[test]
 fn test_schwefel_dimensionality() {
     // Test that the function scales correctly with dimensionality
@@ -154,3 +171,741 @@ fn test_schwefel_dimensionality() {
     // At origin, value should be A*n where A=418.9829 and n=3
     assert_float_eq!(host_result, 3.0 * 418.9829);
 }
+
+#[test]
+fn test_levy_global_minimum() {
+    // Test global minimum at x = (1, ..., 1)
+    let x = af::constant(1.0f32, af::dim4!(4, 1));
+    let result = to_scalar(testfunctions::levy(&x));
+    assert_float_eq!(result, 0.0);
+}
+
+#[test]
+fn test_levy_multiple_points() {
+    // Test with a 2D point at the origin and another one at the minimum
+    let input = vec![0.0f32, 0.0f32, 1.0f32, 1.0f32];
+    let x = af::Array::new(&input, af::dim4!(2, 2));
+    let result = testfunctions::levy(&x);
+
+    let mut host_result = [0.0, 0.0];
+    result.host(&mut host_result);
+
+    // Pre-calculated value
+    assert_float_eq!(host_result[0], 0.715845f32);
+    assert_float_eq!(host_result[1], 0.0f32);
+}
+
+#[test]
+fn test_levy_single_dimension() {
+    // With one variable there are no coupling terms: f(5) = sin²(2π) + 1 * (1 + sin²(4π)) = 1
+    let x = af::constant(5.0f32, af::dim4!(1, 1));
+    let result = to_scalar(testfunctions::levy(&x));
+    assert_float_eq!(result, 1.0);
+    assert_float_eq!(
+        testfunctions::levy_flat(&af::Array::new(&[2.0f32, -1.0, 3.0], af::dim4!(3))),
+        2.388972
+    );
+}
+
+#[test]
+fn test_sphere_multiple_points() {
+    // Test with the origin and the point [1, 2, -2]
+    let input = vec![0.0f32, 0.0, 0.0, 1.0, 2.0, -2.0];
+    let x = af::Array::new(&input, af::dim4!(3, 2));
+    let result = testfunctions::sphere(&x);
+
+    let mut host_result = [0.0, 0.0];
+    result.host(&mut host_result);
+
+    assert_float_eq!(host_result[0], 0.0f32);
+    assert_float_eq!(host_result[1], 9.0f32);
+}
+
+#[test]
+fn test_sum_of_different_powers() {
+    // Test global minimum at x = 0
+    let x = af::constant(0.0f32, af::dim4!(3, 1));
+    assert_float_eq!(to_scalar(testfunctions::sum_of_different_powers(&x)), 0.0);
+
+    // f([-2, 2, 0.5]) = 2² + 2³ + 0.5⁴
+    let x = af::Array::new(&[-2.0f32, 2.0, 0.5], af::dim4!(3));
+    assert_float_eq!(testfunctions::sum_of_different_powers_flat(&x), 12.0625);
+}
+
+#[test]
+fn test_himmelblau_minima() {
+    // Test all four global minima in one batch
+    let input = vec![
+        3.0f32, 2.0, -2.805118, 3.131312, -3.77931, -3.283186, 3.584428, -1.848126,
+    ];
+    let x = af::Array::new(&input, af::dim4!(2, 4));
+    let mut host_result = [0.0f32; 4];
+    testfunctions::himmelblau(&x).host(&mut host_result);
+    for value in host_result {
+        assert_float_eq!(value, 0.0, 1e-3);
+    }
+    // f(0, 0) = 121 + 49
+    assert_float_eq!(
+        testfunctions::himmelblau_flat(&af::constant(0.0f32, af::dim4!(2))),
+        170.0
+    );
+}
+
+#[test]
+fn test_booth_and_beale_minima() {
+    let x = af::Array::new(&[1.0f32, 3.0], af::dim4!(2));
+    assert_float_eq!(testfunctions::booth_flat(&x), 0.0);
+    let x = af::Array::new(&[3.0f32, 0.5], af::dim4!(2));
+    assert_float_eq!(testfunctions::beale_flat(&x), 0.0);
+
+    // f(0, 0) = 49 + 25 and 1.5² + 2.25² + 2.625²
+    let x = af::constant(0.0f32, af::dim4!(2, 1));
+    assert_float_eq!(to_scalar(testfunctions::booth(&x)), 74.0);
+    assert_float_eq!(to_scalar(testfunctions::beale(&x)), 14.203125);
+}
+
+#[test]
+#[should_panic(expected = "only defined in two dimensions")]
+fn test_planar_function_rejects_other_dimensions() {
+    let _ = testfunctions::booth(&af::constant(0.0f32, af::dim4!(3, 1)));
+}
+
+#[test]
+fn test_eggholder_global_minimum() {
+    // Test global minimum at (512, 404.2319)
+    let x = af::Array::new(&[512.0f32, 404.2319], af::dim4!(2));
+    assert_float_eq!(testfunctions::eggholder_flat(&x), -959.6407, 1e-2);
+}
+
+#[test]
+fn test_eggholder_negative_coordinates() {
+    // Test points whose square root arguments are negative before taking absolute values
+    let input = vec![0.0f32, 0.0, -100.0, 50.0];
+    let x = af::Array::new(&input, af::dim4!(2, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::eggholder(&x).host(&mut host_result);
+
+    // Pre-calculated values
+    assert_float_eq!(host_result[0], -25.460337f32, 1e-3);
+    assert_float_eq!(host_result[1], 46.93955f32, 1e-3);
+}
+
+#[test]
+fn test_drop_wave() {
+    // Test global minimum at the origin, and a point on a ripple
+    let input = vec![0.0f32, 0.0, 1.0, 1.0];
+    let x = af::Array::new(&input, af::dim4!(2, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::drop_wave(&x).host(&mut host_result);
+
+    assert_float_eq!(host_result[0], -1.0f32);
+    // Pre-calculated value
+    assert_float_eq!(host_result[1], -0.23222f32, 1e-4);
+}
+
+#[test]
+fn test_shubert() {
+    // Test two of the global minima and the origin
+    let input = vec![-7.0835f32, 4.858, -7.0835, -7.7083, 0.0, 0.0];
+    let x = af::Array::new(&input, af::dim4!(2, 3));
+    let mut host_result = [0.0; 3];
+    testfunctions::shubert(&x).host(&mut host_result);
+
+    assert_float_eq!(host_result[0], -186.7309f32, 1e-2);
+    assert_float_eq!(host_result[1], -186.7309f32, 1e-2);
+    // Pre-calculated value
+    assert_float_eq!(host_result[2], 19.87584f32, 1e-3);
+}
+
+#[test]
+fn test_branin_global_minima() {
+    // Test all three global minima in one batch
+    let input = vec![-PI, 12.275, PI, 2.275, 9.42478, 2.475];
+    let x = af::Array::new(&input, af::dim4!(2, 3));
+    let mut host_result = [0.0f32; 3];
+    testfunctions::branin(&x).host(&mut host_result);
+    for value in host_result {
+        assert_float_eq!(value, 0.397887, 1e-4);
+    }
+    // f(0, 0) = 36 + 10 * (1 - 1/(8π)) + 10
+    assert_float_eq!(
+        testfunctions::branin_flat(&af::constant(0.0f32, af::dim4!(2))),
+        55.60211,
+        1e-3
+    );
+}
+
+#[test]
+fn test_hartmann3() {
+    // Test the global minimum and the centre of the cube in one batch
+    let input = vec![0.114614f32, 0.555649, 0.852547, 0.5, 0.5, 0.5];
+    let x = af::Array::new(&input, af::dim4!(3, 2));
+    let result = testfunctions::hartmann3(&x);
+    assert_eq!(result.dims(), af::dim4!(1, 2));
+
+    let mut host_result = [0.0, 0.0];
+    result.host(&mut host_result);
+    assert_float_eq!(host_result[0], -3.86278f32, 1e-4);
+    // Pre-calculated value
+    assert_float_eq!(host_result[1], -0.628022f32, 1e-4);
+}
+
+#[test]
+fn test_hartmann6() {
+    // Test the global minimum and the centre of the hypercube in one batch
+    let input = vec![
+        0.20169f32, 0.150011, 0.476874, 0.275332, 0.311652, 0.6573, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5,
+    ];
+    let x = af::Array::new(&input, af::dim4!(6, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::hartmann6(&x).host(&mut host_result);
+    assert_float_eq!(host_result[0], -3.32237f32, 1e-4);
+    // Pre-calculated value
+    assert_float_eq!(host_result[1], -0.505315f32, 1e-4);
+    assert_float_eq!(
+        testfunctions::hartmann6_flat(&af::constant(0.5f32, af::dim4!(6))),
+        -0.505315,
+        1e-4
+    );
+}
+
+#[test]
+fn test_dixon_price() {
+    // Test the global minimum x_i = 2^(-(2^i - 2) / 2^i) and the point [1, 2, 3]
+    let minimum: Vec<f32> = (1..=3)
+        .map(|i| 2.0f32.powf(-((1 << i) - 2) as f32 / (1 << i) as f32))
+        .collect();
+    let input = [minimum, vec![1.0, 2.0, 3.0]].concat();
+    let x = af::Array::new(&input, af::dim4!(3, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::dixon_price(&x).host(&mut host_result);
+
+    assert_float_eq!(host_result[0], 0.0f32);
+    // f([1, 2, 3]) = 0 + 2 * (8 - 1)² + 3 * (18 - 2)²
+    assert_float_eq!(host_result[1], 866.0f32);
+}
+
+#[test]
+fn test_trid() {
+    // Test the global minimum x_i = i(n + 1 - i) of value -n(n + 4)(n - 1)/6 for n = 3 and 6
+    let x = af::Array::new(&[3.0f32, 4.0, 3.0], af::dim4!(3));
+    assert_float_eq!(testfunctions::trid_flat(&x), -7.0);
+    let x = af::Array::new(&[6.0f32, 10.0, 12.0, 12.0, 10.0, 6.0], af::dim4!(6, 1));
+    assert_float_eq!(to_scalar(testfunctions::trid(&x)), -50.0);
+
+    // A single variable has no coupling terms
+    let x = af::constant(3.0f32, af::dim4!(1, 1));
+    assert_float_eq!(to_scalar(testfunctions::trid(&x)), 4.0);
+}
+
+#[test]
+fn test_salomon() {
+    // Test the global minimum and a point at distance 3, where the cosine term vanishes
+    let input = vec![0.0f32, 0.0, 0.0, 1.0, 2.0, -2.0];
+    let x = af::Array::new(&input, af::dim4!(3, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::salomon(&x).host(&mut host_result);
+
+    assert_float_eq!(host_result[0], 0.0f32);
+    assert_float_eq!(host_result[1], 0.3f32);
+}
+
+#[test]
+fn test_alpine() {
+    // Test the global minimum
+    let x = af::constant(0.0f32, af::dim4!(4, 1));
+    assert_float_eq!(to_scalar(testfunctions::alpine(&x)), 0.0);
+
+    // Pre-calculated value
+    let x = af::Array::new(&[1.0f32, -2.0, 3.0], af::dim4!(3));
+    assert_float_eq!(testfunctions::alpine_flat(&x), 3.283426);
+}
+
+#[test]
+fn test_weierstrass_global_minimum() {
+    // Test global minimum at x = 0, for several points at once
+    let x = af::constant(0.0f32, af::dim4!(2, 3));
+    let mut host_result = [1.0f32; 3];
+    testfunctions::weierstrass(&x).host(&mut host_result);
+    for value in host_result {
+        assert_float_eq!(value, 0.0, 1e-3);
+    }
+}
+
+#[test]
+fn test_weierstrass_truncated() {
+    // Pre-calculated values of [0.25, -0.1] with 4 and 21 terms
+    let x = af::Array::new(&[0.25f32, -0.1], af::dim4!(2, 1));
+    assert_float_eq!(
+        to_scalar(testfunctions::weierstrass_truncated(&x, 3)),
+        2.931864,
+        1e-4
+    );
+    assert_float_eq!(testfunctions::weierstrass_flat(&x), 3.12732, 1e-3);
+}
+
+#[test]
+fn test_katsuura() {
+    // Test the global minimum and two pre-calculated values
+    let input = vec![0.0f32, 0.0, 0.3, -0.7];
+    let x = af::Array::new(&input, af::dim4!(2, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::katsuura(&x).host(&mut host_result);
+
+    assert_float_eq!(host_result[0], 0.0f32);
+    assert_float_eq!(host_result[1], 78.30165f32, 1e-2);
+    let x = af::Array::new(&[0.1f32, 0.2, 0.3], af::dim4!(3));
+    assert_float_eq!(testfunctions::katsuura_flat(&x), 51.31934, 1e-2);
+}
+
+#[test]
+fn test_happy_cat() {
+    // Test the global minimum at x = -1 and two pre-calculated values
+    let input = vec![-1.0f32, -1.0, 0.0, 0.0];
+    let x = af::Array::new(&input, af::dim4!(2, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::happy_cat(&x).host(&mut host_result);
+
+    assert_float_eq!(host_result[0], 0.0f32);
+    assert_float_eq!(host_result[1], 1.689207f32);
+    let x = af::Array::new(&[1.0f32, 2.0, 3.0], af::dim4!(3));
+    assert_float_eq!(testfunctions::happy_cat_flat(&x), 6.654494);
+}
+
+#[test]
+fn test_expanded_schaffer_f6() {
+    // Test the global minimum and a pre-calculated value
+    let input = vec![0.0f32, 0.0, 0.0, 1.0, 2.0, 3.0];
+    let x = af::Array::new(&input, af::dim4!(3, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::expanded_schaffer_f6(&x).host(&mut host_result);
+
+    assert_float_eq!(host_result[0], 0.0f32);
+    assert_float_eq!(host_result[1], 0.835958f32);
+    // A single coordinate is paired with itself
+    let x = af::constant(1.5f32, af::dim4!(1));
+    assert_float_eq!(testfunctions::expanded_schaffer_f6_flat(&x), 0.724308);
+}
+
+#[test]
+fn test_schaffer_f7() {
+    // Test the global minimum and two pre-calculated values
+    let input = vec![0.0f32, 0.0, 0.0, 1.0, 2.0, 3.0];
+    let x = af::Array::new(&input, af::dim4!(3, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::schaffer_f7(&x).host(&mut host_result);
+
+    assert_float_eq!(host_result[0], 0.0f32);
+    assert_float_eq!(host_result[1], 9.6334f32, 1e-3);
+    let x = af::Array::new(&[0.5f32, -0.5], af::dim4!(2));
+    assert_float_eq!(testfunctions::schaffer_f7_flat(&x), 1.030008, 1e-4);
+}
+
+#[test]
+fn test_bent_cigar_and_discus() {
+    // Test the global minimum and the unit vectors along the first two axes
+    let input = vec![0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+    let x = af::Array::new(&input, af::dim4!(3, 3));
+    let mut host_result = [0.0; 3];
+    testfunctions::bent_cigar(&x).host(&mut host_result);
+    assert_eq!(host_result, [0.0, 1.0, 1e6]);
+    testfunctions::discus(&x).host(&mut host_result);
+    assert_eq!(host_result, [0.0, 1e6, 1.0]);
+}
+
+#[test]
+fn test_ellipsoid_conditioning() {
+    // The weights grow from 1 to 10^6 across the dimensions
+    let input = vec![1.0f32, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0];
+    let x = af::Array::new(&input, af::dim4!(3, 3));
+    let mut host_result = [0.0; 3];
+    testfunctions::ellipsoid(&x).host(&mut host_result);
+    assert_float_eq!(host_result[0], 1.0f32);
+    assert_float_eq!(host_result[1] / 1e6, 1.0f32);
+    assert_float_eq!(host_result[2] / 1e6, 1.001001f32);
+    assert_float_eq!(
+        testfunctions::ellipsoid_flat(&af::constant(2.0f32, af::dim4!(1))),
+        4.0
+    );
+}
+
+#[test]
+fn test_rotated_ellipsoid() {
+    // A quarter turn swaps the axes of a 2D ellipsoid
+    let rotation = af::Array::new(&[0.0f32, 1.0, -1.0, 0.0], af::dim4!(2, 2));
+    let x = af::Array::new(&[1.0f32, 0.0, 0.0, 0.0], af::dim4!(2, 2));
+    let mut host_result = [0.0; 2];
+    testfunctions::rotated_ellipsoid(&x, &rotation).host(&mut host_result);
+    assert_float_eq!(host_result[0] / 1e6, 1.0f32);
+    assert_float_eq!(host_result[1], 0.0f32);
+}
+
+#[test]
+fn test_bohachevsky() {
+    // Test the global minimum at the origin and a pre-calculated value of every variant
+    let input = vec![0.0f32, 0.0, 0.3, 0.2];
+    let x = af::Array::new(&input, af::dim4!(2, 2));
+    let expected = [1.478924f32, 0.239174, 0.293664];
+    let functions = [
+        testfunctions::bohachevsky1,
+        testfunctions::bohachevsky2,
+        testfunctions::bohachevsky3,
+    ];
+    for (f, expected) in functions.into_iter().zip(expected) {
+        let mut host_result = [1.0, 0.0];
+        f(&x).host(&mut host_result);
+        assert_float_eq!(host_result[0], 0.0f32);
+        assert_float_eq!(host_result[1], expected);
+    }
+    assert_float_eq!(
+        testfunctions::bohachevsky2_flat(&af::constant(0.0f32, af::dim4!(2))),
+        0.0
+    );
+}
+
+#[test]
+fn test_cross_in_tray_minima() {
+    // Test the four symmetric global minima and the origin
+    let input = vec![
+        1.3491f32, 1.3491, -1.3491, 1.3491, 1.3491, -1.3491, -1.3491, -1.3491, 0.0, 0.0,
+    ];
+    let x = af::Array::new(&input, af::dim4!(2, 5));
+    let mut host_result = [0.0f32; 5];
+    testfunctions::cross_in_tray(&x).host(&mut host_result);
+    for value in &host_result[..4] {
+        assert_float_eq!(*value, -2.06261, 1e-4);
+    }
+    assert_float_eq!(host_result[4], -0.0001);
+    // Pre-calculated value
+    let x = af::Array::new(&[1.0f32, 2.0], af::dim4!(2));
+    assert_float_eq!(testfunctions::cross_in_tray_flat(&x), -1.997137, 1e-4);
+}
+
+#[test]
+fn test_holder_table_minima() {
+    // Test the four symmetric global minima and the origin
+    let input = vec![
+        8.05502f32, 9.66459, -8.05502, 9.66459, 8.05502, -9.66459, -8.05502, -9.66459, 0.0, 0.0,
+    ];
+    let x = af::Array::new(&input, af::dim4!(2, 5));
+    let mut host_result = [0.0f32; 5];
+    testfunctions::holder_table(&x).host(&mut host_result);
+    for value in &host_result[..4] {
+        assert_float_eq!(*value, -19.2085, 1e-3);
+    }
+    assert_float_eq!(host_result[4], 0.0);
+    // Pre-calculated value
+    let x = af::Array::new(&[1.0f32, 2.0], af::dim4!(2));
+    assert_float_eq!(testfunctions::holder_table_flat(&x), -0.46716);
+}
+
+#[test]
+fn test_goldstein_price() {
+    // Test the global minimum and two pre-calculated values
+    let input = vec![0.0f32, -1.0, 0.0, 0.0, 1.0, 1.0];
+    let x = af::Array::new(&input, af::dim4!(2, 3));
+    let mut host_result = [0.0; 3];
+    testfunctions::goldstein_price(&x).host(&mut host_result);
+    assert_eq!(host_result, [3.0, 600.0, 1876.0]);
+    assert_float_eq!(
+        testfunctions::goldstein_price_flat(&af::Array::new(&[0.0f32, -1.0], af::dim4!(2))),
+        3.0
+    );
+}
+
+#[test]
+fn test_six_hump_camel() {
+    // Test both global minima and a pre-calculated value
+    let input = vec![0.0898f32, -0.7126, -0.0898, 0.7126, 1.0, 1.0];
+    let x = af::Array::new(&input, af::dim4!(2, 3));
+    let mut host_result = [0.0; 3];
+    testfunctions::six_hump_camel(&x).host(&mut host_result);
+    assert_float_eq!(host_result[0], -1.031628f32);
+    assert_float_eq!(host_result[1], -1.031628f32);
+    assert_float_eq!(host_result[2], 3.233333f32);
+}
+
+#[test]
+fn test_schwefel_problems() {
+    // Test the global minimum at x = 0 and the point [1, -2, 3] for all three problems
+    let input = vec![0.0f32, 0.0, 0.0, 1.0, -2.0, 3.0];
+    let x = af::Array::new(&input, af::dim4!(3, 2));
+    let mut host_result = [0.0, 0.0];
+
+    // 1² + (1 - 2)² + (1 - 2 + 3)²
+    testfunctions::schwefel_1_2(&x).host(&mut host_result);
+    assert_eq!(host_result, [0.0, 6.0]);
+    testfunctions::schwefel_2_21(&x).host(&mut host_result);
+    assert_eq!(host_result, [0.0, 3.0]);
+    // (1 + 2 + 3) + (1 * 2 * 3)
+    testfunctions::schwefel_2_22(&x).host(&mut host_result);
+    assert_eq!(host_result, [0.0, 12.0]);
+
+    let x = af::Array::new(&[-4.0f32, 0.5], af::dim4!(2));
+    assert_float_eq!(testfunctions::schwefel_1_2_flat(&x), 28.25);
+    assert_float_eq!(testfunctions::schwefel_2_21_flat(&x), 4.0);
+    assert_float_eq!(testfunctions::schwefel_2_22_flat(&x), 6.5);
+}
+
+#[test]
+fn test_transformed_moves_the_optimum() {
+    // Rastrigin shifted to [1, 2] and turned a quarter
+    let shift = af::Array::new(&[1.0f32, 2.0], af::dim4!(2));
+    let rotation = af::Array::new(&[0.0f32, 1.0, -1.0, 0.0], af::dim4!(2, 2));
+    let f = testfunctions::transformed(testfunctions::rastrigin, &shift, &rotation);
+
+    let input = vec![1.0f32, 2.0, 0.0, 0.0];
+    let x = af::Array::new(&input, af::dim4!(2, 2));
+    let mut host_result = [0.0, 0.0];
+    f(&x).host(&mut host_result);
+    assert_float_eq!(host_result[0], 0.0f32);
+    // The origin is at [-1, -2] from the optimum, which the rotation maps to [2, -1]
+    assert_float_eq!(host_result[1], 5.0f32);
+
+    // The ellipsoid's steep last axis becomes the first one
+    let f = testfunctions::transformed(
+        testfunctions::ellipsoid,
+        &af::constant(0.0f32, af::dim4!(2)),
+        &rotation,
+    );
+    let x = af::Array::new(&[1.0f32, 0.0], af::dim4!(2, 1));
+    assert_float_eq!(to_scalar(f(&x)) / 1e6, 1.0f32);
+}
+
+#[test]
+#[should_panic(expected = "rotation must be an n × n matrix")]
+fn test_transformed_rejects_mismatched_rotation() {
+    let shift = af::constant(0.0f32, af::dim4!(3));
+    let _ = testfunctions::transformed(
+        testfunctions::sphere,
+        &shift,
+        &af::identity(af::dim4!(2, 2)),
+    );
+}
+
+#[test]
+fn test_noisy_gaussian() {
+    // Noise around the minimum of the sphere has the requested standard deviation
+    let f = testfunctions::noisy(testfunctions::sphere, 0.5, testfunctions::Noise::Gaussian);
+    let x = af::constant(0.0f32, af::dim4!(3, 4000));
+    let values = f(&x);
+    let mean = af::sum_all(&values).0 / 4000.0;
+    let deviation = (af::sum_all(&(&values * &values)).0 / 4000.0 - mean * mean).sqrt();
+    assert_float_eq!(mean, 0.0, 0.05);
+    assert_float_eq!(deviation, 0.5, 0.05);
+}
+
+#[test]
+fn test_noisy_multiplicative_uniform() {
+    // Values stay within the relative half-width of the noiseless ones, and the minimum stays exact
+    let engine = af::RandomEngine::new(af::DEFAULT_RANDOM_ENGINE, Some(7));
+    let f = testfunctions::noisy_with(
+        testfunctions::sphere,
+        0.1,
+        testfunctions::Noise::MultiplicativeUniform,
+        &engine,
+    );
+    let x = af::constant(2.0f32, af::dim4!(1, 1000));
+    let values = f(&x);
+    assert!(af::min_all(&values).0 >= 3.6 && af::max_all(&values).0 <= 4.4);
+    assert_float_eq!(to_scalar(f(&af::constant(0.0f32, af::dim4!(2, 1)))), 0.0);
+}
+
+#[test]
+fn test_penalized1() {
+    // Test the global minimum at x = -1 and a point inside the bounds
+    let input = vec![-1.0f32, -1.0, -1.0, 0.0, 1.0, 2.0];
+    let x = af::Array::new(&input, af::dim4!(3, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::penalized1(&x).host(&mut host_result);
+    assert_float_eq!(host_result[0], 0.0f32);
+    // Pre-calculated value
+    assert_float_eq!(host_result[1], 8.115781f32, 1e-4);
+
+    // Leaving [-10, 10] by 1 costs 100 * 1^4 on top of the smooth part
+    let x = af::Array::new(&[11.0f32, 0.0], af::dim4!(2));
+    assert_float_eq!(testfunctions::penalized1_flat(&x), 184.92118, 1e-3);
+}
+
+#[test]
+fn test_penalized2() {
+    // Test the global minimum at x = 1 and a point inside the bounds
+    let input = vec![1.0f32, 1.0, 1.0, 0.0, 0.5, 2.0];
+    let x = af::Array::new(&input, af::dim4!(3, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::penalized2(&x).host(&mut host_result);
+    assert_float_eq!(host_result[0], 0.0f32);
+    // Pre-calculated value
+    assert_float_eq!(host_result[1], 0.325f32);
+
+    // Leaving [-5, 5] by 1 costs 100 * 1^4 on top of the smooth part
+    let x = af::Array::new(&[6.0f32, 0.0], af::dim4!(2));
+    assert_float_eq!(testfunctions::penalized2_flat(&x), 102.6, 1e-3);
+}
+
+#[test]
+fn test_rana() {
+    // Test the best known 2D minimum and the origin
+    let input = vec![-488.63258f32, 512.0, 0.0, 0.0];
+    let x = af::Array::new(&input, af::dim4!(2, 2));
+    let mut host_result = [0.0, 0.0];
+    testfunctions::rana(&x).host(&mut host_result);
+    assert_float_eq!(host_result[0], -511.7329f32, 1e-2);
+    assert_float_eq!(host_result[1], 0.454649f32);
+
+    // Pre-calculated value, with two pairs of coordinates
+    let x = af::Array::new(&[1.0f32, 2.0, 3.0], af::dim4!(3));
+    assert_float_eq!(testfunctions::rana_flat(&x), -1.108565);
+}
+
+#[test]
+fn test_whitley() {
+    // Test the global minimum at x = 1 and two pre-calculated values
+    let input = vec![1.0f32, 1.0, 0.0, 0.0, 0.5, 1.5];
+    let x = af::Array::new(&input, af::dim4!(2, 3));
+    let mut host_result = [0.0; 3];
+    testfunctions::whitley(&x).host(&mut host_result);
+    assert_float_eq!(host_result[0], 0.0f32);
+    assert_float_eq!(host_result[1], 1.839791f32);
+    assert_float_eq!(host_result[2], 31.41169f32, 1e-3);
+    assert_float_eq!(
+        testfunctions::whitley_flat(&af::constant(1.0f32, af::dim4!(4))),
+        0.0
+    );
+}
+
+#[test]
+fn test_double_precision_minima() {
+    // The f64 instantiations resolve the minima far below single-precision rounding
+    let zero = af::constant(0.0f64, af::dim4!(5));
+    assert_float_eq!(testfunctions::rastrigin_flat(&zero), 0.0, 1e-12);
+    assert_float_eq!(testfunctions::ackley_flat(&zero), 0.0, 1e-12);
+    assert_float_eq!(testfunctions::sphere_flat(&zero), 0.0, 1e-12);
+    assert_float_eq!(testfunctions::weierstrass_flat(&zero), 0.0, 1e-12);
+    let one = af::constant(1.0f64, af::dim4!(5));
+    assert_float_eq!(testfunctions::levy_flat(&one), 0.0, 1e-12);
+
+    // The value at the minimum (π, 2.275) is 10 / (8π)
+    let x = af::Array::new(&[std::f64::consts::PI, 2.275], af::dim4!(2));
+    assert_float_eq!(
+        testfunctions::branin_flat(&x),
+        10.0 / (8.0 * std::f64::consts::PI),
+        1e-12
+    );
+}
+
+#[test]
+fn test_double_precision_matches_single() {
+    fn compare(
+        single: fn(&af::Array<f32>) -> af::Array<f32>,
+        double: fn(&af::Array<f64>) -> af::Array<f64>,
+    ) {
+        let input = [0.3f32, -1.7, 2.2, 0.9, -0.4, 1.1];
+        let (mut expected, mut actual) = ([0.0f32; 2], [0.0f64; 2]);
+        single(&af::Array::new(&input, af::dim4!(3, 2))).host(&mut expected);
+        double(&af::Array::new(&input.map(f64::from), af::dim4!(3, 2))).host(&mut actual);
+        for (e, a) in expected.iter().zip(actual) {
+            assert_float_eq!(f64::from(*e), a, 1e-4);
+        }
+    }
+
+    compare(testfunctions::rastrigin, testfunctions::rastrigin);
+    compare(testfunctions::ackley, testfunctions::ackley);
+    compare(testfunctions::dixon_price, testfunctions::dixon_price);
+}
+
+/// Compare `grad` with central differences of `f` at every column of `points`.
+fn assert_gradient(
+    f: fn(&af::Array<f64>) -> af::Array<f64>,
+    grad: fn(&af::Array<f64>) -> af::Array<f64>,
+    points: &[f64],
+    n: u64,
+) {
+    const H: f64 = 1e-6;
+    let dims = af::dim4!(n, points.len() as u64 / n);
+    let mut analytic = vec![0.0; points.len()];
+    grad(&af::Array::new(points, dims)).host(&mut analytic);
+
+    for i in 0..points.len() {
+        let evaluate = |step: f64| {
+            let mut moved = points.to_vec();
+            moved[i] += step;
+            let mut values = vec![0.0; dims[1] as usize];
+            f(&af::Array::new(&moved, dims)).host(&mut values);
+            values[i / n as usize]
+        };
+        let numeric = (evaluate(H) - evaluate(-H)) / (2.0 * H);
+        assert!(
+            (analytic[i] - numeric).abs() <= 1e-5 * numeric.abs().max(1.0),
+            "component {i}: analytic {}, numeric {numeric}",
+            analytic[i]
+        );
+    }
+}
+
+#[test]
+fn test_gradients_match_finite_differences() {
+    use testfunctions::*;
+
+    let points = [0.3, -1.7, 2.2, 0.9, -0.4, 1.1, 0.05, 0.6];
+    assert_gradient(sphere, sphere_grad, &points, 4);
+    assert_gradient(rastrigin, rastrigin_grad, &points, 4);
+    assert_gradient(ackley, ackley_grad, &points, 4);
+    assert_gradient(schwefel, schwefel_grad, &points, 4);
+    assert_gradient(levy, levy_grad, &points, 4);
+    assert_gradient(levy, levy_grad, &points, 1);
+    assert_gradient(
+        sum_of_different_powers,
+        sum_of_different_powers_grad,
+        &points,
+        4,
+    );
+    assert_gradient(dixon_price, dixon_price_grad, &points, 4);
+    assert_gradient(trid, trid_grad, &points, 4);
+    assert_gradient(bent_cigar, bent_cigar_grad, &points, 4);
+    assert_gradient(discus, discus_grad, &points, 4);
+    assert_gradient(ellipsoid, ellipsoid_grad, &points, 4);
+    assert_gradient(schwefel_1_2, schwefel_1_2_grad, &points, 4);
+
+    assert_gradient(himmelblau, himmelblau_grad, &points, 2);
+    assert_gradient(booth, booth_grad, &points, 2);
+    assert_gradient(beale, beale_grad, &points, 2);
+    assert_gradient(branin, branin_grad, &points, 2);
+    assert_gradient(six_hump_camel, six_hump_camel_grad, &points, 2);
+}
+
+#[test]
+fn test_gradients_vanish_at_minima() {
+    let mut host_result = [1.0f32; 3];
+    testfunctions::ackley_grad(&af::constant(0.0f32, af::dim4!(3))).host(&mut host_result);
+    assert!(
+        host_result.iter().all(|g| g.abs() < 1e-5),
+        "{host_result:?}"
+    );
+    let mut host_single = [1.0f32; 1];
+    testfunctions::ackley_grad(&af::constant(0.0f32, af::dim4!(1))).host(&mut host_single);
+    assert_eq!(host_single, [0.0]);
+    testfunctions::levy_grad(&af::constant(1.0f32, af::dim4!(3))).host(&mut host_result);
+    assert!(
+        host_result.iter().all(|g| g.abs() < 1e-5),
+        "{host_result:?}"
+    );
+
+    // Himmelblau's four minima and the Booth minimum, one per column
+    let input = vec![
+        3.0, 2.0, -2.805118, 3.131312, -3.779310, -3.283186, 3.584428, -1.848126,
+    ];
+    let mut host_result = [1.0f64; 8];
+    testfunctions::himmelblau_grad(&af::Array::new(&input, af::dim4!(2, 4))).host(&mut host_result);
+    assert!(
+        host_result.iter().all(|g| g.abs() < 1e-4),
+        "{host_result:?}"
+    );
+    let x = af::Array::new(&[1.0f64, 3.0], af::dim4!(2));
+    let mut host_result = [1.0f64; 2];
+    testfunctions::booth_grad(&x).host(&mut host_result);
+    assert!(
+        host_result.iter().all(|g| g.abs() < 1e-12),
+        "{host_result:?}"
+    );
+}
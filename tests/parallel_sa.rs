@@ -1,6 +1,16 @@
 //! Unit tests for parallel simulated annealing.
+use std::cell::{Cell, RefCell};
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
 use arrayfire as af;
-use safire::{lsops::random_perturbation, parsa, testfunctions};
+use safire::lsops::{
+    random_bit_flip, random_perturbation, random_perturbation_with, AdaptiveMixture, Context,
+    LocalSearchOp, Mixture, Perturbation, Swap,
+};
+use safire::{parsa, testfunctions, Error};
 
 const TEST_SEED: u64 = 1737207124100;
 
@@ -31,8 +41,16 @@ fn test_minimize_rastrigin() {
         exponential_schedule(800.0, 0.8, 20),
     );
 
+    assert_eq!(result.termination, parsa::Termination::ScheduleExhausted);
+    assert_eq!(result.temperatures, 20);
+    assert_eq!(result.steps, 20 * 10);
+    assert_eq!(result.energies.dims(), af::dim4!(1, 800));
+    assert!(result.best_index < 800);
+    assert!(result.best_energy <= result.mean_energy);
+    assert!(result.best_energy < 2.0, "got {}", result.best_energy);
+
     let mut host_result = vec![0.0f32; 2 * 800];
-    result.host(&mut host_result);
+    result.states.host(&mut host_result);
     host_result.truncate(4);
 
     // Check if result is close to global minimum (0,0)
@@ -59,7 +77,7 @@ fn test_minimize_ackley() {
     );
 
     let mut host_result = vec![0.0f32; 3 * 100];
-    result.host(&mut host_result);
+    result.states.host(&mut host_result);
     host_result.truncate(6);
 
     // Check if result is close to global minimum (0,0,0)
@@ -86,7 +104,7 @@ fn test_minimize_schwefel_small() {
     );
 
     let mut host_result = vec![0.0f32; 2 * 100];
-    result.host(&mut host_result);
+    result.states.host(&mut host_result);
     host_result.truncate(4);
 
     // Check if result is close to global minimum (≈420.9687)
@@ -97,6 +115,1159 @@ fn test_minimize_schwefel_small() {
     );
 }
 
+#[test]
+fn test_maximize_rastrigin() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+
+    let result = parsa::maximize_numeric(
+        800,
+        10,
+        0.01,
+        &start,
+        |x| -testfunctions::rastrigin(x),
+        |x| random_perturbation(x, 0.4),
+        exponential_schedule(800.0, 0.8, 20),
+    );
+
+    let mut host_result = vec![0.0f32; 2 * 800];
+    result.states.host(&mut host_result);
+    host_result.truncate(4);
+
+    assert!(
+        host_result.iter().all(|&x| x.abs() < 0.1),
+        "Expected x_i = 0, got {host_result:?}"
+    );
+}
+
+#[test]
+fn test_minimize_noisy_rastrigin() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        resampling: Some(parsa::Resampling {
+            samples: 8,
+            reevaluate_interval: 5,
+        }),
+        ..parsa::Options::new(800, 10, 0.01)
+    };
+
+    let result = parsa::minimize_numeric_with(
+        &start,
+        |x| {
+            let e = testfunctions::rastrigin(x);
+            let noise = af::randn::<f32>(e.dims()) * 0.1f32;
+            e + noise
+        },
+        |x| random_perturbation(x, 0.4),
+        exponential_schedule(800.0, 0.8, 20),
+        &options,
+    );
+
+    let mut host_result = vec![0.0f32; 2 * 800];
+    result.states.host(&mut host_result);
+    host_result.truncate(2);
+
+    assert!(
+        host_result.iter().all(|&x| x.abs() < 0.2),
+        "Expected x_i = 0, got {host_result:?}"
+    );
+}
+
+#[test]
+fn test_minimize_quench() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        quench_iterations: 50,
+        ..parsa::Options::new(100, 10, 0.01)
+    };
+
+    let result = parsa::minimize_numeric_with(
+        &start,
+        testfunctions::rastrigin,
+        |x| random_perturbation(x, 0.05),
+        exponential_schedule(800.0, 0.8, 20),
+        &options,
+    );
+
+    let mut host_result = vec![0.0f32; 2 * 100];
+    result.states.host(&mut host_result);
+    host_result.truncate(2);
+
+    assert!(
+        host_result.iter().all(|&x| x.abs() < 0.05),
+        "Expected x_i = 0, got {host_result:?}"
+    );
+}
+
+#[test]
+fn test_seeded_runs_reproducible() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let run = |seed| {
+        let engine = af::RandomEngine::new(af::DEFAULT_RANDOM_ENGINE, Some(seed));
+        let options = parsa::Options {
+            seed: Some(seed),
+            ..parsa::Options::new(50, 10, 0.01)
+        };
+        let result = parsa::minimize_numeric_with(
+            &start,
+            testfunctions::rastrigin,
+            |x| random_perturbation_with(x, 0.4, &engine),
+            exponential_schedule(800.0, 0.8, 10),
+            &options,
+        );
+        let mut host_result = vec![0.0f32; 2 * 50];
+        result.states.host(&mut host_result);
+        host_result
+    };
+
+    let first = run(TEST_SEED);
+    // Disturb the global generator between runs; seeded runs must not notice.
+    af::set_seed(TEST_SEED + 1);
+    let _ = af::randu::<f32>(af::dim4!(10));
+    assert_eq!(first, run(TEST_SEED));
+}
+
+#[test]
+fn test_best_so_far_is_kept() {
+    af::set_seed(TEST_SEED);
+
+    // Starting at the minimum, every proposal is uphill and almost surely accepted at this
+    // temperature, so only best-so-far tracking remembers the start.
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let result = parsa::minimize_numeric(
+        16,
+        5,
+        1.0,
+        &start,
+        |x| af::sum(&(x * x), 0),
+        |x| x + 1.0f32,
+        std::iter::once(1e6),
+    );
+
+    let mut best = vec![1.0f32; 2];
+    result.best_state.host(&mut best);
+    assert_eq!(best, vec![0.0, 0.0]);
+    assert_eq!(result.best_energy, 0.0);
+    assert!(result.mean_energy > 0.0);
+}
+
+#[test]
+fn test_migration_policies() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let run = |migration| {
+        let options = parsa::Options {
+            migration,
+            ..parsa::Options::new(32, 10, 0.01)
+        };
+        let result = parsa::minimize_numeric_with(
+            &start,
+            testfunctions::rastrigin,
+            |x| random_perturbation(x, 0.4),
+            exponential_schedule(800.0, 0.8, 5),
+            &options,
+        );
+        let mut states = vec![0.0f32; 2 * 32];
+        result.states.host(&mut states);
+        states
+    };
+
+    let collapsed = run(parsa::Migration::Collapse);
+    assert!(collapsed.chunks(2).all(|c| c == &collapsed[..2]));
+
+    let independent = run(parsa::Migration::Independent);
+    assert!(independent.chunks(2).any(|c| c != &independent[..2]));
+
+    for policy in [
+        parsa::Migration::TopK(4),
+        parsa::Migration::Tournament(3),
+        parsa::Migration::ReplaceWorst(0.25),
+        parsa::Migration::UniformCrossover(8),
+        parsa::Migration::SinglePointCrossover(8),
+    ] {
+        let states = run(policy);
+        assert!(states.iter().all(|x| x.is_finite()), "{policy:?}");
+    }
+}
+
+#[test]
+fn test_crossover_migration_reevaluates_children() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(4, 1));
+    for migration in [
+        parsa::Migration::UniformCrossover(8),
+        parsa::Migration::SinglePointCrossover(8),
+    ] {
+        let options = parsa::Options {
+            migration,
+            ..parsa::Options::new(32, 5, 0.1)
+        };
+        parsa::minimize_numeric_observed(
+            &start,
+            testfunctions::rastrigin,
+            |x| random_perturbation(x, 0.4),
+            exponential_schedule(10.0, 0.8, 5),
+            &options,
+            |progress| {
+                let fresh = testfunctions::rastrigin(progress.states);
+                let (error, _) = af::max_all(&af::abs(&(fresh - &*progress.energies)));
+                assert!(error < 1e-3, "{migration:?}");
+                ControlFlow::Continue(())
+            },
+        );
+    }
+}
+
+#[test]
+fn test_minimize_numeric_lazy() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let trace: Vec<_> = parsa::minimize_numeric_lazy(
+        200,
+        10,
+        0.01,
+        &start,
+        testfunctions::rastrigin,
+        |x| random_perturbation(x, 0.4),
+        exponential_schedule(800.0, 0.8, 20),
+    )
+    .take_while(|&(_, _, energy)| energy > 1e-3)
+    .collect();
+
+    assert!(trace.len() <= 20);
+    assert!(trace.windows(2).all(|w| w[1].2 <= w[0].2));
+    assert!(trace
+        .iter()
+        .all(|(_, state, _)| state.dims() == af::dim4!(2, 1)));
+}
+
+#[test]
+fn test_minimize_matrix_states() {
+    af::set_seed(TEST_SEED);
+
+    // Find a 2x2 matrix close to the identity, with the chains batched along dimension 3.
+    let target = af::identity::<f32>(af::dim4!(2, 2));
+    let start = af::constant(0.0f32, af::dim4!(2, 2));
+    let options = parsa::Options {
+        batch_dim: 3,
+        quench_iterations: 50,
+        ..parsa::Options::new(64, 10, 0.01)
+    };
+
+    let result = parsa::minimize_numeric_with(
+        &start,
+        |x| {
+            let diff = af::sub(x, &target, true);
+            af::sum(&af::sum(&(&diff * &diff), 0), 1)
+        },
+        |x| random_perturbation(x, 0.05),
+        exponential_schedule(10.0, 0.8, 20),
+        &options,
+    );
+
+    assert_eq!(result.states.dims(), af::dim4!(2, 2, 1, 64));
+    assert_eq!(result.energies.dims(), af::dim4!(1, 64));
+    assert_eq!(result.best_state.dims(), af::dim4!(2, 2));
+
+    let mut best = vec![0.0f32; 4];
+    result.best_state.host(&mut best);
+    let expected = [1.0, 0.0, 0.0, 1.0];
+    assert!(
+        best.iter().zip(expected).all(|(x, y)| (x - y).abs() < 0.1),
+        "Expected the identity, got {best:?}"
+    );
+}
+
+#[test]
+fn test_minimize_double_precision() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f64, af::dim4!(3, 1));
+    let result = parsa::minimize_numeric(
+        100,
+        10,
+        0.01,
+        &start,
+        |x| af::sum(&(x * x), 0),
+        |x| x + af::randn::<f64>(x.dims()) * 0.05,
+        (0..20).map(|i| 10.0 * 0.8f64.powi(i)),
+    );
+
+    let mut best = vec![1.0f64; 3];
+    result.best_state.host(&mut best);
+    assert!(
+        best.iter().all(|x| x.abs() < 0.1),
+        "Expected x_i = 0, got {best:?}"
+    );
+    assert!(result.best_energy <= result.mean_energy);
+}
+
+#[test]
+fn test_minimize_double_precision_rastrigin() {
+    af::set_seed(TEST_SEED);
+
+    // The same test function, instantiated for double-precision states and energies.
+    let start = af::constant(1.0f64, af::dim4!(2, 1));
+    let result = parsa::minimize_numeric(
+        800,
+        10,
+        0.01,
+        &start,
+        testfunctions::rastrigin,
+        |x| x + af::randn::<f64>(x.dims()) * 0.4,
+        (0..20).map(|i| 800.0 * 0.8f64.powi(i)),
+    );
+
+    assert!(result.best_energy < 2.0, "got {}", result.best_energy);
+    let mut recomputed = [0.0f64];
+    testfunctions::rastrigin(&result.best_state).host(&mut recomputed);
+    assert!((recomputed[0] - result.best_energy).abs() < 1e-9);
+}
+
+#[test]
+fn test_minimize_discrete_onemax() {
+    af::set_seed(TEST_SEED);
+
+    // Maximize the number of ones in a 16-bit string by minimizing the number of zeros.
+    let start = af::constant(0u32, af::dim4!(16, 1));
+    let options = parsa::Options::new(64, 20, 1.0);
+    let result = parsa::minimize_discrete(
+        &start,
+        |x: &af::Array<u32>| (16u32 - af::sum(x, 0)).cast::<f32>(),
+        random_bit_flip,
+        exponential_schedule(2.0, 0.7, 15),
+        &options,
+    );
+
+    let mut best = vec![0u32; 16];
+    result.best_state.host(&mut best);
+    assert_eq!(result.best_energy, 0.0);
+    assert!(best.iter().all(|&bit| bit == 1), "Got {best:?}");
+}
+
+#[test]
+fn test_minimize_replica_exchange() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(3.0f32, af::dim4!(2, 1));
+    let ladder: Vec<f32> = exponential_schedule(100.0, 0.5, 8).collect();
+    let options = parsa::Options::new(16, 10, 0.01);
+    let result = parsa::minimize_replica_exchange(
+        &start,
+        testfunctions::rastrigin,
+        |x| random_perturbation(x, 0.2),
+        &ladder,
+        50,
+        &options,
+    );
+
+    assert_eq!(result.states.dims(), af::dim4!(2, 8 * 16));
+    assert_eq!(result.temperatures, 50);
+    assert_eq!(result.steps, 500);
+    assert!(result.best_energy < 1.0, "Got {}", result.best_energy);
+}
+
+#[test]
+fn test_per_chain_temperatures() {
+    af::set_seed(TEST_SEED);
+
+    // Half of the chains stay frozen at a negligible temperature, the other half stay hot.
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let temperatures = af::join(
+        1,
+        &af::constant(1e-6f32, af::dim4!(1, 8)),
+        &af::constant(1e6f32, af::dim4!(1, 8)),
+    );
+    let options = parsa::Options {
+        migration: parsa::Migration::Independent,
+        ..parsa::Options::new(16, 10, 1.0)
+    };
+    let result = parsa::minimize_numeric_with(
+        &start,
+        |x| af::sum(&(x * x), 0),
+        |x| x + 1.0f32,
+        std::iter::repeat_n(temperatures, 3),
+        &options,
+    );
+
+    let mut energies = vec![0.0f32; 16];
+    result.energies.host(&mut energies);
+    assert!(energies[..8].iter().all(|&e| e == 0.0), "{energies:?}");
+    assert!(energies[8..].iter().all(|&e| e > 0.0), "{energies:?}");
+}
+
+#[test]
+fn test_minimize_multi_device() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        seed: Some(TEST_SEED),
+        ..parsa::Options::new(301, 10, 0.01)
+    };
+    let result = parsa::minimize_numeric_multi_device(
+        &start,
+        testfunctions::rastrigin,
+        |x| random_perturbation(x, 0.4),
+        exponential_schedule(800.0, 0.8, 20),
+        &options,
+    );
+
+    assert_eq!(result.states.dims(), af::dim4!(2, 301));
+    assert_eq!(result.energies.dims(), af::dim4!(1, 301));
+    assert_eq!(result.temperatures, 20);
+    assert!(result.best_energy < 2.0, "Got {}", result.best_energy);
+}
+
+#[test]
+fn test_minimize_numeric_observed() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let mut temperatures_seen = Vec::new();
+    let result = parsa::minimize_numeric_observed(
+        &start,
+        testfunctions::rastrigin,
+        |x| random_perturbation(x, 0.4),
+        exponential_schedule(800.0, 0.8, 20),
+        &parsa::Options::new(50, 10, 0.01),
+        |progress| {
+            assert_eq!(progress.states.dims(), af::dim4!(2, 50));
+            assert_eq!(progress.energies.dims(), af::dim4!(1, 50));
+            temperatures_seen.push(*progress.temperature);
+            if progress.step == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+    );
+
+    assert_eq!(result.termination, parsa::Termination::Observer);
+    assert_eq!(result.temperatures, 3);
+    assert_eq!(temperatures_seen, vec![800.0, 640.0, 512.0]);
+}
+
+#[test]
+fn test_early_stopping() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let run = |convergence, target_energy| {
+        let options = parsa::Options {
+            convergence,
+            target_energy,
+            ..parsa::Options::new(100, 10, 0.01)
+        };
+        parsa::minimize_numeric_with(
+            &start,
+            testfunctions::rastrigin,
+            |x| random_perturbation(x, 0.05),
+            exponential_schedule(800.0, 0.8, 100),
+            &options,
+        )
+    };
+
+    // Chains collapse between temperatures, so their energies only spread during one.
+    let converged = run(Some(parsa::Spread::Range(1e-2)), None);
+    assert_eq!(converged.termination, parsa::Termination::Converged);
+    assert!(converged.temperatures < 100);
+
+    let reached = run(None, Some(1.0));
+    assert_eq!(reached.termination, parsa::Termination::TargetReached);
+    assert!(reached.best_energy <= 1.0);
+    assert!(reached.temperatures < 100);
+}
+
+#[test]
+fn test_target_energy_boundary() {
+    let start = af::constant(1.0f32, af::dim4!(1, 1));
+    // Without chain steps the best energy stays at the start's, exactly 1.
+    let run = |target_energy| {
+        let options = parsa::Options {
+            target_energy: Some(target_energy),
+            ..parsa::Options::new(4, 0, 0.01)
+        };
+        parsa::minimize_numeric_with(
+            &start,
+            testfunctions::sphere,
+            |x| random_perturbation(x, 0.05),
+            exponential_schedule(10.0, 0.5, 5),
+            &options,
+        )
+    };
+
+    let reached = run(1.0);
+    assert_eq!(reached.termination, parsa::Termination::TargetReached);
+    assert_eq!(reached.temperatures, 1);
+    let missed = run(0.999);
+    assert_eq!(missed.termination, parsa::Termination::ScheduleExhausted);
+    assert_eq!(missed.temperatures, 5);
+}
+
+#[test]
+fn test_heterogeneous_starts() {
+    let starts = [0.0f32, 0.0, 1.0, 1.0, 2.0, 2.0, 3.0, 3.0];
+    let start = af::Array::new(&starts, af::dim4!(2, 4));
+    let options = parsa::Options {
+        migration: parsa::Migration::Independent,
+        ..parsa::Options::new(4, 0, 0.01)
+    };
+
+    // Without any chain step, every chain keeps its own start.
+    let result = parsa::minimize_numeric_with(
+        &start,
+        |x| af::sum(&(x * x), 0),
+        |x| random_perturbation(x, 0.4),
+        std::iter::once(1.0),
+        &options,
+    );
+
+    let mut states = vec![0.0f32; 8];
+    result.states.host(&mut states);
+    assert_eq!(states, starts);
+    assert_eq!(result.best_index, 0);
+}
+
+#[test]
+fn test_eval_and_sync_intervals() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let run = |eval_interval, sync_interval| {
+        let engine = af::RandomEngine::new(af::DEFAULT_RANDOM_ENGINE, Some(TEST_SEED));
+        let options = parsa::Options {
+            seed: Some(TEST_SEED),
+            eval_interval,
+            sync_interval,
+            ..parsa::Options::new(50, 100, 0.01)
+        };
+        let result = parsa::minimize_numeric_with(
+            &start,
+            testfunctions::rastrigin,
+            |x| random_perturbation_with(x, 0.4, &engine),
+            exponential_schedule(800.0, 0.8, 5),
+            &options,
+        );
+        let mut states = vec![0.0f32; 2 * 50];
+        result.states.host(&mut states);
+        states
+    };
+
+    // Forcing evaluation only bounds the JIT trees; it must not change the outcome.
+    assert_eq!(run(0, 0), run(7, 5));
+}
+
+#[test]
+fn test_non_positive_temperatures() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let run = |non_positive_temperature| {
+        let options = parsa::Options {
+            non_positive_temperature,
+            ..parsa::Options::new(16, 5, 1.0)
+        };
+        parsa::minimize_numeric_with(
+            &start,
+            |x| af::sum(&(x * x), 0),
+            |x| x + 1.0f32,
+            [0.0f32, -1.0].into_iter(),
+            &options,
+        )
+    };
+
+    let terminated = run(parsa::NonPositiveTemperature::Terminate);
+    assert_eq!(terminated.termination, parsa::Termination::ZeroTemperature);
+    assert_eq!(terminated.temperatures, 0);
+
+    // Greedy chains reject every uphill move, instead of producing NaN log-probabilities.
+    let greedy = run(parsa::NonPositiveTemperature::Greedy);
+    assert_eq!(greedy.termination, parsa::Termination::ScheduleExhausted);
+    assert_eq!(greedy.temperatures, 2);
+    assert_eq!(greedy.mean_energy, 0.0);
+}
+
+#[test]
+fn test_archive() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let schedule = (0..10).map(|i| 10.0 * 0.7f32.powi(i));
+    let options = parsa::Options {
+        archive_size: 5,
+        ..parsa::Options::new(64, 10, 0.1)
+    };
+
+    let result = parsa::minimize_numeric_with(
+        &start,
+        testfunctions::rastrigin,
+        |x| random_perturbation(x, 0.5),
+        schedule,
+        &options,
+    );
+
+    let archive = result.archive.expect("archive was requested");
+    assert_eq!(archive.states.dims(), af::dim4!(2, 5));
+    assert_eq!(archive.energies.dims(), af::dim4!(1, 5));
+
+    let mut energies = vec![0.0f32; 5];
+    archive.energies.host(&mut energies);
+    assert!(energies.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(energies[0], result.best_energy);
+
+    let mut states = vec![0.0f32; 10];
+    archive.states.host(&mut states);
+    for (i, a) in states.chunks(2).enumerate() {
+        assert!(states.chunks(2).skip(i + 1).all(|b| a != b));
+    }
+}
+
+#[test]
+fn test_archive_deduplicates_interleaved_states() {
+    // Every chain improves to one of two states of equal energy, alternating along the batch,
+    // and the archive is larger than a single `af::topk` selection.
+    let flips: Vec<f32> = (0..400)
+        .flat_map(|chain| [if chain % 2 == 0 { 1.0 } else { -1.0 }, 0.0])
+        .collect();
+    let flips = af::Array::new(&flips, af::dim4!(2, 400));
+    let options = parsa::Options {
+        archive_size: 300,
+        migration: parsa::Migration::Independent,
+        ..parsa::Options::new(400, 1, 1.0)
+    };
+
+    let result = parsa::minimize_numeric_with(
+        &af::Array::new(&[2.0f32, 0.0], af::dim4!(2, 1)),
+        |x| af::sum(&(x * x), 0),
+        |_| flips.clone(),
+        [1.0f32].into_iter(),
+        &options,
+    );
+
+    let archive = result.archive.expect("archive was requested");
+    assert_eq!(archive.states.dims(), af::dim4!(2, 2));
+    let mut states = vec![0.0f32; 4];
+    archive.states.host(&mut states);
+    assert_ne!(states[..2], states[2..]);
+    let mut energies = vec![0.0f32; 2];
+    archive.energies.host(&mut energies);
+    assert_eq!(energies, [1.0; 2]);
+}
+
+#[test]
+fn test_scheduled_chain_lengths() {
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        chain_lengths: vec![1, 2, 3],
+        ..parsa::Options::new(8, 5, 1.0)
+    };
+
+    let result = parsa::minimize_numeric_with(
+        &start,
+        |x| af::sum(&(x * x), 0),
+        |x| random_perturbation(x, 0.1),
+        (0..4).map(|i| 1.0 / (i + 1) as f32),
+        &options,
+    );
+
+    assert_eq!(result.temperatures, 4);
+    assert_eq!(result.steps, 1 + 2 + 3 + 5);
+}
+
+#[test]
+fn test_per_chain_boltzmann_constants() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let chain_k: Vec<f32> = [1e-6; 4].into_iter().chain([1e6; 4]).collect();
+    let options = parsa::Options {
+        chain_k: Some(af::Array::new(&chain_k, af::dim4!(8))),
+        migration: parsa::Migration::Independent,
+        ..parsa::Options::new(8, 3, 1.0)
+    };
+
+    // Every proposal is uphill: cold chains reject them all, hot ones accept them all.
+    let result = parsa::minimize_numeric_with(
+        &start,
+        |x| af::sum(&(x * x), 0),
+        |x| x + 1.0f32,
+        [1.0f32].into_iter(),
+        &options,
+    );
+
+    let mut energies = vec![0.0f32; 8];
+    result.energies.host(&mut energies);
+    assert_eq!(energies[..4], [0.0; 4]);
+    assert_eq!(energies[4..], [18.0; 4]);
+}
+
+#[test]
+fn test_minimize_numeric_adaptive() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let last_scale = RefCell::new(None);
+    let adaptation = parsa::StepAdaptation::one_fifth(1.0);
+
+    // Every proposal is uphill and rejected by the cold chains, so their scales shrink.
+    let result = parsa::minimize_numeric_adaptive(
+        &start,
+        |x| af::sum(&(x * x), 0),
+        |x, scale| {
+            last_scale.replace(Some(scale.clone()));
+            af::add(x, scale, true)
+        },
+        [1e-6f32; 3].into_iter(),
+        &parsa::Options::new(4, 2, 1.0),
+        &adaptation,
+    );
+    assert_eq!(result.mean_energy, 0.0);
+
+    let scale = last_scale.into_inner().expect("neighbour was called");
+    assert_eq!(scale.dims(), af::dim4!(1, 4));
+    let mut scales = vec![0.0f32; 4];
+    scale.host(&mut scales);
+    for scale in scales {
+        assert!((scale - 1.0 / (1.22f32 * 1.22)).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn test_minimize_numeric_covariance() {
+    af::set_seed(TEST_SEED);
+
+    // Rosenbrock's banana-shaped valley, with its minimum of 0 at (1, 1).
+    let rosenbrock = |x: &af::Array<f32>| {
+        let a = af::row(x, 0);
+        let b = af::row(x, 1);
+        let valley = &b - &a * &a;
+        100.0f32 * &valley * &valley + (1.0f32 - &a) * (1.0f32 - &a)
+    };
+    let start = af::Array::new(&[-1.5f32, 2.0], af::dim4!(2, 1));
+    let schedule = (0..30).map(|i| 10.0 * 0.8f32.powi(i));
+    let options = parsa::Options {
+        seed: Some(TEST_SEED),
+        ..parsa::Options::new(64, 20, 0.1)
+    };
+
+    let result = parsa::minimize_numeric_covariance(
+        &start,
+        rosenbrock,
+        schedule,
+        &options,
+        &parsa::CovarianceAdaptation::new(0.1),
+    );
+
+    assert_eq!(result.termination, parsa::Termination::ScheduleExhausted);
+    assert_eq!(result.best_state.dims(), af::dim4!(2, 1));
+    assert!(result.best_energy < 0.5);
+}
+
+#[test]
+#[should_panic(expected = "column states batched along dimension 1")]
+fn test_minimize_numeric_covariance_rejects_matrix_states() {
+    let start = af::constant(0.0f32, af::dim4!(2, 1, 2));
+    parsa::minimize_numeric_covariance(
+        &start,
+        |x| af::sum(&af::sum(&(x * x), 0), 1),
+        [1.0f32].into_iter(),
+        &parsa::Options::new(4, 1, 1.0),
+        &parsa::CovarianceAdaptation::new(0.1),
+    );
+}
+
+#[test]
+fn test_minimize_numeric_chunked() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let widest = Cell::new(0);
+    let energy = |x: &af::Array<f32>| {
+        widest.set(widest.get().max(x.dims()[1]));
+        testfunctions::rastrigin(x)
+    };
+
+    let result = parsa::minimize_numeric_chunked(
+        &start,
+        energy,
+        |x| random_perturbation(x, 0.4),
+        exponential_schedule(800.0, 0.8, 10),
+        &parsa::Options::new(50, 10, 0.01),
+        16,
+    );
+
+    assert_eq!(widest.get(), 16);
+    assert_eq!(result.states.dims(), af::dim4!(2, 50));
+    assert_eq!(result.energies.dims(), af::dim4!(1, 50));
+    assert_eq!(result.temperatures, 10);
+    assert_eq!(result.steps, 100);
+    assert!(result.best_energy < 2.0);
+}
+
+#[test]
+fn test_minimize_numeric_chunked_per_chain_temperatures() {
+    af::set_seed(TEST_SEED);
+
+    // Tiles of 5 chains straddle the frozen and hot halves of the batch.
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let temperatures = af::join(
+        1,
+        &af::constant(1e-6f32, af::dim4!(1, 8)),
+        &af::constant(1e6f32, af::dim4!(1, 8)),
+    );
+    let options = parsa::Options {
+        migration: parsa::Migration::Independent,
+        ..parsa::Options::new(16, 10, 1.0)
+    };
+    let result = parsa::minimize_numeric_chunked(
+        &start,
+        |x| af::sum(&(x * x), 0),
+        |x| x + 1.0f32,
+        std::iter::repeat_n(temperatures, 3),
+        &options,
+        5,
+    );
+
+    let mut energies = vec![0.0f32; 16];
+    result.energies.host(&mut energies);
+    assert!(energies[..8].iter().all(|&e| e == 0.0), "{energies:?}");
+    assert!(energies[8..].iter().all(|&e| e > 0.0), "{energies:?}");
+}
+
+#[test]
+fn test_minimize_numeric_chunked_per_chain_boltzmann_constants() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let chain_k: Vec<f32> = [1e-6; 4].into_iter().chain([1e6; 4]).collect();
+    let options = parsa::Options {
+        chain_k: Some(af::Array::new(&chain_k, af::dim4!(8))),
+        migration: parsa::Migration::Independent,
+        ..parsa::Options::new(8, 3, 1.0)
+    };
+
+    // Every proposal is uphill: cold chains reject them all, hot ones accept them all.
+    let result = parsa::minimize_numeric_chunked(
+        &start,
+        |x| af::sum(&(x * x), 0),
+        |x| x + 1.0f32,
+        [1.0f32].into_iter(),
+        &options,
+        3,
+    );
+
+    let mut energies = vec![0.0f32; 8];
+    result.energies.host(&mut energies);
+    assert_eq!(energies[..4], [0.0; 4]);
+    assert_eq!(energies[4..], [18.0; 4]);
+}
+
+#[test]
+fn test_acceptance_statistics() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let run = |k| {
+        let mut observed = Vec::new();
+        let result = parsa::minimize_numeric_observed(
+            &start,
+            |x| af::sum(&(x * x), 0),
+            |x| x + 1.0f32,
+            [1.0f32, 0.5].into_iter(),
+            &parsa::Options {
+                migration: parsa::Migration::Independent,
+                ..parsa::Options::new(8, 3, k)
+            },
+            |progress| {
+                assert_eq!(progress.accepted.dims(), af::dim4!(1, 8));
+                let (accepted, _) = af::sum_all(progress.accepted);
+                observed.push((accepted, progress.proposed));
+                ControlFlow::Continue(())
+            },
+        );
+        (result.acceptances, observed)
+    };
+
+    // Every proposal is uphill: cold runs reject them all, hot ones accept them all.
+    let (cold, observed) = run(1e-6);
+    assert_eq!(cold, [0, 0]);
+    assert_eq!(observed, [(0, 3), (0, 3)]);
+    let (hot, observed) = run(1e6);
+    assert_eq!(hot, [24, 24]);
+    assert_eq!(observed, [(24, 3), (24, 3)]);
+}
+
+#[test]
+fn test_best_solution() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(3, 1));
+    let result = parsa::minimize_numeric(
+        64,
+        10,
+        0.01,
+        &start,
+        testfunctions::rastrigin,
+        |x| random_perturbation(x, 0.4),
+        exponential_schedule(800.0, 0.8, 10),
+    );
+
+    let (state, energy) = result.best();
+    assert_eq!(state.dims(), af::dim4!(3, 1));
+    assert_eq!(energy, result.best_energy);
+
+    let expected_energy = result.best_energy;
+    let (state, energy) = result.into_best();
+    let mut recomputed = [0.0f32];
+    testfunctions::rastrigin(&state).host(&mut recomputed);
+    assert_eq!(energy, expected_energy);
+    assert!((recomputed[0] - energy).abs() < 1e-4);
+}
+
+#[test]
+fn test_projection() {
+    af::set_seed(TEST_SEED);
+
+    // Unbounded below, so the chains would run away without the box constraint.
+    let start = af::constant(0.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        projection: Some(parsa::Projection::new(|x| {
+            af::clamp(x, &0.0f32, &0.5f32, false)
+        })),
+        ..parsa::Options::new(16, 10, 0.1)
+    };
+
+    let result = parsa::minimize_numeric_with(
+        &start,
+        |x| -af::sum(x, 0),
+        |x| random_perturbation(x, 1.0),
+        exponential_schedule(1.0, 0.5, 5),
+        &options,
+    );
+
+    let mut states = vec![0.0f32; 2 * 16];
+    result.states.host(&mut states);
+    assert!(states.iter().all(|x| (0.0..=0.5).contains(x)));
+    assert!(result.best_energy >= -1.0);
+}
+
+#[test]
+fn test_stateful_closures() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let engine = af::RandomEngine::new(af::DEFAULT_RANDOM_ENGINE, Some(TEST_SEED));
+    let mut evaluations = 0;
+    let mut proposals = 0;
+
+    parsa::minimize_numeric(
+        16,
+        5,
+        0.1,
+        &start,
+        |x| {
+            evaluations += 1;
+            testfunctions::rastrigin(x)
+        },
+        |x| {
+            proposals += 1;
+            random_perturbation_with(x, 0.4, &engine)
+        },
+        exponential_schedule(10.0, 0.5, 4),
+    );
+
+    assert_eq!(proposals, 20);
+    assert_eq!(evaluations, 21);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_minimize_numeric_stream() {
+    use futures::StreamExt;
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let stream = parsa::minimize_numeric_stream(
+        &start,
+        testfunctions::rastrigin,
+        |x| random_perturbation(x, 0.4),
+        exponential_schedule(10.0, 0.5, 5),
+        &parsa::Options::new(16, 5, 0.1),
+    );
+
+    let snapshots: Vec<_> = futures::executor::block_on(stream.collect());
+    assert_eq!(snapshots.len(), 5);
+    for (step, snapshot) in snapshots.iter().enumerate() {
+        assert_eq!(snapshot.step, step);
+        assert_eq!(snapshot.states.dims(), af::dim4!(2, 16));
+        assert!(snapshot.accepted <= 16 * 5);
+    }
+    assert!(snapshots
+        .windows(2)
+        .all(|pair| pair[1].best_energy <= pair[0].best_energy));
+}
+
+#[test]
+fn test_minimize_op_accepts_operators_and_closures() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        seed: Some(TEST_SEED),
+        ..parsa::Options::new(32, 10, 0.1)
+    };
+    let operators: Vec<Box<dyn LocalSearchOp<af::Array<f32>>>> = vec![
+        Box::new(Perturbation { scale: 0.4 }),
+        Box::new(|x: &af::Array<f32>| random_perturbation(x, 0.1)),
+    ];
+    for operator in operators {
+        let result = parsa::minimize_op(
+            &start,
+            testfunctions::rastrigin,
+            operator,
+            exponential_schedule(10.0, 0.7, 10),
+            &options,
+            Some(&parsa::StepAdaptation::one_fifth(1.0)),
+        );
+        let (_, best) = result.into_best();
+        // The Rastrigin function is 2 at the start (1, 1).
+        assert!(best < 2.0);
+    }
+}
+
+#[test]
+fn test_minimize_op_generic_temperatures() {
+    struct Recorder<'a>(&'a RefCell<Vec<Option<f32>>>);
+
+    impl LocalSearchOp<af::Array<f64>> for Recorder<'_> {
+        fn propose(&mut self, x: &af::Array<f64>, context: &mut Context<'_>) -> af::Array<f64> {
+            self.0.borrow_mut().push(context.temperature);
+            x - 0.1f64
+        }
+    }
+
+    let start = af::constant(1.0f64, af::dim4!(2, 1));
+    let energy = |x: &af::Array<f64>| af::sum(&(x * x), 0);
+    let options = parsa::Options {
+        seed: Some(TEST_SEED),
+        ..parsa::Options::new(4, 2, 0.1f64)
+    };
+
+    let temperatures = RefCell::new(Vec::new());
+    let result = parsa::minimize_op(
+        &start,
+        energy,
+        Recorder(&temperatures),
+        [4.0f64, 2.0].into_iter(),
+        &options,
+        None,
+    );
+    assert!(result.best_energy < 2.0);
+    assert_eq!(
+        temperatures.take(),
+        [Some(4.0), Some(4.0), Some(2.0), Some(2.0)]
+    );
+
+    // Per-chain temperatures have no single value to report.
+    let per_chain = af::Array::new(&[4.0f64, 3.0, 2.0, 1.0], af::dim4!(1, 4));
+    parsa::minimize_op(
+        &start,
+        energy,
+        Recorder(&temperatures),
+        std::iter::once(per_chain),
+        &options,
+        None,
+    );
+    assert_eq!(temperatures.take(), [None, None]);
+}
+
+#[test]
+fn test_minimize_op_adapts_mixture() {
+    // Only moving left lowers the energy, so the mixture should learn to prefer it.
+    let picks = RefCell::new(Vec::new());
+    let mixture = Mixture::new()
+        .with(1.0, |x: &af::Array<f32>| {
+            picks.borrow_mut().push(0);
+            x + 1.0f32
+        })
+        .with(1.0, |x: &af::Array<f32>| {
+            picks.borrow_mut().push(1);
+            x - 1.0f32
+        });
+    let options = parsa::Options {
+        seed: Some(TEST_SEED),
+        ..parsa::Options::new(8, 200, 1.0)
+    };
+    parsa::minimize_op(
+        &af::constant(0.0f32, af::dim4!(2, 1)),
+        |x: &af::Array<f32>| af::sum(x, 0),
+        AdaptiveMixture::new(mixture, 0.2, 0.05),
+        std::iter::once(1e-3),
+        &options,
+        None,
+    );
+
+    let picks = picks.into_inner();
+    assert_eq!(picks.len(), 200);
+    let late = picks[100..].iter().filter(|&&pick| pick == 1).count();
+    assert!(
+        late > 80,
+        "picked the productive operator {late} times out of 100"
+    );
+}
+
+#[test]
+fn test_minimize_op_discrete_operator() {
+    let start = af::Array::new(&[3u32, 1, 0, 2], af::dim4!(4));
+    let displacement = |x: &af::Array<u32>| {
+        let target = af::range::<u32>(x.dims(), 0).cast::<f32>();
+        af::sum(&af::abs(&(x.cast::<f32>() - target)), 0)
+    };
+    let result = parsa::minimize_op(
+        &start,
+        displacement,
+        Swap,
+        exponential_schedule(2.0, 0.8, 20),
+        &parsa::Options::new(16, 10, 1.0),
+        None,
+    );
+    let (_, best) = result.into_best();
+    assert_eq!(best, 0.0);
+}
+
+/// Operator that keeps every state and records the temperature of each proposal.
+struct TemperatureLog<'a>(&'a mut Vec<Option<f32>>);
+
+impl LocalSearchOp<af::Array<f32>> for TemperatureLog<'_> {
+    fn propose(&mut self, x: &af::Array<f32>, context: &mut Context<'_>) -> af::Array<f32> {
+        self.0.push(context.temperature);
+        x.clone()
+    }
+}
+
+#[test]
+fn test_minimize_op_quenches_at_zero_temperature() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        quench_iterations: 2,
+        ..parsa::Options::new(4, 2, 1.0)
+    };
+    let mut temperatures = Vec::new();
+    parsa::minimize_op(
+        &start,
+        testfunctions::sphere,
+        TemperatureLog(&mut temperatures),
+        [4.0, 2.0].into_iter(),
+        &options,
+        None,
+    );
+
+    let expected = [4.0, 4.0, 2.0, 2.0, 0.0, 0.0].map(Some);
+    assert_eq!(temperatures, expected);
+}
+
 #[test]
 #[should_panic(expected = "Boltzmann constant must be positive")]
 fn test_invalid_boltzmann_constant() {
@@ -113,3 +1284,169 @@ fn test_invalid_boltzmann_constant() {
         exponential_schedule(1000.0, 0.8, 20),
     );
 }
+
+#[test]
+#[should_panic(expected = "does not support per-chain Boltzmann constants")]
+fn test_replica_exchange_rejects_chain_k() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        chain_k: Some(af::constant(1.0f32, af::dim4!(8))),
+        ..parsa::Options::new(4, 10, 1.0)
+    };
+    parsa::minimize_replica_exchange(
+        &start,
+        testfunctions::sphere,
+        |x| random_perturbation(x, 0.1),
+        &[1.0, 2.0],
+        2,
+        &options,
+    );
+}
+
+#[test]
+#[should_panic(expected = "does not support per-chain Boltzmann constants")]
+fn test_multi_device_rejects_chain_k() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        chain_k: Some(af::constant(1.0f32, af::dim4!(4))),
+        ..parsa::Options::new(4, 10, 1.0)
+    };
+    parsa::minimize_numeric_multi_device(
+        &start,
+        testfunctions::sphere,
+        |x| random_perturbation(x, 0.1),
+        exponential_schedule(1.0, 0.5, 2),
+        &options,
+    );
+}
+
+#[test]
+fn test_try_minimize_numeric_errors() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let neighbour = |x: &af::Array<f32>| random_perturbation(x, 0.2);
+
+    let result = parsa::try_minimize_numeric(
+        0,
+        10,
+        0.01,
+        &start,
+        testfunctions::ackley,
+        neighbour,
+        exponential_schedule(1000.0, 0.8, 20),
+    );
+    assert_eq!(result.unwrap_err(), Error::EmptyBatch);
+
+    let result = parsa::try_minimize_numeric(
+        10,
+        10,
+        -1.0,
+        &start,
+        testfunctions::ackley,
+        neighbour,
+        exponential_schedule(1000.0, 0.8, 20),
+    );
+    assert_eq!(result.unwrap_err(), Error::NonPositiveBoltzmann);
+
+    let result = parsa::try_minimize_numeric(
+        10,
+        10,
+        0.01,
+        &start,
+        testfunctions::ackley,
+        neighbour,
+        std::iter::empty::<f32>(),
+    );
+    assert_eq!(result.unwrap_err(), Error::EmptySchedule);
+
+    let nan_start = af::constant(f32::NAN, af::dim4!(2, 1));
+    let result = parsa::try_minimize_numeric(
+        10,
+        10,
+        0.01,
+        &nan_start,
+        testfunctions::ackley,
+        neighbour,
+        exponential_schedule(1000.0, 0.8, 20),
+    );
+    assert_eq!(result.unwrap_err(), Error::NanInitialEnergy);
+}
+
+#[test]
+fn test_try_minimize_numeric_evaluates_start_once() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let evaluations = Cell::new(0);
+    let result = parsa::try_minimize_numeric(
+        10,
+        5,
+        0.01,
+        &start,
+        |x: &af::Array<f32>| {
+            evaluations.set(evaluations.get() + 1);
+            testfunctions::ackley(x)
+        },
+        |x: &af::Array<f32>| random_perturbation(x, 0.2),
+        exponential_schedule(1000.0, 0.8, 3),
+    );
+
+    assert!(result.is_ok());
+    // The batched start, then one proposal per step.
+    assert_eq!(evaluations.get(), 1 + 3 * 5);
+}
+
+#[test]
+fn test_interruption_skips_migration() {
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let options = parsa::Options {
+        cancel: Some(Arc::new(AtomicBool::new(true))),
+        migration: parsa::Migration::UniformCrossover(2),
+        ..parsa::Options::new(10, 100, 0.01)
+    };
+    let evaluations = Cell::new(0);
+    parsa::minimize_numeric_with(
+        &start,
+        |x: &af::Array<f32>| {
+            evaluations.set(evaluations.get() + 1);
+            testfunctions::sphere(x)
+        },
+        |x| random_perturbation(x, 0.2),
+        exponential_schedule(1000.0, 0.8, 20),
+        &options,
+    );
+
+    // The start and the one proposal made before the cancellation is noticed.
+    assert_eq!(evaluations.get(), 2);
+}
+
+#[test]
+fn test_minimize_numeric_interrupted() {
+    af::set_seed(TEST_SEED);
+
+    let start = af::constant(1.0f32, af::dim4!(2, 1));
+    let neighbour = |x: &af::Array<f32>| random_perturbation(x, 0.2);
+
+    let options = parsa::Options {
+        cancel: Some(Arc::new(AtomicBool::new(true))),
+        ..parsa::Options::new(10, 100, 0.01)
+    };
+    let result = parsa::minimize_numeric_with(
+        &start,
+        testfunctions::ackley,
+        neighbour,
+        exponential_schedule(1000.0, 0.8, 20),
+        &options,
+    );
+    assert_eq!(result.states.dims(), af::dim4!(2, 10));
+
+    let options = parsa::Options {
+        time_limit: Some(Duration::ZERO),
+        ..parsa::Options::new(10, 100, 0.01)
+    };
+    let result = parsa::minimize_numeric_with(
+        &start,
+        testfunctions::ackley,
+        neighbour,
+        exponential_schedule(1000.0, 0.8, 20),
+        &options,
+    );
+    assert_eq!(result.states.dims(), af::dim4!(2, 10));
+}
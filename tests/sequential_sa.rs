@@ -1,6 +1,14 @@
 //! Unit tests for sequential simulated annealing.
+use std::cell::RefCell;
+use std::ops::ControlFlow;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
 use arrayfire as af;
-use safire::{lsops::random_perturbation, seqsa, testfunctions};
+use safire::lsops::{random_perturbation, AdaptiveMixture, AdjacentSwap, LocalSearchOp, Mixture};
+use safire::tinyrand::{Probability, Rand, Seeded, StdRand};
+use safire::{seqsa, testfunctions, Error};
 
 const TEST_SEED: u64 = 1737207124100;
 
@@ -39,7 +47,8 @@ fn test_minimize_rastrigin() {
         neighbour,
         exponential_schedule(1000.0, 0.8, 25),
         TEST_SEED,
-    );
+    )
+    .best_state;
 
     dbg!(energy(&result));
 
@@ -77,7 +86,8 @@ fn test_minimize_ackley() {
         neighbour,
         exponential_schedule(1000.0, 0.8, 20),
         TEST_SEED,
-    );
+    )
+    .best_state;
 
     let mut host_result = vec![0.0f32; 3];
     result.host(&mut host_result);
@@ -113,7 +123,8 @@ fn test_minimize_schwefel() {
         neighbour,
         exponential_schedule(1000.0, 0.8, 30),
         TEST_SEED,
-    );
+    )
+    .best_state;
 
     let mut host_result = vec![0.0f32; 2];
     result.host(&mut host_result);
@@ -125,3 +136,1204 @@ fn test_minimize_schwefel() {
         "Expected x_i = {EXPECTED}, got: {host_result:?}"
     );
 }
+
+#[test]
+fn test_run_result_statistics() {
+    // Simple 1D quadratic on host values; no arrayfire required.
+    let energy = |x: &f32| x * x;
+    let neighbour = |x: &f32| x - 0.1;
+
+    let result = seqsa::minimize(
+        10,
+        1.0,
+        5.0f32,
+        energy,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 4),
+        TEST_SEED,
+    );
+
+    assert_eq!(result.evaluations, 1 + 10 * 4);
+    assert_eq!(result.acceptances, vec![10; 4]);
+    assert_eq!(result.termination, seqsa::Termination::ScheduleExhausted);
+    assert!((result.best_state - 1.0).abs() < 1e-4);
+    assert!((result.best_energy - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_run_result_zero_temperature() {
+    let schedule = [1.0, 0.0, 1.0].into_iter();
+    let result = seqsa::minimize(5, 1.0, 0.0f32, |x| x * x, |x| x + 1.0, schedule, TEST_SEED);
+
+    assert_eq!(result.termination, seqsa::Termination::ZeroTemperature);
+    assert_eq!(result.acceptances.len(), 1);
+    assert_eq!(result.best_energy, 0.0);
+}
+
+#[test]
+fn test_non_positive_temperature_greedy() {
+    let options = seqsa::Options {
+        non_positive_temperature: seqsa::NonPositiveTemperature::Greedy,
+        ..seqsa::Options::new(5, 1.0)
+    };
+    let mut rng = StdRand::seed(TEST_SEED);
+    let result = seqsa::minimize_with(
+        0.0f32,
+        |x| x * x,
+        |x| x + 1.0,
+        [1.0, 0.0, -1.0].into_iter(),
+        &options,
+        &mut rng,
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.termination, seqsa::Termination::ScheduleExhausted);
+    assert_eq!(result.acceptances.len(), 3);
+    assert_eq!(result.acceptances[1..], [0, 0]);
+}
+
+#[test]
+fn test_scheduled_chain_lengths() {
+    let options = seqsa::Options {
+        chain_lengths: vec![1, 2, 3],
+        ..seqsa::Options::new(5, 1.0)
+    };
+    let mut proposed = Vec::new();
+    let result = seqsa::minimize_with(
+        5.0f32,
+        |x| x * x,
+        |x| x - 0.1,
+        exponential_schedule(10.0, 0.5, 4),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |progress| {
+            proposed.push(progress.proposed);
+            ControlFlow::Continue(())
+        },
+    );
+
+    assert_eq!(proposed, [1, 2, 3, 5]);
+    assert_eq!(result.evaluations, 1 + 1 + 2 + 3 + 5);
+}
+
+#[test]
+fn test_infinite_energies_are_rejected() {
+    // inf - inf is NaN, which must reject the move rather than yield an invalid probability.
+    let result = seqsa::minimize_with(
+        0.0f32,
+        |_| f32::INFINITY,
+        |x| x + 1.0,
+        [1.0, 1e-30].into_iter(),
+        &seqsa::Options::new(5, 1.0),
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.acceptances, [0, 0]);
+    assert_eq!(result.best_state, 0.0);
+}
+
+#[test]
+fn test_tempered_neighbour_sees_temperature() {
+    let mut seen = Vec::new();
+    let options = seqsa::Options {
+        quench_iterations: 1,
+        ..seqsa::Options::new(2, 0.5)
+    };
+    seqsa::minimize_tempered(
+        5.0f32,
+        |x| x * x,
+        |x: &f32, t: f32| {
+            seen.push(t);
+            x - 0.1
+        },
+        [8.0, 4.0].into_iter(),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(seen, [8.0, 8.0, 4.0, 4.0, 0.0]);
+}
+
+#[test]
+fn test_minimize_op_with_mixture() {
+    let mixture = Mixture::new()
+        .with(1.0, |x: &f32| x - 0.5)
+        .with(1.0, |x: &f32| x + 0.5);
+    let result = seqsa::minimize_op(
+        5.0f32,
+        |x| x * x,
+        mixture,
+        exponential_schedule(1.0, 0.8, 30),
+        &seqsa::Options::new(20, 1.0),
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.best_energy, 0.0);
+}
+
+#[test]
+fn test_minimize_op_adapts_mixture() {
+    // Only moving left lowers the energy, so the mixture should learn to prefer it.
+    let picks = RefCell::new(Vec::new());
+    let mixture = Mixture::new()
+        .with(1.0, |x: &f32| {
+            picks.borrow_mut().push(0);
+            x + 1.0
+        })
+        .with(1.0, |x: &f32| {
+            picks.borrow_mut().push(1);
+            x - 1.0
+        });
+    seqsa::minimize_op(
+        0.0f32,
+        |x| *x,
+        AdaptiveMixture::new(mixture, 0.2, 0.05),
+        std::iter::once(1e-3),
+        &seqsa::Options::new(400, 1.0),
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    let picks = picks.into_inner();
+    assert_eq!(picks.len(), 400);
+    let late = picks[200..].iter().filter(|&&pick| pick == 1).count();
+    assert!(
+        late > 160,
+        "picked the productive operator {late} times out of 200"
+    );
+}
+
+#[test]
+fn test_minimize_op_host_operator() {
+    let displacement = |x: &Vec<usize>| {
+        x.iter()
+            .enumerate()
+            .map(|(i, &v)| i.abs_diff(v) as f32)
+            .sum::<f32>()
+    };
+    let operator: Box<dyn LocalSearchOp<Vec<usize>>> = Box::new(AdjacentSwap);
+    let result = seqsa::minimize_op(
+        vec![1, 0, 3, 2, 5, 4],
+        displacement,
+        operator,
+        exponential_schedule(1.0, 0.8, 30),
+        &seqsa::Options::new(20, 1.0),
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.best_state, [0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn test_observer_early_stop() {
+    let mut temperatures_seen = Vec::new();
+    let result = seqsa::minimize_with(
+        5.0f32,
+        |x| x * x,
+        |x| x - 0.1,
+        exponential_schedule(10.0, 0.5, 10),
+        &seqsa::Options::new(10, 1.0),
+        &mut StdRand::seed(TEST_SEED),
+        |progress| {
+            assert_eq!(progress.iteration, None);
+            temperatures_seen.push(progress.temperature);
+            if progress.step == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+    );
+
+    assert_eq!(temperatures_seen, vec![10.0, 5.0, 2.5]);
+    assert_eq!(result.termination, seqsa::Termination::Observer);
+    assert_eq!(result.acceptances.len(), 3);
+}
+
+#[test]
+fn test_observer_every_iteration() {
+    let options = seqsa::Options {
+        observe_iterations: true,
+        ..seqsa::Options::new(4, 1.0)
+    };
+
+    let mut calls = 0;
+    let result = seqsa::minimize_with(
+        5.0f32,
+        |x| x * x,
+        |x| x - 0.1,
+        exponential_schedule(10.0, 0.5, 3),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| {
+            calls += 1;
+            ControlFlow::Continue(())
+        },
+    );
+
+    // One call per inner iteration plus one per temperature.
+    assert_eq!(calls, 3 * (4 + 1));
+    assert_eq!(result.termination, seqsa::Termination::ScheduleExhausted);
+}
+
+#[test]
+fn test_early_stopping_converged() {
+    // The neighbour never moves, so the best energy cannot improve.
+    let options = seqsa::Options {
+        early_stopping: Some(seqsa::EarlyStopping {
+            patience: 3,
+            tolerance: 1e-6,
+        }),
+        ..seqsa::Options::new(5, 1.0)
+    };
+
+    let result = seqsa::minimize_with(
+        1.0f32,
+        |x| x * x,
+        |x| *x,
+        exponential_schedule(10.0, 0.9, 50),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.termination, seqsa::Termination::Converged);
+    assert_eq!(result.acceptances.len(), 3);
+}
+
+#[test]
+fn test_early_stopping_target_energy() {
+    let options = seqsa::Options {
+        target_energy: Some(1.0),
+        ..seqsa::Options::new(10, 1.0)
+    };
+
+    let result = seqsa::minimize_with(
+        5.0f32,
+        |x| x * x,
+        |x| x - 0.5,
+        exponential_schedule(10.0, 0.9, 50),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.termination, seqsa::Termination::TargetReached);
+    // Reaching the target exactly is enough: 5.0 -> 1.0 takes eight improving moves.
+    assert_eq!(result.best_energy, 1.0);
+    assert_eq!(result.evaluations, 1 + 8);
+}
+
+#[test]
+fn test_cancellation_and_time_limit() {
+    let cancel = Arc::new(AtomicBool::new(true));
+    let options = seqsa::Options {
+        cancel: Some(Arc::clone(&cancel)),
+        ..seqsa::Options::new(100, 1.0)
+    };
+    let result = seqsa::minimize_with(
+        5.0f32,
+        |x| x * x,
+        |x| x - 0.1,
+        exponential_schedule(10.0, 0.5, 10),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+    assert_eq!(result.termination, seqsa::Termination::Cancelled);
+    assert_eq!(result.evaluations, 2);
+
+    let options = seqsa::Options {
+        time_limit: Some(Duration::ZERO),
+        ..seqsa::Options::new(100, 1.0)
+    };
+    let result = seqsa::minimize_with(
+        5.0f32,
+        |x| x * x,
+        |x| x - 0.1,
+        exponential_schedule(10.0, 0.5, 10),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+    assert_eq!(result.termination, seqsa::Termination::TimeLimit);
+    assert_eq!(result.acceptances.len(), 1);
+    assert!(result.best_energy <= 25.0);
+}
+
+#[test]
+fn test_minimize_multistart() {
+    let starts = [-8.0f32, -3.0, 2.0, 7.0];
+    let energy = |x: &f32| (x - 1.0).powi(2);
+    let neighbour = |x: &f32| x + 0.1;
+    let options = seqsa::Options::new(20, 1.0);
+
+    let sequential = seqsa::minimize_multistart(
+        starts,
+        energy,
+        neighbour,
+        || exponential_schedule(1.0, 0.5, 10),
+        &options,
+        TEST_SEED,
+        1,
+    );
+    let threaded = seqsa::minimize_multistart(
+        starts,
+        energy,
+        neighbour,
+        || exponential_schedule(1.0, 0.5, 10),
+        &options,
+        TEST_SEED,
+        3,
+    );
+
+    assert_eq!(sequential.runs.len(), 4);
+    assert_eq!(sequential.best_run, threaded.best_run);
+    for (a, b) in sequential.runs.iter().zip(&threaded.runs) {
+        assert_eq!(a.best_state, b.best_state);
+        assert_eq!(a.evaluations, b.evaluations);
+    }
+
+    let best = sequential.best();
+    assert!(sequential
+        .runs
+        .iter()
+        .all(|run| best.best_energy <= run.best_energy));
+    assert!(best.best_energy < 0.01, "got {}", best.best_energy);
+}
+
+#[test]
+fn test_maximize() {
+    let result = seqsa::maximize(
+        20,
+        1.0,
+        -2.0f32,
+        |x| 3.0 - (x - 1.0).powi(2),
+        |x| x + 0.1,
+        exponential_schedule(1.0, 0.5, 10),
+        TEST_SEED,
+    );
+
+    assert!(
+        (result.best_state - 1.0).abs() < 0.05,
+        "got {}",
+        result.best_state
+    );
+    assert!(
+        (result.best_energy - 3.0).abs() < 0.01,
+        "got {}",
+        result.best_energy
+    );
+}
+
+#[test]
+fn test_minimize_adaptive_scale() {
+    // A flat landscape accepts every proposal, so the scale keeps growing until clamped.
+    let mut scales = Vec::new();
+    let adaptation = seqsa::StepAdaptation {
+        max_scale: 1.3,
+        ..seqsa::StepAdaptation::new(1.0)
+    };
+    let result = seqsa::minimize_adaptive(
+        0.0f32,
+        |_| 0.0,
+        |x: &f32, scale| {
+            scales.push(scale);
+            x + scale
+        },
+        exponential_schedule(1.0, 0.5, 3),
+        &seqsa::Options::new(2, 1.0),
+        &adaptation,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(scales, vec![1.0, 1.0, 1.2, 1.2, 1.3, 1.3]);
+    assert_eq!(result.acceptances, vec![2, 2, 2]);
+}
+
+#[test]
+fn test_minimize_adaptive_scale_per_temperature() {
+    // Empty temperatures and the quench leave the scale alone.
+    let mut scales = Vec::new();
+    let adaptation = seqsa::StepAdaptation {
+        factor: 2.0,
+        ..seqsa::StepAdaptation::new(1.0)
+    };
+    let mut options = seqsa::Options::new(2, 1.0);
+    options.chain_lengths = vec![2, 0, 1];
+    options.quench_iterations = 2;
+    seqsa::minimize_adaptive(
+        0.0f32,
+        |_| 0.0,
+        |x: &f32, scale| {
+            scales.push(scale);
+            x + scale
+        },
+        exponential_schedule(1.0, 0.5, 4),
+        &options,
+        &adaptation,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(scales, vec![1.0, 1.0, 2.0, 4.0, 4.0, 8.0, 8.0]);
+}
+
+#[test]
+fn test_minimize_with_history() {
+    let (result, history) = seqsa::minimize_with_history(
+        10,
+        1.0,
+        5.0f32,
+        |x| x * x,
+        |x| x - 0.1,
+        exponential_schedule(10.0, 0.5, 4),
+        TEST_SEED,
+    );
+
+    let temperatures: Vec<f32> = history.iter().map(|&(t, _, _)| t).collect();
+    assert_eq!(temperatures, vec![10.0, 5.0, 2.5, 1.25]);
+    // Every proposal moves downhill, so all of them are accepted.
+    assert!(history.iter().all(|&(_, _, rate)| rate == 1.0));
+    assert!(history.windows(2).all(|w| w[1].1 <= w[0].1));
+    assert_eq!(history.last().unwrap().1, result.best_energy);
+}
+
+#[test]
+fn test_minimize_with_history_without_proposals() {
+    let (_, history) = seqsa::minimize_with_history(
+        0,
+        1.0,
+        5.0f32,
+        |x| x * x,
+        |x| x - 0.1,
+        exponential_schedule(10.0, 0.5, 3),
+        TEST_SEED,
+    );
+
+    assert_eq!(
+        history,
+        vec![(10.0, 25.0, 0.0), (5.0, 25.0, 0.0), (2.5, 25.0, 0.0)]
+    );
+}
+
+#[test]
+fn test_resume_from_checkpoint() {
+    let energy = |x: &f32| x * x;
+    // Deterministic but irregular neighbour, so that only the acceptance test is random.
+    let neighbour = |x: &f32| (x * 7.3 + 1.1).sin() * 5.0;
+    let options = seqsa::Options::new(10, 1.0);
+
+    let mut checkpoints = Vec::new();
+    let full = seqsa::minimize_resumable(
+        4.0f32,
+        energy,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 8),
+        &options,
+        TEST_SEED,
+        |checkpoint| checkpoints.push(checkpoint.clone()),
+    );
+    assert_eq!(checkpoints.len(), 8);
+
+    let checkpoint = checkpoints[2].clone();
+    assert_eq!(checkpoint.step(), 3);
+    let resumed = seqsa::resume_from(
+        checkpoint,
+        energy,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 8),
+        &options,
+        |_| {},
+    );
+
+    assert_eq!(resumed.best_state, full.best_state);
+    assert_eq!(resumed.best_energy, full.best_energy);
+    assert_eq!(resumed.evaluations, full.evaluations);
+    assert_eq!(resumed.acceptances, full.acceptances);
+}
+
+#[test]
+fn test_resume_from_checkpoint_with_counters() {
+    let energy = |x: &f32| x * x;
+    let neighbour = |x: &f32| (x * 7.3 + 1.1).sin() * 5.0;
+    // Intervals that straddle temperatures, so the counters are mid-way at every checkpoint.
+    let options = seqsa::Options {
+        restart: Some(seqsa::Restart {
+            patience: 7,
+            temperature_bump: 2.0,
+        }),
+        early_stopping: Some(seqsa::EarlyStopping {
+            patience: 3,
+            tolerance: 1e-3,
+        }),
+        resampling: Some(seqsa::Resampling {
+            samples: 2,
+            reevaluate_interval: 4,
+        }),
+        ..seqsa::Options::new(10, 1.0)
+    };
+
+    let mut checkpoints = Vec::new();
+    let full = seqsa::minimize_resumable(
+        4.0f32,
+        energy,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 8),
+        &options,
+        TEST_SEED,
+        |checkpoint| checkpoints.push(checkpoint.clone()),
+    );
+
+    // The last checkpoint precedes the decision to stop, so a resumed run would carry on.
+    checkpoints.pop();
+    assert!(!checkpoints.is_empty());
+    for checkpoint in checkpoints {
+        let resumed = seqsa::resume_from(
+            checkpoint,
+            energy,
+            neighbour,
+            exponential_schedule(10.0, 0.5, 8),
+            &options,
+            |_| {},
+        );
+
+        assert_eq!(resumed.best_state, full.best_state);
+        assert_eq!(resumed.evaluations, full.evaluations);
+        assert_eq!(resumed.acceptances, full.acceptances);
+        assert_eq!(resumed.restarts, full.restarts);
+        assert_eq!(resumed.termination, full.termination);
+    }
+}
+
+#[test]
+fn test_minimize_resumable_resamples_start() {
+    let options = seqsa::Options {
+        resampling: Some(seqsa::Resampling {
+            samples: 3,
+            reevaluate_interval: 0,
+        }),
+        ..seqsa::Options::new(10, 1.0)
+    };
+    let mut calls = 0;
+    let result = seqsa::minimize_resumable(
+        1.0f32,
+        |x| {
+            calls += 1;
+            x + calls as f32
+        },
+        |x| *x,
+        std::iter::empty(),
+        &options,
+        TEST_SEED,
+        |_| {},
+    );
+
+    // Mean of 2, 3 and 4.
+    assert_eq!(result.best_energy, 3.0);
+    assert_eq!(result.evaluations, 3);
+}
+
+#[test]
+fn test_asa_minimize_sphere() {
+    let bounds = [(-5.0, 5.0); 4];
+    let result = seqsa::asa_minimize(
+        vec![4.0f64, -3.0, 2.5, -4.5],
+        &bounds,
+        |x| x.iter().map(|xi| (xi - 1.0).powi(2)).sum(),
+        &seqsa::AsaOptions::new(20_000),
+        &mut StdRand::seed(TEST_SEED),
+    );
+
+    assert_eq!(result.termination, seqsa::Termination::BudgetExhausted);
+    assert!(result.evaluations >= 20_000);
+    assert!(result
+        .best_state
+        .iter()
+        .zip(&bounds)
+        .all(|(x, (lo, hi))| lo <= x && x <= hi));
+    assert!(result.best_energy < 1e-3, "got {}", result.best_energy);
+}
+
+#[test]
+fn test_restart_from_best() {
+    // Walking right only ever improves until x = 0; from then on the chain wanders uphill
+    // and is pulled back to the incumbent after `patience` iterations.
+    let mut visited = Vec::new();
+    let options = seqsa::Options {
+        restart: Some(seqsa::Restart {
+            patience: 5,
+            temperature_bump: 1.0,
+        }),
+        ..seqsa::Options::new(10, 1.0)
+    };
+    let result = seqsa::minimize_with(
+        -1.0f32,
+        |x| x.abs(),
+        |x| {
+            visited.push(*x);
+            x + 0.5
+        },
+        std::iter::repeat_n(100.0, 3),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.best_state, 0.0);
+    assert!(result.restarts > 0);
+    assert!(visited.iter().all(|&x| x <= 2.0), "got {visited:?}");
+}
+
+#[test]
+fn test_resampling_noisy_objective() {
+    let mut noise = StdRand::seed(TEST_SEED + 1);
+    let mut calls = 0;
+    let options = seqsa::Options {
+        resampling: Some(seqsa::Resampling {
+            samples: 4,
+            reevaluate_interval: 5,
+        }),
+        ..seqsa::Options::new(10, 1.0)
+    };
+    let result = seqsa::minimize_with(
+        3.0f64,
+        |x| {
+            calls += 1;
+            x * x + noise.next_u64() as f64 / u64::MAX as f64 - 0.5
+        },
+        |x| x - 0.25,
+        exponential_schedule(1.0, 0.5, 3).map(f64::from),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    // Start, 30 proposals and 6 re-evaluations of the current state, 4 samples each.
+    assert_eq!(result.evaluations, 4 * (1 + 30 + 6));
+    assert_eq!(calls, result.evaluations);
+}
+
+#[test]
+fn test_evaluation_budget() {
+    let options = seqsa::Options {
+        max_evaluations: Some(25),
+        ..seqsa::Options::new(10, 1.0)
+    };
+    let result = seqsa::minimize_with(
+        5.0f32,
+        |x| x * x,
+        |x| x - 0.1,
+        exponential_schedule(10.0, 0.5, 10),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.termination, seqsa::Termination::BudgetExhausted);
+    assert_eq!(result.evaluations, 25);
+    assert_eq!(result.acceptances, vec![10, 10, 4]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_minimize_par() {
+    let neighbour = |x: &f32| (x * 7.3 + 1.1).sin() * 5.0 + x * 0.1;
+    let options = seqsa::Options::new(50, 1.0);
+
+    let independent = seqsa::minimize_par(
+        8,
+        4.0f32,
+        |x| x * x,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 10),
+        &options,
+        TEST_SEED,
+        0,
+    );
+    assert_eq!(independent.runs.len(), 8);
+    assert!(independent
+        .runs
+        .iter()
+        .all(|run| run.acceptances.len() == 10 && run.evaluations == 1 + 10 * 50));
+    assert!(independent
+        .runs
+        .iter()
+        .all(|run| independent.best().best_energy <= run.best_energy));
+
+    let exchanging = seqsa::minimize_par(
+        8,
+        4.0f32,
+        |x| x * x,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 10),
+        &options,
+        TEST_SEED,
+        2,
+    );
+    assert!(exchanging.best().best_energy < 16.0);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_minimize_par_stopping_options() {
+    let neighbour = |x: &f32| (x * 7.3 + 1.1).sin() * 5.0 + x * 0.1;
+    let options = seqsa::Options {
+        max_evaluations: Some(120),
+        quench_iterations: 5,
+        ..seqsa::Options::new(50, 1.0)
+    };
+    let budgeted = seqsa::minimize_par(
+        4,
+        4.0f32,
+        |x| x * x,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 10),
+        &options,
+        TEST_SEED,
+        2,
+    );
+    // The budget runs out during the third temperature, so there is no quench either.
+    assert!(budgeted.runs.iter().all(|run| {
+        run.termination == seqsa::Termination::BudgetExhausted
+            && run.evaluations == 120
+            && run.acceptances.len() == 3
+    }));
+
+    let options = seqsa::Options {
+        quench_iterations: 5,
+        ..seqsa::Options::new(50, 1.0)
+    };
+    let quenched = seqsa::minimize_par(
+        4,
+        4.0f32,
+        |x| x * x,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 2),
+        &options,
+        TEST_SEED,
+        0,
+    );
+    assert!(quenched.runs.iter().all(|run| {
+        run.termination == seqsa::Termination::ScheduleExhausted
+            && run.evaluations == 1 + 2 * 50 + 5
+            && run.acceptances.len() == 3
+    }));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_minimize_async() {
+    let neighbour = |x: &f32| (x * 7.3 + 1.1).sin() * 5.0 + x * 0.1;
+    let options = seqsa::Options::new(20, 1.0);
+    let energy = |x: &f32| {
+        let x = *x;
+        async move { x * x }
+    };
+
+    let sequential = seqsa::minimize_with(
+        4.0f32,
+        |x| x * x,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 8),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+    let single = futures::executor::block_on(seqsa::minimize_async(
+        4.0f32,
+        energy,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 8),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        1,
+    ));
+    assert_eq!(single.best_state, sequential.best_state);
+    assert_eq!(single.acceptances, sequential.acceptances);
+    assert_eq!(single.evaluations, sequential.evaluations);
+
+    let speculative = futures::executor::block_on(seqsa::minimize_async(
+        4.0f32,
+        energy,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 8),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        4,
+    ));
+    assert_eq!(speculative.acceptances.len(), 8);
+    assert!(speculative.evaluations >= sequential.evaluations);
+    assert!(speculative.best_energy < 16.0);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_minimize_async_matches_options() {
+    let neighbour = |x: &f32| (x * 7.3 + 1.1).sin() * 5.0 + x * 0.1;
+    let options = seqsa::Options {
+        restart: Some(seqsa::Restart {
+            patience: 7,
+            temperature_bump: 2.0,
+        }),
+        early_stopping: Some(seqsa::EarlyStopping {
+            patience: 3,
+            tolerance: 1e-3,
+        }),
+        resampling: Some(seqsa::Resampling {
+            samples: 2,
+            reevaluate_interval: 4,
+        }),
+        quench_iterations: 5,
+        ..seqsa::Options::new(20, 1.0)
+    };
+
+    let sequential = seqsa::minimize_with(
+        4.0f32,
+        |x| x * x,
+        neighbour,
+        exponential_schedule(10.0, 0.5, 8),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+    let single = futures::executor::block_on(seqsa::minimize_async(
+        4.0f32,
+        |x: &f32| {
+            let x = *x;
+            async move { x * x }
+        },
+        neighbour,
+        exponential_schedule(10.0, 0.5, 8),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        1,
+    ));
+    assert_eq!(single.best_state, sequential.best_state);
+    assert_eq!(single.acceptances, sequential.acceptances);
+    assert_eq!(single.evaluations, sequential.evaluations);
+    assert_eq!(single.restarts, sequential.restarts);
+    assert_eq!(single.termination, sequential.termination);
+}
+
+#[test]
+fn test_quench_phase() {
+    let options = seqsa::Options {
+        quench_iterations: 20,
+        ..seqsa::Options::new(1, 1.0)
+    };
+    let result = seqsa::minimize_with(
+        5.0f32,
+        |x| (x - 1.0).abs(),
+        |x| if *x > 1.0 { x - 0.25 } else { x + 0.5 },
+        exponential_schedule(1.0, 0.5, 2),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    // The quench walks down to x = 1 and then rejects every uphill proposal.
+    assert_eq!(result.best_state, 1.0);
+    assert_eq!(result.acceptances, vec![1, 1, 14]);
+    assert_eq!(result.evaluations, 1 + 2 + 20);
+}
+
+#[test]
+fn test_minimize_f64_energy() {
+    // Energies, temperatures and k all in double precision.
+    let result = seqsa::minimize(
+        10,
+        1.0f64,
+        3.0f64,
+        |x: &f64| (x - 1.0).powi(2),
+        |x: &f64| x - 0.25,
+        std::iter::successors(Some(10.0f64), |t| Some(t * 0.5)).take(5),
+        TEST_SEED,
+    );
+
+    assert_eq!(result.best_energy, 0.0f64);
+    assert_eq!(result.best_state, 1.0f64);
+}
+
+#[test]
+fn test_stateful_closures() {
+    // Closures that mutate captured state: an evaluation counter and a step sequence.
+    let mut calls = 0;
+    let mut step = 0.0f32;
+
+    let result = seqsa::minimize(
+        5,
+        1.0,
+        4.0f32,
+        |x| {
+            calls += 1;
+            x * x
+        },
+        |x| {
+            step += 0.1;
+            x - step
+        },
+        exponential_schedule(10.0, 0.5, 2),
+        TEST_SEED,
+    );
+
+    assert_eq!(calls, result.evaluations);
+    assert!((step - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn test_try_minimize_errors() {
+    let energy = |x: &f32| x * x;
+    let neighbour = |x: &f32| x + 0.1;
+
+    let result = seqsa::try_minimize(10, 0.0, 1.0f32, energy, neighbour, [1.0].into_iter(), 0);
+    assert_eq!(result.unwrap_err(), Error::NonPositiveBoltzmann);
+
+    let result = seqsa::try_minimize(10, 1.0, 1.0f32, energy, neighbour, std::iter::empty(), 0);
+    assert_eq!(result.unwrap_err(), Error::EmptySchedule);
+
+    let result = seqsa::try_minimize(10, 1.0, f32::NAN, energy, neighbour, [1.0].into_iter(), 0);
+    assert_eq!(result.unwrap_err(), Error::NanInitialEnergy);
+
+    let result = seqsa::try_minimize(10, 1.0, 1.0f32, energy, neighbour, [1.0].into_iter(), 0);
+    assert!(result.is_ok());
+}
+
+/// Minimal linear congruential generator, standing in for a user-supplied RNG.
+struct Lcg(u64);
+
+impl Rand for Lcg {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0
+    }
+}
+
+#[test]
+fn test_custom_rng() {
+    let options = seqsa::Options::new(50, 1.0);
+    let mut rng = Lcg(TEST_SEED);
+
+    // One generator shared across two consecutive runs.
+    let first = seqsa::minimize_with(
+        3.0f32,
+        |x| x * x,
+        |x| x - 0.5,
+        exponential_schedule(1.0, 0.5, 4),
+        &options,
+        &mut rng,
+        |_| ControlFlow::Continue(()),
+    );
+    let second = seqsa::minimize_with(
+        first.best_state,
+        |x| x * x,
+        |x| x + 0.5,
+        exponential_schedule(1.0, 0.5, 4),
+        &options,
+        &mut rng,
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(first.best_energy, 0.0);
+    assert_eq!(second.best_energy, 0.0);
+}
+
+#[test]
+fn test_same_rng_state_reproducible() {
+    let run = |rng: &mut StdRand| {
+        seqsa::minimize_with(
+            3.0f32,
+            |x| x * x,
+            |x| x - 0.5,
+            exponential_schedule(1.0, 0.5, 4),
+            &seqsa::Options::new(20, 1.0),
+            rng,
+            |_| ControlFlow::Continue(()),
+        )
+    };
+
+    let a = run(&mut StdRand::seed(TEST_SEED));
+    let b = run(&mut StdRand::seed(TEST_SEED));
+    assert_eq!(a.best_state, b.best_state);
+    assert_eq!(a.acceptances, b.acceptances);
+}
+
+/// Swaps two positions of a sequence; its own inverse.
+struct Swap(usize, usize);
+
+impl seqsa::Move<Vec<u32>> for Swap {
+    fn apply(&self, state: &mut Vec<u32>) {
+        state.swap(self.0, self.1);
+    }
+
+    fn undo(&self, state: &mut Vec<u32>) {
+        state.swap(self.0, self.1);
+    }
+}
+
+fn inversions(x: &[u32]) -> f32 {
+    let mut count = 0;
+    for i in 0..x.len() {
+        for j in i + 1..x.len() {
+            if x[i] > x[j] {
+                count += 1;
+            }
+        }
+    }
+    count as f32
+}
+
+#[test]
+fn test_minimize_moves_sorts() {
+    let mut proposals = Lcg(TEST_SEED);
+    let result = seqsa::minimize_moves(
+        vec![5, 4, 3, 2, 1, 0],
+        |x: &Vec<u32>| inversions(x),
+        |x: &Vec<u32>| {
+            let i = proposals.next_lim_usize(x.len());
+            let j = proposals.next_lim_usize(x.len());
+            Swap(i, j)
+        },
+        exponential_schedule(1.0, 0.5, 10),
+        &seqsa::Options::new(200, 1.0),
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.best_energy, 0.0);
+    assert_eq!(result.best_state, vec![0, 1, 2, 3, 4, 5]);
+}
+
+/// Increments the first element.
+struct Bump;
+
+impl seqsa::Move<Vec<u32>> for Bump {
+    fn apply(&self, state: &mut Vec<u32>) {
+        state[0] += 1;
+    }
+
+    fn undo(&self, state: &mut Vec<u32>) {
+        state[0] -= 1;
+    }
+}
+
+#[test]
+fn test_minimize_moves_undo_rejected() {
+    // Every move is uphill and the temperature is tiny, so all proposals are undone.
+    let start = vec![0, 7, 3];
+    let result = seqsa::minimize_moves(
+        start.clone(),
+        |x: &Vec<u32>| x.iter().sum::<u32>() as f32,
+        |_: &Vec<u32>| Bump,
+        [1e-6f32; 3].into_iter(),
+        &seqsa::Options::new(10, 1.0),
+        &mut StdRand::seed(TEST_SEED),
+        |progress| {
+            assert_eq!(progress.state, &start);
+            ControlFlow::Continue(())
+        },
+    );
+
+    assert_eq!(result.acceptances, vec![0; 3]);
+    assert_eq!(result.best_state, start);
+}
+
+/// Adds a fixed offset to a scalar state.
+struct Step(f64);
+
+impl seqsa::Move<f64> for Step {
+    fn apply(&self, state: &mut f64) {
+        *state += self.0;
+    }
+
+    fn undo(&self, state: &mut f64) {
+        *state -= self.0;
+    }
+}
+
+#[test]
+fn test_minimize_delta() {
+    let energy = |x: &f64| (x - 2.0).powi(2);
+    let mut proposals = Lcg(TEST_SEED);
+
+    let result = seqsa::minimize_delta(
+        10.0f64,
+        energy,
+        |x: &f64, step: &Step| energy(&(x + step.0)) - energy(x),
+        |_: &f64| {
+            if proposals.next_bool(Probability::new(0.5)) {
+                Step(0.5)
+            } else {
+                Step(-0.5)
+            }
+        },
+        exponential_schedule(1.0, 0.5, 10).map(f64::from),
+        &seqsa::Options::new(100, 1.0),
+        &mut StdRand::seed(TEST_SEED),
+        |_| ControlFlow::Continue(()),
+    );
+
+    assert_eq!(result.best_state, 2.0);
+    assert_eq!(result.best_energy, energy(&result.best_state));
+}
+
+#[test]
+fn test_minimize_delta_recompute() {
+    // A biased delta function drifts away from the true energy; recomputing after every
+    // accepted move keeps the running energy exact.
+    let energy = |x: &f64| x.abs();
+    let options = seqsa::Options {
+        recompute_interval: 1,
+        ..seqsa::Options::new(20, 1.0)
+    };
+
+    seqsa::minimize_delta(
+        5.0f64,
+        energy,
+        |x: &f64, step: &Step| energy(&(x + step.0)) - energy(x) + 0.01,
+        |_: &f64| Step(-0.25),
+        exponential_schedule(1.0, 0.5, 5).map(f64::from),
+        &options,
+        &mut StdRand::seed(TEST_SEED),
+        |progress| {
+            assert_eq!(progress.energy, energy(progress.state));
+            ControlFlow::Continue(())
+        },
+    );
+}
+
+#[test]
+fn test_minimize_lazy_steps() {
+    let samples: Vec<_> = seqsa::minimize_lazy_steps(
+        3,
+        1.0,
+        5.0f32,
+        |x| x * x,
+        |x| x - 1.0,
+        [4.0, 2.0, 0.0, 1.0].into_iter(),
+        TEST_SEED,
+    )
+    .collect();
+
+    // Two positive temperatures of three iterations each; the zero temperature ends the run.
+    assert_eq!(samples.len(), 6);
+    let indices: Vec<_> = samples
+        .iter()
+        .map(|s| (s.temperature, s.iteration))
+        .collect();
+    assert_eq!(
+        indices,
+        vec![(4.0, 0), (4.0, 1), (4.0, 2), (2.0, 0), (2.0, 1), (2.0, 2)]
+    );
+    // Every move is downhill for the first five iterations.
+    assert_eq!(samples[4].state, 0.0);
+    assert!(samples.iter().all(|s| s.energy == s.state * s.state));
+}